@@ -33,6 +33,7 @@ struct ApiError {
 
 #[derive(Debug, Deserialize, ToSchema)]
 struct CreateStrategyRequest {
+    #[allow(dead_code)]
     pub account: String,
     pub strategy: StrategyData,
 }
@@ -57,6 +58,7 @@ struct StrategyResponse {
 #[derive(Debug, Deserialize, ToSchema)]
 struct ChatRequest {
     pub message: String,
+    #[allow(dead_code)]
     pub user_id: String,
     pub session_id: Option<String>,
 }
@@ -207,7 +209,7 @@ async fn save_strategy(
     )
 )]
 async fn get_strategies(
-    Path(account_id): Path<String>,
+    Path(_account_id): Path<String>,
 ) -> Json<ApiResponse<Vec<StrategyResponse>>> {
     // Mock response with sample strategies
     let strategies = vec![
@@ -0,0 +1,104 @@
+use std::str::FromStr;
+
+/// Address formats accepted at the API boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressChain {
+    /// SS58, as used by Polkadot and its parachains
+    Polkadot,
+    /// 20-byte hex with an EIP-55 checksum
+    Evm,
+}
+
+impl AddressChain {
+    /// EVM for `0x`-prefixed addresses, Polkadot otherwise; for fields that
+    /// take either.
+    pub fn infer(address: &str) -> Self {
+        if address.starts_with("0x") || address.starts_with("0X") {
+            AddressChain::Evm
+        } else {
+            AddressChain::Polkadot
+        }
+    }
+}
+
+/// Check that `address` is well-formed for `chain`.
+///
+/// SS58 addresses are decoded and their checksum verified. EVM addresses must
+/// be `0x` plus 40 hex digits; mixed-case ones must match their EIP-55
+/// checksum, while all-lowercase or all-uppercase ones carry no checksum and
+/// are accepted as-is.
+pub fn validate_address(address: &str, chain: AddressChain) -> Result<(), String> {
+    match chain {
+        AddressChain::Polkadot => subxt::utils::AccountId32::from_str(address)
+            .map(|_| ())
+            .map_err(|e| format!("Invalid SS58 address: {:?}", e)),
+        AddressChain::Evm => validate_evm_address(address),
+    }
+}
+
+fn validate_evm_address(address: &str) -> Result<(), String> {
+    let hex = address
+        .strip_prefix("0x")
+        .or_else(|| address.strip_prefix("0X"))
+        .ok_or_else(|| "EVM address must start with 0x".to_string())?;
+    if hex.len() != 40 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err("EVM address must be 0x followed by 40 hex digits".to_string());
+    }
+
+    let is_single_case = hex == hex.to_lowercase() || hex == hex.to_uppercase();
+    if is_single_case {
+        return Ok(());
+    }
+
+    let parsed = ethers::types::Address::from_str(hex).map_err(|e| format!("Invalid EVM address: {}", e))?;
+    if ethers::utils::to_checksum(&parsed, None)[2..] == *hex {
+        Ok(())
+    } else {
+        Err("EVM address does not match its EIP-55 checksum".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Alice's well-known development account
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+    const CHECKSUMMED: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+
+    #[test]
+    fn test_valid_ss58_address() {
+        assert!(validate_address(ALICE, AddressChain::Polkadot).is_ok());
+        assert_eq!(AddressChain::infer(ALICE), AddressChain::Polkadot);
+
+        // Last character changed, so the checksum no longer matches
+        let typo = format!("{}J", &ALICE[..ALICE.len() - 1]);
+        assert!(validate_address(&typo, AddressChain::Polkadot).is_err());
+    }
+
+    #[test]
+    fn test_valid_checksummed_evm_address() {
+        assert!(validate_address(CHECKSUMMED, AddressChain::Evm).is_ok());
+        assert!(validate_address(&CHECKSUMMED.to_lowercase(), AddressChain::Evm).is_ok());
+        assert_eq!(AddressChain::infer(CHECKSUMMED), AddressChain::Evm);
+
+        // `infer` takes `0X` as EVM too, so validation must accept it
+        let upper_prefix = CHECKSUMMED.replacen("0x", "0X", 1);
+        assert_eq!(AddressChain::infer(&upper_prefix), AddressChain::Evm);
+        assert!(validate_address(&upper_prefix, AddressChain::Evm).is_ok());
+
+        // One letter's case flipped breaks the checksum
+        let bad_checksum = CHECKSUMMED.replacen("aAeb", "aaeb", 1);
+        assert!(validate_address(&bad_checksum, AddressChain::Evm).is_err());
+    }
+
+    #[test]
+    fn test_invalid_address_strings() {
+        for address in ["", "alice", "0x1234", "0xZZZeb6053F3E94C9b9A09f33669435E7Ef1BeAed"] {
+            let chain = AddressChain::infer(address);
+            assert!(validate_address(address, chain).is_err(), "{} should be rejected", address);
+        }
+        assert!(validate_address(CHECKSUMMED, AddressChain::Polkadot).is_err());
+        assert!(validate_address(ALICE, AddressChain::Evm).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::collections::BTreeMap;
+use tracing::warn;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Keywords that identify the contract type a free-text question is about
+const QUESTION_KEYWORDS: &[(&str, &[&str])] = &[
+    ("ERC20", &["erc20", "erc-20", "fungible token"]),
+    ("ERC721", &["erc721", "erc-721", "nft"]),
+    ("ERC1155", &["erc1155", "erc-1155", "multi-token"]),
+    ("MultiSig", &["multisig", "multi-sig", "multisignature"]),
+    ("Escrow", &["escrow"]),
+    ("Flipper", &["flipper"]),
+];
+
+/// One `/ask` or conversion request. Holds no request text or account data,
+/// only what's needed for aggregate counts.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct AnalyticsEvent {
+    pub id: Uuid,
+    pub endpoint: String,
+    pub contract_type: Option<String>,
+    pub solidity_version: Option<String>,
+    pub success: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AnalyticsEvent {
+    pub fn new(endpoint: &str, contract_type: Option<&str>, solidity_version: Option<String>, success: bool) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            endpoint: endpoint.to_string(),
+            contract_type: contract_type.map(str::to_string),
+            solidity_version,
+            success,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Event for a question, tagged with the contract type it mentions.
+    pub fn for_question(endpoint: &str, question: &str, success: bool) -> Self {
+        Self::new(endpoint, question_contract_type(question), solidity_version(question), success)
+    }
+}
+
+/// The contract type a free-text question is about, if it names one.
+pub fn question_contract_type(question: &str) -> Option<&'static str> {
+    let question_lower = question.to_lowercase();
+    QUESTION_KEYWORDS
+        .iter()
+        .find(|(_, keywords)| keywords.iter().any(|k| question_lower.contains(k)))
+        .map(|(contract_type, _)| *contract_type)
+}
+
+/// The version constraint from `pragma solidity ...;`, e.g. `^0.8.20`.
+pub fn solidity_version(source: &str) -> Option<String> {
+    let pragma_re = Regex::new(r"pragma\s+solidity\s+([^;]+);").ok()?;
+    pragma_re.captures(source).map(|c| c[1].trim().to_string())
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct ContractTypeCounts {
+    pub total: i64,
+    pub succeeded: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct AnalyticsSummary {
+    pub total: i64,
+    /// Fraction of requests that succeeded, 0.0 when there are none
+    pub success_rate: f64,
+    /// Counts keyed by contract type; requests with none detected are under "unknown"
+    pub by_contract_type: BTreeMap<String, ContractTypeCounts>,
+}
+
+/// Usage events in the `analytics_events` table.
+#[derive(Debug, Clone)]
+pub struct AnalyticsLog {
+    db: PgPool,
+}
+
+impl AnalyticsLog {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    pub async fn migrate(db: &PgPool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS analytics_events (
+                id UUID PRIMARY KEY,
+                endpoint TEXT NOT NULL,
+                contract_type TEXT,
+                solidity_version TEXT,
+                success BOOLEAN NOT NULL,
+                created_at TIMESTAMP WITH TIME ZONE NOT NULL
+            )
+            "#,
+        )
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn record(&self, event: &AnalyticsEvent) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO analytics_events (id, endpoint, contract_type, solidity_version, success, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(event.id)
+        .bind(&event.endpoint)
+        .bind(&event.contract_type)
+        .bind(&event.solidity_version)
+        .bind(event.success)
+        .bind(event.created_at)
+        .execute(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record on a background task so the request never waits on the insert.
+    pub fn record_in_background(&self, event: AnalyticsEvent) {
+        let log = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = log.record(&event).await {
+                warn!("Failed to record analytics event for {}: {}", event.endpoint, e);
+            }
+        });
+    }
+
+    pub async fn summary(&self) -> Result<AnalyticsSummary, sqlx::Error> {
+        let rows: Vec<(Option<String>, i64, i64)> = sqlx::query_as(
+            r#"
+            SELECT contract_type, COUNT(*), COUNT(*) FILTER (WHERE success)
+            FROM analytics_events
+            GROUP BY contract_type
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(summarize(rows))
+    }
+}
+
+/// Build the summary from `(contract_type, total, succeeded)` rows.
+fn summarize(rows: Vec<(Option<String>, i64, i64)>) -> AnalyticsSummary {
+    let mut summary = AnalyticsSummary::default();
+    let mut succeeded = 0;
+    for (contract_type, total, ok) in rows {
+        summary.total += total;
+        succeeded += ok;
+        let counts = summary
+            .by_contract_type
+            .entry(contract_type.unwrap_or_else(|| "unknown".to_string()))
+            .or_default();
+        counts.total += total;
+        counts.succeeded += ok;
+    }
+    if summary.total > 0 {
+        summary.success_rate = succeeded as f64 / summary.total as f64;
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract_classifier::classify_contract;
+
+    #[test]
+    fn test_question_events_detect_contract_type() {
+        let event = AnalyticsEvent::for_question("/ask", "How do I port my ERC721 mint function?", true);
+        assert_eq!(event.contract_type.as_deref(), Some("ERC721"));
+        assert_eq!(event.solidity_version, None);
+
+        let event = AnalyticsEvent::for_question("/ask", "what is ink!?", false);
+        assert_eq!(event.contract_type, None);
+        assert_eq!(solidity_version("pragma solidity ^0.8.20;\ncontract A {}").as_deref(), Some("^0.8.20"));
+    }
+
+    #[test]
+    fn test_summary_rates_success_by_contract_type() {
+        let summary = summarize(vec![(Some("ERC20".to_string()), 3, 2), (None, 1, 1)]);
+        assert_eq!(summary.total, 4);
+        assert_eq!(summary.success_rate, 0.75);
+        assert_eq!(summary.by_contract_type["ERC20"], ContractTypeCounts { total: 3, succeeded: 2 });
+        assert_eq!(summary.by_contract_type["unknown"], ContractTypeCounts { total: 1, succeeded: 1 });
+
+        assert_eq!(summarize(Vec::new()), AnalyticsSummary::default());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a scratch Postgres database at TEST_DATABASE_URL"]
+    async fn test_conversion_writes_analytics_row() {
+        let database_url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL");
+        let db = PgPool::connect(&database_url).await.unwrap();
+        AnalyticsLog::migrate(&db).await.unwrap();
+        let log = AnalyticsLog::new(db.clone());
+
+        let source = r#"
+pragma solidity ^0.8.0;
+contract Token {
+    event Transfer(address indexed from, address indexed to, uint256 value);
+    event Approval(address indexed owner, address indexed spender, uint256 value);
+    function totalSupply() public view returns (uint256) { return 0; }
+    function balanceOf(address account) public view returns (uint256) { return 0; }
+    function transfer(address to, uint256 amount) public returns (bool) { return true; }
+    function approve(address spender, uint256 amount) public returns (bool) { return true; }
+    function allowance(address owner, address spender) public view returns (uint256) { return 0; }
+    function transferFrom(address from, address to, uint256 amount) public returns (bool) { return true; }
+}
+"#;
+        let classification = classify_contract(source).unwrap();
+        let event = AnalyticsEvent::new("/classify", Some(&classification.contract_type), solidity_version(source), true);
+        log.record(&event).await.unwrap();
+
+        let row: AnalyticsEvent = sqlx::query_as("SELECT * FROM analytics_events WHERE id = $1")
+            .bind(event.id)
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        assert_eq!(row.contract_type.as_deref(), Some("ERC20"));
+        assert_eq!(row.solidity_version.as_deref(), Some("^0.8.0"));
+        assert!(row.success);
+
+        assert!(log.summary().await.unwrap().by_contract_type["ERC20"].total >= 1);
+    }
+}
@@ -0,0 +1,124 @@
+//! `Json` extractor whose rejections use the `ApiResponse` error shape.
+
+use shuttle_axum::axum::{
+    extract::{rejection::JsonRejection, FromRequest, Request},
+    http::StatusCode,
+    Json,
+};
+
+use crate::{ApiError, ApiResponse, ErrorCode};
+
+/// Prefix axum puts in front of serde's message for a body that parsed but
+/// didn't fit the target type
+const DATA_ERROR_PREFIX: &str = "Failed to deserialize the JSON body into the target type: ";
+
+/// Drop-in replacement for `Json` in handler arguments. Malformed or
+/// mistyped bodies are rejected with an `invalid_json` `ApiError` naming the
+/// offending field where serde reports one, keeping axum's status code.
+pub(crate) struct ApiJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ApiJson<T>
+where
+    Json<T>: FromRequest<S, Rejection = JsonRejection>,
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, Json<ApiResponse<()>>);
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Json::<T>::from_request(req, state).await {
+            Ok(Json(value)) => Ok(ApiJson(value)),
+            Err(rejection) => Err((
+                rejection.status(),
+                Json(ApiResponse {
+                    object: "error".to_string(),
+                    success: false,
+                    data: None,
+                    error: Some(invalid_json_error(&rejection.body_text())),
+                }),
+            )),
+        }
+    }
+}
+
+fn invalid_json_error(body_text: &str) -> ApiError {
+    ApiError {
+        error_type: "invalid_request_error".to_string(),
+        code: ErrorCode::InvalidJson,
+        message: body_text.to_string(),
+        param: field_path(body_text),
+    }
+}
+
+/// The field serde complained about: the `a.b[0]` path prefixed to the
+/// message, or the name in "missing field `x`".
+fn field_path(body_text: &str) -> Option<String> {
+    let detail = body_text.strip_prefix(DATA_ERROR_PREFIX)?;
+
+    if let Some((path, _)) = detail.split_once(": ") {
+        let is_path = path
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '[' | ']' | '?'));
+        if is_path && !path.is_empty() {
+            return Some(path.to_string());
+        }
+    }
+
+    let missing = detail.split_once("missing field `")?.1;
+    missing.split_once('`').map(|(field, _)| field.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use shuttle_axum::axum::{routing::post, Router};
+
+    #[derive(Deserialize)]
+    struct Strategy {
+        #[allow(dead_code)]
+        name: String,
+        #[allow(dead_code)]
+        risk_level: i32,
+    }
+
+    async fn post_body(body: &'static str) -> (u16, ApiResponse<()>) {
+        let app = Router::new().route("/strategies", post(|ApiJson(_): ApiJson<Strategy>| async { "ok" }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            shuttle_axum::axum::serve(listener, app).await.unwrap();
+        });
+
+        let response = reqwest::Client::new()
+            .post(format!("http://{}/strategies", addr))
+            .header("content-type", "application/json")
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+        (response.status().as_u16(), response.json().await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_malformed_body_gets_structured_error() {
+        let (status, body) = post_body(r#"{"name": "Yield", "risk_level": "#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST.as_u16());
+        assert!(!body.success);
+        let error = body.error.unwrap();
+        assert_eq!(error.code, "invalid_json");
+        assert_eq!(error.param, None);
+    }
+
+    #[tokio::test]
+    async fn test_type_mismatch_names_the_field() {
+        let (status, body) = post_body(r#"{"name": "Yield", "risk_level": "high"}"#).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY.as_u16());
+        let error = body.error.unwrap();
+        assert_eq!(error.code, "invalid_json");
+        assert_eq!(error.param.as_deref(), Some("risk_level"));
+        assert!(error.message.contains("invalid type"));
+
+        let (_, body) = post_body(r#"{"name": "Yield"}"#).await;
+        assert_eq!(body.error.unwrap().param.as_deref(), Some("risk_level"));
+    }
+}
@@ -0,0 +1,146 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use tracing::warn;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Entries returned by one `recent` call at most
+pub const MAX_AUDIT_ENTRIES: i64 = 500;
+
+/// One contract-interaction call, successful or not.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct AuditEntry {
+    pub id: Uuid,
+    /// Account the call was made for
+    pub actor: String,
+    /// e.g. `create_strategy`, `invest`, `withdraw`
+    pub action: String,
+    /// The request body or path parameters
+    #[schema(value_type = Object)]
+    pub parameters: serde_json::Value,
+    /// Whether the contract service was in offline mock mode
+    pub mock: bool,
+    /// Transaction hash, strategy id or other result on success
+    pub result: Option<String>,
+    /// Why the call failed, including validation errors
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditEntry {
+    pub fn new(actor: &str, action: &str, parameters: serde_json::Value, mock: bool, outcome: &Result<String, String>) -> Self {
+        let (result, error) = match outcome {
+            Ok(result) => (Some(result.clone()), None),
+            Err(error) => (None, Some(error.clone())),
+        };
+        Self {
+            id: Uuid::new_v4(),
+            actor: actor.to_string(),
+            action: action.to_string(),
+            parameters,
+            mock,
+            result,
+            error,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Contract-interaction calls in the `audit_log` table.
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    db: PgPool,
+}
+
+impl AuditLog {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    pub async fn migrate(db: &PgPool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS audit_log (
+                id UUID PRIMARY KEY,
+                actor TEXT NOT NULL,
+                action TEXT NOT NULL,
+                parameters JSONB NOT NULL,
+                mock BOOLEAN NOT NULL,
+                result TEXT,
+                error TEXT,
+                created_at TIMESTAMP WITH TIME ZONE NOT NULL
+            )
+            "#,
+        )
+        .execute(db)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_audit_log_actor ON audit_log(actor, created_at)")
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn record(&self, entry: &AuditEntry) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO audit_log (id, actor, action, parameters, mock, result, error, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+        )
+        .bind(entry.id)
+        .bind(&entry.actor)
+        .bind(&entry.action)
+        .bind(&entry.parameters)
+        .bind(entry.mock)
+        .bind(&entry.result)
+        .bind(&entry.error)
+        .bind(entry.created_at)
+        .execute(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record before the response goes out. A failed insert is logged, not
+    /// returned, so the caller still learns what happened to their call.
+    pub async fn record_or_warn(&self, entry: &AuditEntry) {
+        if let Err(e) = self.record(entry).await {
+            warn!("Failed to write audit entry for {} by {}: {}", entry.action, entry.actor, e);
+        }
+    }
+
+    /// Newest entries first, only `account`'s when given.
+    pub async fn recent(&self, account: Option<&str>, limit: i64) -> Result<Vec<AuditEntry>, sqlx::Error> {
+        sqlx::query_as::<_, AuditEntry>(
+            r#"
+            SELECT * FROM audit_log
+            WHERE $1::TEXT IS NULL OR actor = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(account)
+        .bind(limit.clamp(1, MAX_AUDIT_ENTRIES))
+        .fetch_all(&self.db)
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_splits_outcome() {
+        let params = serde_json::json!({"strategy_id": 1, "amount": 100});
+
+        let ok = AuditEntry::new("alice", "invest", params.clone(), true, &Ok("0xabc".to_string()));
+        assert_eq!((ok.result.as_deref(), ok.error), (Some("0xabc"), None));
+
+        let failed = AuditEntry::new("alice", "invest", params, true, &Err("Amount must be positive".to_string()));
+        assert_eq!((failed.result, failed.error.as_deref()), (None, Some("Amount must be positive")));
+    }
+}
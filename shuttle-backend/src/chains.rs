@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A chain strategies can target.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct ChainInfo {
+    pub name: String,
+    pub chain_id: String,
+    pub native_token: String,
+    pub rpc_url: String,
+    /// Whether the strategy contract can be deployed and called on this chain
+    pub supports_contracts: bool,
+}
+
+impl ChainInfo {
+    fn new(name: &str, chain_id: &str, native_token: &str, rpc_url: &str, supports_contracts: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            chain_id: chain_id.to_string(),
+            native_token: native_token.to_string(),
+            rpc_url: rpc_url.to_string(),
+            supports_contracts,
+        }
+    }
+
+    /// Case-insensitive match on name, chain id or native token.
+    fn matches(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        self.name.to_lowercase().contains(&query)
+            || self.chain_id == query
+            || self.native_token.to_lowercase() == query
+    }
+}
+
+/// Chains known to the backend, with ids matching those handed to the
+/// frontend in `ActionRequirements::chain_id`.
+#[derive(Debug, Clone)]
+pub struct ChainRegistry {
+    chains: Vec<ChainInfo>,
+}
+
+impl Default for ChainRegistry {
+    fn default() -> Self {
+        Self {
+            chains: vec![
+                ChainInfo::new("Polkadot", "0", "DOT", "wss://rpc.polkadot.io", false),
+                ChainInfo::new("Polkadot Asset Hub", "1000", "DOT", "wss://polkadot-asset-hub-rpc.polkadot.io", true),
+                ChainInfo::new("Astar", "592", "ASTR", "wss://rpc.astar.network", true),
+                ChainInfo::new("Ethereum", "1", "ETH", "https://eth.llamarpc.com", false),
+                ChainInfo::new("Base", "8453", "ETH", "https://mainnet.base.org", false),
+                ChainInfo::new("Arbitrum", "42161", "ETH", "https://arb1.arbitrum.io/rpc", false),
+                ChainInfo::new("Optimism", "10", "ETH", "https://mainnet.optimism.io", false),
+                ChainInfo::new("Polygon", "137", "POL", "https://polygon-rpc.com", false),
+                ChainInfo::new("BNB", "56", "BNB", "https://bsc-dataseed.binance.org", false),
+            ],
+        }
+    }
+}
+
+impl ChainRegistry {
+    pub fn all(&self) -> &[ChainInfo] {
+        &self.chains
+    }
+
+    /// Chains whose name contains `query` or whose id or native token equals it.
+    pub fn search(&self, query: &str) -> Vec<ChainInfo> {
+        self.chains.iter().filter(|c| c.matches(query)).cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_lists_known_chains() {
+        let registry = ChainRegistry::default();
+        let names: Vec<&str> = registry.all().iter().map(|c| c.name.as_str()).collect();
+        assert!(names.contains(&"Polkadot"));
+        assert!(names.contains(&"Ethereum"));
+        assert!(names.contains(&"Base"));
+    }
+
+    #[test]
+    fn test_search_narrows_results() {
+        let registry = ChainRegistry::default();
+
+        let polkadot = registry.search("polkadot");
+        assert_eq!(polkadot.len(), 2);
+        assert!(polkadot.iter().all(|c| c.native_token == "DOT"));
+
+        let base = registry.search("8453");
+        assert_eq!(base.len(), 1);
+        assert_eq!(base[0].name, "Base");
+
+        assert!(registry.search("solana").is_empty());
+    }
+}
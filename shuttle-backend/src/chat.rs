@@ -2,11 +2,14 @@ use qdrant_client::Qdrant;
 use qdrant_client::qdrant::{Distance, SearchPointsBuilder, CreateCollectionBuilder, VectorParamsBuilder};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use tracing::info;
+use std::sync::Arc;
+use tracing::{info, warn};
 use crate::gemini_client::GeminiClient;
+use crate::prompts::{render, Prompt};
+use crate::session_store::{InMemorySessionStore, SessionStore};
 use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatMessage {
     pub role: String,
     pub content: String,
@@ -34,21 +37,97 @@ pub struct UISuggestion {
     pub data: HashMap<String, String>,
 }
 
+/// Conversation state kept per session: a rolling summary of older turns
+/// plus the most recent turns verbatim.
+#[derive(Debug, Clone, Default)]
+pub struct ChatSession {
+    pub summary: Option<String>,
+    pub turns: Vec<ChatMessage>,
+}
+
+impl ChatSession {
+    pub fn push(&mut self, role: &str, content: &str) {
+        self.turns.push(ChatMessage {
+            role: role.to_string(),
+            content: content.to_string(),
+            timestamp: chrono::Utc::now(),
+        });
+    }
+
+    /// Remove and return the oldest turns beyond `max_turns`, which should be
+    /// folded into the summary.
+    pub fn take_overflow(&mut self, max_turns: usize) -> Vec<ChatMessage> {
+        if self.turns.len() <= max_turns {
+            return Vec::new();
+        }
+        let overflow = self.turns.len() - max_turns;
+        self.turns.drain(..overflow).collect()
+    }
+
+    /// Prompt asking the LLM to fold `overflow` into the existing summary.
+    pub fn summary_prompt(&self, overflow: &[ChatMessage]) -> String {
+        let previous = self.summary.as_deref().unwrap_or("(none)");
+        render(Prompt::ChatSummary, &[("summary", previous), ("turns", format_turns(overflow).as_str())])
+    }
+
+    /// History block to include in the next prompt.
+    pub fn history_prompt(&self) -> String {
+        let mut sections = Vec::new();
+        if let Some(summary) = &self.summary {
+            sections.push(format!("Summary of earlier conversation:\n{}", summary));
+        }
+        if !self.turns.is_empty() {
+            sections.push(format!("Recent turns:\n{}", format_turns(&self.turns)));
+        }
+        sections.join("\n\n")
+    }
+}
+
+fn format_turns(turns: &[ChatMessage]) -> String {
+    turns
+        .iter()
+        .map(|turn| format!("{}: {}", turn.role, turn.content))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 pub struct ChatService {
     qdrant_client: Qdrant,
     gemini_client: GeminiClient,
+    sessions: Arc<dyn SessionStore>,
+    /// Number of turns (single user or assistant messages) kept verbatim
+    /// before older ones are summarized
+    max_history_turns: usize,
 }
 
 impl ChatService {
     pub fn new(qdrant_client: Qdrant, gemini_api_key: String) -> Self {
         let gemini_client = GeminiClient::new(gemini_api_key);
+        let max_history_turns = std::env::var("CHAT_MAX_HISTORY_TURNS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10);
         
         Self {
             qdrant_client,
             gemini_client,
+            sessions: Arc::new(InMemorySessionStore::new()),
+            max_history_turns,
         }
     }
 
+    #[allow(dead_code)]
+    pub fn with_gemini_client(mut self, gemini_client: GeminiClient) -> Self {
+        self.gemini_client = gemini_client;
+        self
+    }
+
+    /// Keep sessions in `sessions` instead of process memory.
+    pub fn with_session_store(mut self, sessions: Arc<dyn SessionStore>) -> Self {
+        self.sessions = sessions;
+        self
+    }
+
     pub async fn initialize_collection(&self) -> Result<(), Box<dyn std::error::Error>> {
         let collection_name = "defi_knowledge";
         
@@ -125,12 +204,18 @@ impl ChatService {
     }
 
     pub async fn generate_response(&self, user_message: &str, context: &[String]) -> Result<ChatResponse, Box<dyn std::error::Error>> {
-        let context_str = context.join("\n\n");
-        
-        let prompt = format!(
-            "You are DynaVest AI, a DeFi strategy advisor. Use the following context to answer questions about DeFi strategies, yield farming, and investment opportunities.\n\nContext:\n{}\n\nQuestion: {}\n\nProvide helpful, accurate advice about DeFi strategies. Include relevant keywords and UI suggestions in your response.",
-            context_str, user_message
-        );
+        self.generate_response_with_history(user_message, context, "", uuid::Uuid::new_v4().to_string())
+            .await
+    }
+
+    async fn generate_response_with_history(
+        &self,
+        user_message: &str,
+        context: &[String],
+        history: &str,
+        session_id: String,
+    ) -> Result<ChatResponse, Box<dyn std::error::Error>> {
+        let prompt = build_chat_prompt(user_message, context, history);
 
         let response = self.gemini_client.generate_response(&prompt, &[]).await?;
         let keywords = self.extract_keywords(&response);
@@ -140,10 +225,35 @@ impl ChatService {
             message: response,
             keywords,
             ui_suggestions,
-            session_id: uuid::Uuid::new_v4().to_string(),
+            session_id,
         })
     }
 
+    /// Fold turns beyond `max_history_turns` into the session's rolling
+    /// summary and return the history block for the next prompt.
+    async fn compact_history(&self, session_id: &str) -> anyhow::Result<String> {
+        let mut session = self.sessions.load(session_id).await?;
+        let overflow = session.take_overflow(self.max_history_turns);
+
+        if !overflow.is_empty() {
+            let summary = match self.gemini_client.try_generate_response(&session.summary_prompt(&overflow), &[]).await {
+                Ok(summary) => Some(summary),
+                Err(e) => {
+                    warn!("Failed to summarize chat history for {}: {}", session_id, e);
+                    None
+                }
+            };
+            self.sessions
+                .compact(session_id, self.max_history_turns, summary.as_deref())
+                .await?;
+            if summary.is_some() {
+                session.summary = summary;
+            }
+        }
+
+        Ok(session.history_prompt())
+    }
+
     fn extract_keywords(&self, content: &str) -> Vec<String> {
         let mut keywords = Vec::new();
         let content_lower = content.to_lowercase();
@@ -221,12 +331,76 @@ impl ChatService {
     pub async fn process_chat(&self, request: ChatRequest) -> Result<ChatResponse, Box<dyn std::error::Error>> {
         info!("Processing chat request from user: {}", request.user_id);
         
+        let session_id = request
+            .session_id
+            .clone()
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
         // Search for relevant context
         let context = self.search_knowledge(&request.message, 3).await?;
+        let history = self.compact_history(&session_id).await?;
         
         // Generate response
-        let response = self.generate_response(&request.message, &context).await?;
+        let response = self
+            .generate_response_with_history(&request.message, &context, &history, session_id.clone())
+            .await?;
+
+        let mut exchange = ChatSession::default();
+        exchange.push("user", &request.message);
+        exchange.push("assistant", &response.message);
+        self.sessions.append(&session_id, &exchange.turns).await?;
         
         Ok(response)
     }
+}
+
+fn build_chat_prompt(user_message: &str, context: &[String], history: &str) -> String {
+    let context_str = context.join("\n\n");
+    let history_str = if history.is_empty() {
+        String::new()
+    } else {
+        format!("Conversation so far:\n{}\n\n", history)
+    };
+
+    render(
+        Prompt::Chat,
+        &[("context", context_str.as_str()), ("history", history_str.as_str()), ("question", user_message)],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_exceeding_turn_limit_is_summarized() {
+        let mut session = ChatSession::default();
+        for i in 0..6 {
+            session.push("user", &format!("question {}", i));
+            session.push("assistant", &format!("answer {}", i));
+        }
+
+        let overflow = session.take_overflow(4);
+        assert_eq!(overflow.len(), 8);
+        assert!(session.summary_prompt(&overflow).contains("question 0"));
+
+        // Stand-in for the LLM summary
+        session.summary = Some("User asked about staking DOT.".to_string());
+
+        let prompt = build_chat_prompt("question 6", &[], &session.history_prompt());
+        assert!(prompt.contains("Summary of earlier conversation:\nUser asked about staking DOT."));
+        assert!(prompt.contains("user: question 4"));
+        assert!(prompt.contains("assistant: answer 5"));
+        assert!(!prompt.contains("question 3"));
+        assert!(prompt.contains("Question: question 6"));
+    }
+
+    #[test]
+    fn test_no_overflow_within_limit() {
+        let mut session = ChatSession::default();
+        session.push("user", "hi");
+        assert!(session.take_overflow(4).is_empty());
+        assert!(session.history_prompt().contains("user: hi"));
+        assert!(session.summary.is_none());
+    }
 }
\ No newline at end of file
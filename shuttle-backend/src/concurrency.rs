@@ -0,0 +1,96 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Poll;
+
+use tokio::sync::Semaphore;
+
+/// Worker-pool size for batch jobs: `MAX_CONCURRENCY` if set to a positive
+/// number, otherwise the number of available CPUs.
+pub fn max_concurrency_from_env() -> usize {
+    std::env::var("MAX_CONCURRENCY")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&n: &usize| n > 0)
+        .unwrap_or_else(default_concurrency)
+}
+
+fn default_concurrency() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4)
+}
+
+/// Run `f` over `items` with at most `limit` calls in progress at once,
+/// returning the results in input order.
+pub async fn map_concurrent<T, R, F, Fut>(items: Vec<T>, limit: usize, f: F) -> Vec<R>
+where
+    F: Fn(T) -> Fut,
+    Fut: Future<Output = R>,
+{
+    let semaphore = Semaphore::new(limit.max(1));
+    let (semaphore, f) = (&semaphore, &f);
+    let futures: Vec<Pin<Box<dyn Future<Output = R> + '_>>> = items
+        .into_iter()
+        .map(|item| {
+            Box::pin(async move {
+                let _permit = semaphore.acquire().await.expect("semaphore is never closed");
+                f(item).await
+            }) as Pin<Box<dyn Future<Output = R> + '_>>
+        })
+        .collect();
+    join_all(futures).await
+}
+
+/// Drive every future to completion on the current task.
+async fn join_all<R>(mut futures: Vec<Pin<Box<dyn Future<Output = R> + '_>>>) -> Vec<R> {
+    let mut outputs: Vec<Option<R>> = futures.iter().map(|_| None).collect();
+    std::future::poll_fn(|cx| {
+        let mut pending = false;
+        for (future, output) in futures.iter_mut().zip(outputs.iter_mut()) {
+            if output.is_none() {
+                match future.as_mut().poll(cx) {
+                    Poll::Ready(value) => *output = Some(value),
+                    Poll::Pending => pending = true,
+                }
+            }
+        }
+        if pending {
+            Poll::Pending
+        } else {
+            Poll::Ready(())
+        }
+    })
+    .await;
+    outputs.into_iter().map(|output| output.expect("every future completed")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_concurrency_limit_caps_simultaneous_items() {
+        let active = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+
+        let results = map_concurrent((0..10).collect(), 2, |i: u32| {
+            let (active, peak) = (&active, &peak);
+            async move {
+                let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                active.fetch_sub(1, Ordering::SeqCst);
+                i * 10
+            }
+        })
+        .await;
+
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        assert_eq!(results, (0..10).map(|i| i * 10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_default_concurrency_is_positive() {
+        assert!(default_concurrency() >= 1);
+    }
+}
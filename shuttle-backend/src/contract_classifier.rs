@@ -0,0 +1,304 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use utoipa::ToSchema;
+
+use crate::parsers::access_control::access_control_notes;
+use crate::parsers::custody::{custody_notes, transfer_notes};
+use crate::parsers::difficulty::migration_difficulty;
+use crate::parsers::inheritance::inheritance_notes;
+use crate::parsers::parse_cache::ParseCache;
+use crate::parsers::reentrancy::{reentrancy_notes, ReentrancyFinding};
+
+/// Default minimum confidence for a match to be reported as the detected type
+const STRONG_MATCH: f32 = 0.6;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ClassifyRequest {
+    pub solidity_code: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ContractTypeMatch {
+    pub contract_type: String,
+    pub confidence: f32,
+    pub matched_signatures: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ContractClassification {
+    pub contract_name: String,
+    /// Best match, or "unknown" when nothing reaches the confidence threshold
+    pub contract_type: String,
+    pub confidence: f32,
+    /// All candidate types with a non-zero score, best first
+    pub matches: Vec<ContractTypeMatch>,
+    /// Notes for behaviour inherited from well-known base contracts and for
+    /// contracts that hold native value
+    pub migration_notes: Vec<String>,
+    /// 1 (trivial) to 10 (needs a redesign), see `parsers::difficulty`
+    pub migration_difficulty: u8,
+    /// Reentrancy guards and risky call orderings found in the source
+    pub reentrancy: Vec<ReentrancyFinding>,
+}
+
+/// Weighted signatures that identify one contract type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractShape {
+    pub contract_type: String,
+    /// Function name -> weight
+    pub functions: Vec<(String, f32)>,
+    /// Event name -> weight
+    pub events: Vec<(String, f32)>,
+}
+
+impl ContractShape {
+    fn new(contract_type: &str, functions: &[(&str, f32)], events: &[(&str, f32)]) -> Self {
+        let owned = |signals: &[(&str, f32)]| signals.iter().map(|(name, weight)| (name.to_string(), *weight)).collect();
+        Self {
+            contract_type: contract_type.to_string(),
+            functions: owned(functions),
+            events: owned(events),
+        }
+    }
+}
+
+/// Scoring table and threshold used by `classify_contract_with`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassifierConfig {
+    pub shapes: Vec<ContractShape>,
+    /// Minimum confidence for the best match to be reported instead of "unknown"
+    pub threshold: f32,
+}
+
+impl Default for ClassifierConfig {
+    /// Core signatures weigh more than optional ones, so a token without
+    /// `approve` or `allowance` still scores as an ERC20.
+    fn default() -> Self {
+        Self {
+            shapes: vec![
+                ContractShape::new(
+                    "ERC20",
+                    &[("totalSupply", 1.5), ("balanceOf", 2.0), ("transfer", 2.0), ("transferFrom", 1.0), ("approve", 1.0), ("allowance", 1.0)],
+                    &[("Transfer", 1.5), ("Approval", 0.5)],
+                ),
+                ContractShape::new(
+                    "ERC721",
+                    &[("balanceOf", 1.0), ("ownerOf", 2.0), ("safeTransferFrom", 2.0), ("transferFrom", 1.0), ("approve", 1.0), ("setApprovalForAll", 1.0), ("getApproved", 1.0), ("isApprovedForAll", 1.0)],
+                    &[("Transfer", 1.0), ("Approval", 0.5), ("ApprovalForAll", 1.0)],
+                ),
+                ContractShape::new(
+                    "ERC1155",
+                    &[("balanceOf", 1.0), ("balanceOfBatch", 2.0), ("safeTransferFrom", 1.0), ("safeBatchTransferFrom", 2.0), ("setApprovalForAll", 1.0), ("isApprovedForAll", 1.0)],
+                    &[("TransferSingle", 1.5), ("TransferBatch", 1.5), ("ApprovalForAll", 0.5)],
+                ),
+                ContractShape::new(
+                    "MultiSig",
+                    &[("submitTransaction", 2.0), ("confirmTransaction", 2.0), ("executeTransaction", 2.0), ("revokeConfirmation", 1.0), ("getOwners", 1.0)],
+                    &[("SubmitTransaction", 1.0), ("ConfirmTransaction", 1.0), ("ExecuteTransaction", 1.0)],
+                ),
+                ContractShape::new("Escrow", &[("deposit", 1.0), ("release", 1.0), ("refund", 1.0)], &[]),
+                ContractShape::new("Counter", &[("increment", 1.0), ("decrement", 1.0), ("getCount", 1.0)], &[]),
+                ContractShape::new("Flipper", &[("flip", 1.0), ("get", 1.0)], &[]),
+                ContractShape::new("Storage", &[("set", 1.0), ("get", 1.0)], &[]),
+            ],
+            threshold: STRONG_MATCH,
+        }
+    }
+}
+
+impl ClassifierConfig {
+    #[allow(dead_code)]
+    pub fn with_threshold(mut self, threshold: f32) -> Result<Self, String> {
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err(format!("Classification threshold must be in [0.0, 1.0], got {}", threshold));
+        }
+        self.threshold = threshold;
+        Ok(self)
+    }
+
+    /// Score the contract against each shape by the weighted fraction of its
+    /// signatures that are present. Shapes with no matches are dropped.
+    pub fn score(&self, function_names: &HashSet<String>, event_names: &HashSet<String>) -> Vec<ContractTypeMatch> {
+        let mut matches: Vec<ContractTypeMatch> = self
+            .shapes
+            .iter()
+            .filter_map(|shape| {
+                let functions = shape.functions.iter().filter(|(name, _)| function_names.contains(name));
+                let events = shape.events.iter().filter(|(name, _)| event_names.contains(name));
+                let matched: Vec<&(String, f32)> = functions.chain(events).collect();
+
+                let total: f32 = shape.functions.iter().chain(&shape.events).map(|(_, weight)| weight).sum();
+                if matched.is_empty() || total <= 0.0 {
+                    return None;
+                }
+
+                Some(ContractTypeMatch {
+                    contract_type: shape.contract_type.clone(),
+                    confidence: matched.iter().map(|(_, weight)| weight).sum::<f32>() / total,
+                    matched_signatures: matched.iter().map(|(name, _)| name.clone()).collect(),
+                })
+            })
+            .collect();
+
+        matches.sort_by(|a, b| {
+            b.confidence
+                .partial_cmp(&a.confidence)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| b.matched_signatures.len().cmp(&a.matched_signatures.len()))
+        });
+        matches
+    }
+}
+
+/// Score the contract against the default shapes.
+#[allow(dead_code)]
+pub fn fuzzy_match_contract_type(function_names: &HashSet<String>, event_names: &HashSet<String>) -> Vec<ContractTypeMatch> {
+    ClassifierConfig::default().score(function_names, event_names)
+}
+
+pub fn classify_contract(source: &str) -> Result<ContractClassification, String> {
+    classify_contract_with(source, &ClassifierConfig::default())
+}
+
+pub fn classify_contract_with(source: &str, config: &ClassifierConfig) -> Result<ContractClassification, String> {
+    let contract = ParseCache::shared().parse_contract(source).map_err(|e| e.to_string())?;
+
+    // The parser skips functions with no body, so also collect every
+    // declared name directly
+    let function_re = Regex::new(r"function\s+(\w+)").map_err(|e| format!("Regex error: {}", e))?;
+    let mut function_names: HashSet<String> = contract.functions.iter().map(|f| f.name.clone()).collect();
+    function_names.extend(function_re.captures_iter(source).map(|c| c[1].to_string()));
+    let event_names: HashSet<String> = contract.events.iter().map(|e| e.name.clone()).collect();
+
+    let matches = config.score(&function_names, &event_names);
+    let (contract_type, confidence) = match matches.first() {
+        Some(best) if best.confidence >= config.threshold => (best.contract_type.clone(), best.confidence),
+        Some(best) => ("unknown".to_string(), best.confidence),
+        None => ("unknown".to_string(), 0.0),
+    };
+
+    let mut migration_notes = access_control_notes(&contract);
+    migration_notes.extend(custody_notes(&contract));
+    migration_notes.extend(transfer_notes(&contract));
+    migration_notes.extend(inheritance_notes(&contract));
+    migration_notes.extend(reentrancy_notes(&contract));
+    let migration_difficulty = migration_difficulty(&contract);
+
+    Ok(ContractClassification {
+        contract_name: contract.name,
+        contract_type,
+        confidence,
+        matches,
+        migration_notes,
+        migration_difficulty,
+        reentrancy: contract.reentrancy,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_erc20() {
+        let source = r#"
+contract MyToken {
+    mapping(address => uint256) public balances;
+    event Transfer(address indexed from, address indexed to, uint256 value);
+    event Approval(address indexed owner, address indexed spender, uint256 value);
+
+    function totalSupply() public view returns (uint256) { return 0; }
+    function balanceOf(address account) public view returns (uint256) { return balances[account]; }
+    function transfer(address to, uint256 amount) public returns (bool) { return true; }
+    function approve(address spender, uint256 amount) public returns (bool) { return true; }
+    function allowance(address owner, address spender) public view returns (uint256) { return 0; }
+    function transferFrom(address from, address to, uint256 amount) public returns (bool) { return true; }
+}
+"#;
+
+        let classification = classify_contract(source).unwrap();
+        assert_eq!(classification.contract_name, "MyToken");
+        assert_eq!(classification.contract_type, "ERC20");
+        assert_eq!(classification.confidence, 1.0);
+        // ERC721 shares some signatures but scores lower
+        assert!(classification.matches.iter().any(|m| m.contract_type == "ERC721" && m.confidence < 1.0));
+        assert_eq!(classification.migration_difficulty, 1);
+    }
+
+    #[test]
+    fn test_classify_multisig() {
+        let source = r#"
+contract Wallet {
+    address[] public owners;
+
+    function submitTransaction(address to, uint256 value, bytes memory data) public onlyOwner {}
+    function confirmTransaction(uint256 txIndex) public onlyOwner {}
+    function executeTransaction(uint256 txIndex) public onlyOwner {}
+    function revokeConfirmation(uint256 txIndex) public onlyOwner {}
+    function getOwners() public view returns (address[] memory) { return owners; }
+}
+"#;
+
+        let classification = classify_contract(source).unwrap();
+        assert_eq!(classification.contract_type, "MultiSig");
+        assert!(classification.confidence >= STRONG_MATCH);
+    }
+
+    #[test]
+    fn test_weak_match_is_unknown_with_guesses() {
+        let source = r#"
+contract Vault {
+    function deposit() public payable {}
+    function withdraw(uint256 amount) public {}
+}
+"#;
+
+        let classification = classify_contract(source).unwrap();
+        assert_eq!(classification.contract_type, "unknown");
+        assert_eq!(classification.matches[0].contract_type, "Escrow");
+    }
+
+    #[test]
+    fn test_partial_erc20_still_classifies() {
+        let source = r#"
+contract LiteToken {
+    event Transfer(address indexed from, address indexed to, uint256 value);
+
+    function totalSupply() public view returns (uint256) { return 0; }
+    function balanceOf(address account) public view returns (uint256) { return 0; }
+    function transfer(address to, uint256 amount) public returns (bool) { return true; }
+    function transferFrom(address from, address to, uint256 amount) public returns (bool) { return true; }
+}
+"#;
+
+        let classification = classify_contract(source).unwrap();
+        assert_eq!(classification.contract_type, "ERC20");
+        assert!(classification.confidence >= STRONG_MATCH && classification.confidence < 1.0);
+
+        // A stricter deployment can still reject it
+        let strict = ClassifierConfig::default().with_threshold(0.95).unwrap();
+        assert_eq!(classify_contract_with(source, &strict).unwrap().contract_type, "unknown");
+    }
+
+    #[test]
+    fn test_unrelated_contract_is_unknown() {
+        let source = r#"
+contract Lottery {
+    function enter() public payable {}
+    function pickWinner() public {}
+}
+"#;
+
+        let classification = classify_contract(source).unwrap();
+        assert_eq!(classification.contract_type, "unknown");
+        assert!(classification.matches.is_empty());
+        assert_eq!(classification.confidence, 0.0);
+    }
+
+    #[test]
+    fn test_threshold_is_validated() {
+        assert!(ClassifierConfig::default().with_threshold(1.5).is_err());
+        assert!(ClassifierConfig::default().with_threshold(0.9).is_ok());
+    }
+}
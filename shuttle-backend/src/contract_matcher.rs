@@ -1,7 +1,8 @@
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContractPair {
@@ -13,16 +14,279 @@ pub struct ContractPair {
     pub ink_content: String,
 }
 
+/// Folder under a Solidity base path that holds the sources, as in a
+/// Foundry project
+pub const DEFAULT_SOLIDITY_SRC_DIR: &str = "src";
+
+fn default_solidity_src_dir() -> Option<String> {
+    Some(DEFAULT_SOLIDITY_SRC_DIR.to_string())
+}
+
+/// A Solidity contract, by file stem, and the ink! example it ports to.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ContractMapping {
+    pub contract_type: String,
+    /// ink! source relative to the ink! base path, e.g. `erc20/lib.rs`
+    pub ink_path: String,
+    pub description: String,
+}
+
+impl ContractMapping {
+    fn new(contract_type: &str, ink_path: &str, description: &str) -> Self {
+        Self {
+            contract_type: contract_type.to_string(),
+            ink_path: ink_path.to_string(),
+            description: description.to_string(),
+        }
+    }
+}
+
+/// The built-in pairs between `solidity-examples` and the official ink! examples.
+pub fn default_contract_mappings() -> Vec<ContractMapping> {
+    vec![
+        // Token standards
+        ContractMapping::new("SimpleERC20", "erc20/lib.rs", "ERC20 fungible token implementation with basic transfer, approve, and allowance functionality"),
+        ContractMapping::new("SimpleNFT", "erc721/lib.rs", "ERC721 non-fungible token implementation with minting, burning, and transfer capabilities"),
+        ContractMapping::new("SimpleERC1155", "erc1155/lib.rs", "Multi-token standard supporting both fungible and non-fungible tokens with batch operations"),
+        // Basic contracts
+        ContractMapping::new("Flipper", "flipper/lib.rs", "Simple boolean state contract that can be flipped between true and false"),
+        ContractMapping::new("Counter", "incrementer/lib.rs", "Basic counter contract with increment and decrement functionality"),
+        ContractMapping::new("SimpleStorage", "contract-storage/lib.rs", "Basic storage contract demonstrating state management and data persistence"),
+        ContractMapping::new("MultiSigWallet", "multisig/lib.rs", "Multi-signature wallet requiring multiple approvals for transactions"),
+        ContractMapping::new("SimpleEscrow", "payment-channel/lib.rs", "Escrow contract for holding funds until conditions are met"),
+        ContractMapping::new("EventEmitter", "events/lib.rs", "Contract demonstrating event emission and indexing patterns"),
+        // Cross-contract calls
+        ContractMapping::new("CallerContract", "basic-contract-caller/lib.rs", "Contract that calls other contracts, demonstrating cross-contract interactions"),
+        ContractMapping::new("TargetContract", "basic-contract-caller/other-contract/lib.rs", "Target contract for cross-contract calls and interactions"),
+    ]
+}
+
+#[derive(Debug, Deserialize)]
+struct ContractMappingsFile {
+    mappings: Vec<ContractMapping>,
+}
+
+/// Read contract mappings from a JSON file:
+///
+/// ```json
+/// { "mappings": [{ "contract_type": "MyToken", "ink_path": "my-token/lib.rs", "description": "..." }] }
+/// ```
+///
+/// Every `ink_path` must exist under `ink_base_path`; the error lists the
+/// ones that don't.
+pub fn load_contract_mappings(path: &Path, ink_base_path: &str) -> Result<Vec<ContractMapping>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read contract mappings {}: {}", path.display(), e))?;
+    let file: ContractMappingsFile = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid contract mappings {}: {}", path.display(), e))?;
+
+    let missing: Vec<&str> = file
+        .mappings
+        .iter()
+        .filter(|m| !Path::new(ink_base_path).join(&m.ink_path).exists())
+        .map(|m| m.ink_path.as_str())
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "Contract mappings {} name ink! paths missing under {}: {}",
+            path.display(),
+            ink_base_path,
+            missing.join(", ")
+        ));
+    }
+
+    Ok(file.mappings)
+}
+
+/// A contract file that couldn't be read, e.g. because it isn't UTF-8.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileReadError {
+    pub path: String,
+    pub reason: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContractMatchResult {
     pub pairs: Vec<ContractPair>,
     pub unmatched_solidity: Vec<String>,
     pub unmatched_ink: Vec<String>,
+    /// Files skipped because they couldn't be read; the rest still pair
+    #[serde(default)]
+    pub read_errors: Vec<FileReadError>,
+}
+
+/// Read a contract source, naming the offset of the first bad byte when
+/// the file isn't valid UTF-8.
+fn read_source(path: &str) -> Result<String, FileReadError> {
+    let error = |reason: String| FileReadError {
+        path: path.to_string(),
+        reason,
+    };
+    let bytes = fs::read(path).map_err(|e| error(e.to_string()))?;
+    String::from_utf8(bytes)
+        .map_err(|e| error(format!("not valid UTF-8 (invalid byte at offset {})", e.utf8_error().valid_up_to())))
+}
+
+/// A corpus of matching Solidity and ink! examples, e.g. the official ink!
+/// examples or a team's own curated contracts.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExampleRoot {
+    pub name: String,
+    pub solidity_path: String,
+    pub ink_path: String,
+    /// Sources folder under `solidity_path`; `null` when `solidity_path`
+    /// is the contracts folder itself
+    #[serde(default = "default_solidity_src_dir")]
+    pub solidity_src_dir: Option<String>,
+    /// JSON file of contract mappings for this root; the built-in mappings
+    /// otherwise
+    #[serde(default)]
+    pub mappings_path: Option<String>,
+}
+
+impl ExampleRoot {
+    pub fn new(name: &str, solidity_path: String, ink_path: String) -> Self {
+        Self {
+            name: name.to_string(),
+            solidity_path,
+            ink_path,
+            solidity_src_dir: default_solidity_src_dir(),
+            mappings_path: None,
+        }
+    }
+
+    pub fn matcher(&self) -> Result<ContractMatcher, String> {
+        let matcher = match &self.mappings_path {
+            Some(path) => ContractMatcher::with_mappings_file(self.solidity_path.clone(), self.ink_path.clone(), Path::new(path))?,
+            None => ContractMatcher::new(self.solidity_path.clone(), self.ink_path.clone()),
+        };
+        Ok(matcher.with_solidity_src_dir(self.solidity_src_dir.clone()))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ExampleRootsManifest {
+    roots: Vec<ExampleRoot>,
+}
+
+/// Override where the official Solidity examples are checked out
+pub const SOLIDITY_EXAMPLES_ENV: &str = "SOLIDITY_EXAMPLES_PATH";
+/// Override where the official ink! examples are checked out
+pub const INK_EXAMPLES_ENV: &str = "INK_EXAMPLES_PATH";
+
+/// Whether the example directories could be indexed, as checked at startup.
+#[derive(Debug, Clone, Serialize, ToSchema, PartialEq)]
+pub struct ExampleDirsStatus {
+    /// Every configured directory exists
+    pub available: bool,
+    /// Contract pairs found; 0 when any directory is missing
+    pub contract_pairs: usize,
+    /// Configured directories that don't exist
+    pub missing: Vec<String>,
+    /// Why the roots couldn't be determined at all, e.g. a bad manifest
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ExampleDirsStatus {
+    pub fn unavailable(error: String) -> Self {
+        Self {
+            available: false,
+            contract_pairs: 0,
+            missing: Vec::new(),
+            error: Some(error),
+        }
+    }
+
+    /// What to log when the examples can't be indexed, naming the expected
+    /// paths and how to point the server at them.
+    pub fn problem(&self) -> Option<String> {
+        if let Some(error) = &self.error {
+            return Some(format!(
+                "Example roots are unavailable: {}. Fix EXAMPLE_ROOTS_MANIFEST or unset it to use {} and {}",
+                error, SOLIDITY_EXAMPLES_ENV, INK_EXAMPLES_ENV
+            ));
+        }
+        (!self.missing.is_empty()).then(|| missing_dirs_message(&self.missing))
+    }
+}
+
+/// Which example directories are missing and how to configure them.
+pub fn missing_dirs_message(missing: &[String]) -> String {
+    format!(
+        "Example directories not found: {}. Set {} and {} to where the Solidity and ink! examples are checked out, or EXAMPLE_ROOTS_MANIFEST to index several roots",
+        missing.join(", "),
+        SOLIDITY_EXAMPLES_ENV,
+        INK_EXAMPLES_ENV
+    )
+}
+
+/// Directories of `roots` that don't exist, in root order.
+pub fn missing_example_dirs(roots: &[ExampleRoot]) -> Vec<String> {
+    roots
+        .iter()
+        .flat_map(|root| [&root.solidity_path, &root.ink_path])
+        .filter(|path| !Path::new(path).is_dir())
+        .cloned()
+        .collect()
+}
+
+/// Check that `roots` exist and count the pairs they hold.
+pub fn check_example_roots(roots: &[ExampleRoot]) -> ExampleDirsStatus {
+    let missing = missing_example_dirs(roots);
+    let contract_pairs = if missing.is_empty() {
+        roots
+            .iter()
+            .filter_map(|root| root.matcher().ok()?.find_contract_pairs().ok())
+            .map(|result| result.pairs.len())
+            .sum()
+    } else {
+        0
+    };
+    ExampleDirsStatus {
+        available: missing.is_empty(),
+        contract_pairs,
+        missing,
+        error: None,
+    }
+}
+
+/// Read example roots from a JSON manifest:
+///
+/// ```json
+/// { "roots": [{ "name": "official", "solidity_path": "../solidity-examples", "ink_path": "../ink-examples-main" }] }
+/// ```
+///
+/// Roots may also set `solidity_src_dir` and `mappings_path` (see
+/// `load_contract_mappings`). Relative paths are resolved against the
+/// manifest's directory.
+pub fn load_example_roots(manifest_path: &Path) -> Result<Vec<ExampleRoot>, String> {
+    let content = fs::read_to_string(manifest_path)
+        .map_err(|e| format!("Failed to read example roots manifest {}: {}", manifest_path.display(), e))?;
+    let manifest: ExampleRootsManifest = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid example roots manifest {}: {}", manifest_path.display(), e))?;
+
+    let base = manifest_path.parent().unwrap_or_else(|| Path::new("."));
+    let resolve = |path: &str| base.join(path).to_string_lossy().to_string();
+    Ok(manifest
+        .roots
+        .into_iter()
+        .map(|root| ExampleRoot {
+            solidity_path: resolve(&root.solidity_path),
+            ink_path: resolve(&root.ink_path),
+            name: root.name,
+            solidity_src_dir: root.solidity_src_dir,
+            mappings_path: root.mappings_path.as_deref().map(resolve),
+        })
+        .collect())
 }
 
 pub struct ContractMatcher {
     pub solidity_base_path: String,
     pub ink_base_path: String,
+    /// Folder under `solidity_base_path` to scan, `src` by default
+    pub solidity_src_dir: Option<String>,
+    mappings: Vec<ContractMapping>,
 }
 
 impl ContractMatcher {
@@ -30,13 +294,33 @@ impl ContractMatcher {
         Self {
             solidity_base_path,
             ink_base_path,
+            solidity_src_dir: default_solidity_src_dir(),
+            mappings: default_contract_mappings(),
         }
     }
 
+    /// Like `new`, but pairing contracts by the mappings in `mappings_path`
+    /// (see `load_contract_mappings`) instead of the built-in ones.
+    pub fn with_mappings_file(solidity_base_path: String, ink_base_path: String, mappings_path: &Path) -> Result<Self, String> {
+        let mappings = load_contract_mappings(mappings_path, &ink_base_path)?;
+        Ok(Self {
+            mappings,
+            ..Self::new(solidity_base_path, ink_base_path)
+        })
+    }
+
+    /// Scan `{solidity_base_path}/{dir}` instead of `src`, or the base path
+    /// itself when `dir` is `None`.
+    pub fn with_solidity_src_dir(mut self, dir: Option<String>) -> Self {
+        self.solidity_src_dir = dir;
+        self
+    }
+
     pub fn find_contract_pairs(&self) -> Result<ContractMatchResult, String> {
         let mut pairs = Vec::new();
         let mut unmatched_solidity = Vec::new();
         let mut unmatched_ink = Vec::new();
+        let mut read_errors = Vec::new();
 
         // Define known contract mappings
         let contract_mappings = self.get_contract_mappings();
@@ -51,11 +335,15 @@ impl ContractMatcher {
                 // Check if ink contract exists
                 let full_ink_path = format!("{}/{}", self.ink_base_path, ink_path);
                 if Path::new(&full_ink_path).exists() {
-                    // Read both contract contents
-                    let solidity_content = fs::read_to_string(&solidity_contract)
-                        .map_err(|e| format!("Failed to read Solidity contract: {}", e))?;
-                    let ink_content = fs::read_to_string(&full_ink_path)
-                        .map_err(|e| format!("Failed to read ink! contract: {}", e))?;
+                    // Read both contract contents; one bad file shouldn't stop the run
+                    let (solidity_content, ink_content) = match (read_source(&solidity_contract), read_source(&full_ink_path)) {
+                        (Ok(solidity_content), Ok(ink_content)) => (solidity_content, ink_content),
+                        (solidity, ink) => {
+                            read_errors.extend(solidity.err());
+                            read_errors.extend(ink.err());
+                            continue;
+                        }
+                    };
 
                     pairs.push(ContractPair {
                         solidity_path: solidity_contract.clone(),
@@ -88,25 +376,25 @@ impl ContractMatcher {
             pairs,
             unmatched_solidity,
             unmatched_ink,
+            read_errors,
         })
     }
 
+    /// Every `.sol` file under the sources folder, at any depth, in path order.
     fn find_solidity_contracts(&self) -> Result<Vec<String>, String> {
+        let src_path = match &self.solidity_src_dir {
+            Some(dir) => Path::new(&self.solidity_base_path).join(dir),
+            None => PathBuf::from(&self.solidity_base_path),
+        };
+
         let mut contracts = Vec::new();
-        let src_path = format!("{}/src", self.solidity_base_path);
-        
-        if let Ok(entries) = fs::read_dir(&src_path) {
-            for entry in entries {
-                if let Ok(entry) = entry {
-                    let path = entry.path();
-                    if path.extension().and_then(|s| s.to_str()) == Some("sol") {
-                        contracts.push(path.to_string_lossy().to_string());
-                    }
-                }
-            }
-        }
-        
-        Ok(contracts)
+        collect_solidity_files(&src_path, &mut contracts);
+        contracts.sort();
+
+        Ok(contracts
+            .into_iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect())
     }
 
     fn extract_contract_name(&self, file_path: &str) -> String {
@@ -118,42 +406,35 @@ impl ContractMatcher {
     }
 
     fn get_contract_mappings(&self) -> HashMap<String, String> {
-        let mut mappings = HashMap::new();
-        
-        // Token standards
-        mappings.insert("SimpleERC20".to_string(), "erc20/lib.rs".to_string());
-        mappings.insert("SimpleNFT".to_string(), "erc721/lib.rs".to_string());
-        mappings.insert("SimpleERC1155".to_string(), "erc1155/lib.rs".to_string());
-        
-        // Basic contracts
-        mappings.insert("Flipper".to_string(), "flipper/lib.rs".to_string());
-        mappings.insert("Counter".to_string(), "incrementer/lib.rs".to_string());
-        mappings.insert("SimpleStorage".to_string(), "contract-storage/lib.rs".to_string());
-        mappings.insert("MultiSigWallet".to_string(), "multisig/lib.rs".to_string());
-        mappings.insert("SimpleEscrow".to_string(), "payment-channel/lib.rs".to_string());
-        mappings.insert("EventEmitter".to_string(), "events/lib.rs".to_string());
-        
-        // Cross-contract calls
-        mappings.insert("CallerContract".to_string(), "basic-contract-caller/lib.rs".to_string());
-        mappings.insert("TargetContract".to_string(), "basic-contract-caller/other-contract/lib.rs".to_string());
-        
-        mappings
+        self.mappings
+            .iter()
+            .map(|m| (m.contract_type.clone(), m.ink_path.clone()))
+            .collect()
     }
 
     fn get_contract_description(&self, contract_name: &str) -> String {
-        match contract_name {
-            "SimpleERC20" => "ERC20 fungible token implementation with basic transfer, approve, and allowance functionality".to_string(),
-            "SimpleNFT" => "ERC721 non-fungible token implementation with minting, burning, and transfer capabilities".to_string(),
-            "SimpleERC1155" => "Multi-token standard supporting both fungible and non-fungible tokens with batch operations".to_string(),
-            "Flipper" => "Simple boolean state contract that can be flipped between true and false".to_string(),
-            "Counter" => "Basic counter contract with increment and decrement functionality".to_string(),
-            "SimpleStorage" => "Basic storage contract demonstrating state management and data persistence".to_string(),
-            "MultiSigWallet" => "Multi-signature wallet requiring multiple approvals for transactions".to_string(),
-            "SimpleEscrow" => "Escrow contract for holding funds until conditions are met".to_string(),
-            "EventEmitter" => "Contract demonstrating event emission and indexing patterns".to_string(),
-            "CallerContract" => "Contract that calls other contracts, demonstrating cross-contract interactions".to_string(),
-            "TargetContract" => "Target contract for cross-contract calls and interactions".to_string(),
-            _ => format!("Smart contract implementation: {}", contract_name),
+        self.mappings
+            .iter()
+            .find(|m| m.contract_type == contract_name)
+            .map(|m| m.description.clone())
+            .unwrap_or_else(|| format!("Smart contract implementation: {}", contract_name))
+    }
+}
+
+/// Walk `dir` recursively, collecting `.sol` files. Unreadable folders are
+/// skipped, like a missing sources folder.
+fn collect_solidity_files(dir: &Path, contracts: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // Don't follow symlinked folders, which could loop
+        let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+        if is_dir {
+            collect_solidity_files(&path, contracts);
+        } else if path.extension().and_then(|s| s.to_str()) == Some("sol") {
+            contracts.push(path);
         }
     }
 }
@@ -162,6 +443,116 @@ impl ContractMatcher {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_load_example_roots_resolves_relative_paths() {
+        let dir = std::env::temp_dir().join(format!("roots-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let manifest = dir.join("examples.json");
+        fs::write(
+            &manifest,
+            r#"{"roots": [
+                {"name": "official", "solidity_path": "solidity-examples", "ink_path": "ink-examples-main"},
+                {"name": "team", "solidity_path": "/srv/team/sol", "ink_path": "/srv/team/ink"}
+            ]}"#,
+        )
+        .unwrap();
+
+        let roots = load_example_roots(&manifest).unwrap();
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(roots.len(), 2);
+        assert_eq!(roots[0].solidity_path, dir.join("solidity-examples").to_string_lossy());
+        assert_eq!(roots[1], ExampleRoot::new("team", "/srv/team/sol".to_string(), "/srv/team/ink".to_string()));
+    }
+
+    #[test]
+    fn test_unreadable_file_is_reported_and_others_still_pair() {
+        let dir = std::env::temp_dir().join(format!("matcher-{}", uuid::Uuid::new_v4()));
+        let (solidity, ink) = (dir.join("solidity"), dir.join("ink"));
+        fs::create_dir_all(solidity.join("src")).unwrap();
+        fs::create_dir_all(ink.join("flipper")).unwrap();
+        fs::create_dir_all(ink.join("erc20")).unwrap();
+        fs::write(solidity.join("src/Flipper.sol"), "contract Flipper { bool value; }").unwrap();
+        fs::write(ink.join("flipper/lib.rs"), "#[ink::contract] mod flipper {}").unwrap();
+        // Latin-1 encoded, so not valid UTF-8
+        fs::write(solidity.join("src/SimpleERC20.sol"), b"// Caf\xe9\ncontract SimpleERC20 {}").unwrap();
+        fs::write(ink.join("erc20/lib.rs"), "#[ink::contract] mod erc20 {}").unwrap();
+
+        let matcher = ContractMatcher::new(solidity.to_string_lossy().to_string(), ink.to_string_lossy().to_string());
+        let result = matcher.find_contract_pairs().unwrap();
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(result.pairs.len(), 1);
+        assert_eq!(result.pairs[0].contract_type, "Flipper");
+        assert_eq!(result.read_errors.len(), 1);
+        assert!(result.read_errors[0].path.ends_with("SimpleERC20.sol"));
+        assert_eq!(result.read_errors[0].reason, "not valid UTF-8 (invalid byte at offset 6)");
+    }
+
+    #[test]
+    fn test_nested_solidity_files_are_found() {
+        let dir = std::env::temp_dir().join(format!("matcher-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("src/tokens/fungible")).unwrap();
+        fs::create_dir_all(dir.join("src/basic")).unwrap();
+        fs::write(dir.join("src/Flipper.sol"), "contract Flipper {}").unwrap();
+        fs::write(dir.join("src/tokens/SimpleNFT.sol"), "contract SimpleNFT {}").unwrap();
+        fs::write(dir.join("src/tokens/fungible/SimpleERC20.sol"), "contract SimpleERC20 {}").unwrap();
+        fs::write(dir.join("src/basic/Counter.sol"), "contract Counter {}").unwrap();
+        fs::write(dir.join("src/basic/README.md"), "# Basic contracts").unwrap();
+
+        let matcher = ContractMatcher::new(dir.to_string_lossy().to_string(), String::new());
+        let found = matcher.find_solidity_contracts().unwrap();
+        let names: Vec<_> = found.iter().map(|p| matcher.extract_contract_name(p)).collect();
+        assert_eq!(names, vec!["Flipper", "Counter", "SimpleNFT", "SimpleERC20"]);
+
+        // Pointing the base path straight at the contracts folder
+        let direct = ContractMatcher::new(dir.join("src").to_string_lossy().to_string(), String::new())
+            .with_solidity_src_dir(None)
+            .find_solidity_contracts()
+            .unwrap();
+        fs::remove_dir_all(&dir).ok();
+        assert_eq!(direct, found);
+    }
+
+    #[test]
+    fn test_mappings_file_replaces_built_in_pairs() {
+        let dir = std::env::temp_dir().join(format!("matcher-{}", uuid::Uuid::new_v4()));
+        let (solidity, ink) = (dir.join("solidity"), dir.join("ink"));
+        fs::create_dir_all(solidity.join("src")).unwrap();
+        fs::create_dir_all(ink.join("my-token")).unwrap();
+        fs::write(solidity.join("src/MyToken.sol"), "contract MyToken {}").unwrap();
+        fs::write(solidity.join("src/Flipper.sol"), "contract Flipper {}").unwrap();
+        fs::write(ink.join("my-token/lib.rs"), "#[ink::contract] mod my_token {}").unwrap();
+        let mappings = dir.join("mappings.json");
+        fs::write(
+            &mappings,
+            r#"{"mappings": [{"contract_type": "MyToken", "ink_path": "my-token/lib.rs", "description": "Team token with a capped supply"}]}"#,
+        )
+        .unwrap();
+        let (solidity, ink) = (solidity.to_string_lossy().to_string(), ink.to_string_lossy().to_string());
+
+        let matcher = ContractMatcher::with_mappings_file(solidity.clone(), ink.clone(), &mappings).unwrap();
+        let result = matcher.find_contract_pairs().unwrap();
+        assert_eq!(result.pairs.len(), 1);
+        assert_eq!(result.pairs[0].contract_type, "MyToken");
+        assert_eq!(result.pairs[0].description, "Team token with a capped supply");
+        // Flipper is only in the built-in mappings
+        assert!(result.unmatched_solidity[0].ends_with("Flipper.sol"));
+
+        fs::write(
+            &mappings,
+            r#"{"mappings": [
+                {"contract_type": "MyToken", "ink_path": "my-token/lib.rs", "description": ""},
+                {"contract_type": "Vault", "ink_path": "vault/lib.rs", "description": ""},
+                {"contract_type": "Staking", "ink_path": "staking/lib.rs", "description": ""}
+            ]}"#,
+        )
+        .unwrap();
+        let error = ContractMatcher::with_mappings_file(solidity, ink.clone(), &mappings).err().unwrap();
+        fs::remove_dir_all(&dir).ok();
+        assert!(error.ends_with(&format!("name ink! paths missing under {}: vault/lib.rs, staking/lib.rs", ink)));
+    }
+
     #[test]
     fn test_contract_matcher_creation() {
         let matcher = ContractMatcher::new(
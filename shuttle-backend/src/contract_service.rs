@@ -1,6 +1,5 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
 use subxt::{
     client::OnlineClient,
     config::SubstrateConfig,
@@ -10,6 +9,8 @@ use tracing::info;
 use std::sync::Mutex;
 use std::collections::HashMap;
 
+use crate::network_config::NetworkConfig;
+
 // Contract metadata and types
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ContractStrategy {
@@ -25,24 +26,53 @@ pub struct ContractStrategy {
     pub updated_at: u64,
 }
 
+/// A strategy with its balance rendered for display
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ContractStrategyDetails {
+    #[serde(flatten)]
+    pub strategy: ContractStrategy,
+    pub formatted_balance: String,
+}
+
+/// How much a user has invested in one strategy
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ContractInvestment {
+    pub user_address: String,
+    pub strategy_id: u32,
+    /// Amount in planck
+    pub amount: u128,
+    pub formatted_amount: String,
+    /// True when the amount came from the offline mock, not the chain
+    pub mock: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateStrategyParams {
     pub name: String,
     pub risk_level: u8,
     pub parameters: String,
     pub initial_investment: Option<u128>,
+    /// Account the call is signed for; defaults to the caller's tenant
+    #[serde(default)]
+    pub account: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct InvestmentParams {
     pub strategy_id: u32,
     pub amount: u128,
+    /// Account the call is signed for; defaults to the caller's tenant
+    #[serde(default)]
+    pub account: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WithdrawParams {
     pub strategy_id: u32,
     pub amount: u128,
+    /// Account the call is signed for; defaults to the caller's tenant
+    #[serde(default)]
+    pub account: Option<String>,
 }
 
 pub struct ContractService {
@@ -58,18 +88,16 @@ pub struct ContractService {
 }
 
 impl ContractService {
+    #[allow(dead_code)]
     pub async fn new() -> Result<Self> {
-        // Try multiple RPC endpoints in order of preference
-        let rpc_endpoints = vec![
-            "wss://rococo-contracts-rpc.polkadot.io",
-            "wss://rpc.polkadot.io",
-            "wss://kusama-rpc.polkadot.io",
-        ];
+        Self::connect(&NetworkConfig::default()).await
+    }
 
+    /// Connect to the first of `network`'s RPC endpoints that answers,
+    /// falling back to offline mode (no client) if none can be reached.
+    pub async fn connect(network: &NetworkConfig) -> Result<Self> {
         let mut client = None;
-        let mut last_error = None;
-
-        for endpoint in rpc_endpoints {
+        for endpoint in &network.rpc_urls {
             match OnlineClient::<SubstrateConfig>::from_url(endpoint).await {
                 Ok(c) => {
                     info!("Successfully connected to RPC endpoint: {}", endpoint);
@@ -78,52 +106,41 @@ impl ContractService {
                 }
                 Err(e) => {
                     info!("Failed to connect to {}: {}", endpoint, e);
-                    last_error = Some(e);
-                    continue;
                 }
             }
         }
 
-        // These would be the actual deployed contract addresses
-        let strategy_manager_address = AccountId32::from_str(
-            "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
-        )?;
-        
-        let dynavest_strategy_address = AccountId32::from_str(
-            "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty"
-        )?;
-
-        Ok(Self {
-            client,
-            strategy_manager_address,
-            dynavest_strategy_address,
-            mock_strategies: Mutex::new(HashMap::new()),
-            next_strategy_id: Mutex::new(1),
-        })
+        Self::with_client(client, network)
     }
 
+    #[allow(dead_code)]
     pub async fn new_mock() -> Result<Self> {
+        Self::new_mock_for(&NetworkConfig::default()).await
+    }
+
+    /// A mock service using `network`'s contract addresses.
+    pub async fn new_mock_for(network: &NetworkConfig) -> Result<Self> {
         // Create a mock client that doesn't require network connection
         // This will allow the service to start even if RPC endpoints are down
         info!("Creating mock ContractService for offline operation");
-        
-        let strategy_manager_address = AccountId32::from_str(
-            "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
-        )?;
-        
-        let dynavest_strategy_address = AccountId32::from_str(
-            "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty"
-        )?;
+        Self::with_client(None, network)
+    }
 
+    fn with_client(client: Option<OnlineClient<SubstrateConfig>>, network: &NetworkConfig) -> Result<Self> {
         Ok(Self {
-            client: None, // No client for mock mode
-            strategy_manager_address,
-            dynavest_strategy_address,
+            client,
+            strategy_manager_address: network.strategy_manager().map_err(|e| anyhow::anyhow!(e))?,
+            dynavest_strategy_address: network.dynavest_strategy().map_err(|e| anyhow::anyhow!(e))?,
             mock_strategies: Mutex::new(HashMap::new()),
             next_strategy_id: Mutex::new(1),
         })
     }
 
+    /// True when there's no chain client and calls are simulated.
+    pub fn is_mock(&self) -> bool {
+        self.client.is_none()
+    }
+
     pub async fn create_strategy_on_chain(
         &self,
         user_account: &str,
@@ -198,7 +215,6 @@ impl ContractService {
         Ok(strategies)
     }
 
-    #[allow(dead_code)]
     pub async fn get_strategy_details(&self, strategy_id: u32) -> Result<Option<ContractStrategy>> {
         info!("Getting details for strategy: {}", strategy_id);
 
@@ -255,7 +271,6 @@ impl ContractService {
         Ok(tx_hash)
     }
 
-    #[allow(dead_code)]
     pub async fn get_user_investment(&self, user_account: &str, strategy_id: u32) -> Result<u128> {
         info!("Getting investment for user {} in strategy {}", user_account, strategy_id);
 
@@ -321,7 +336,7 @@ impl ContractService {
         // Store in mock storage
         {
             let mut strategies = self.mock_strategies.lock().unwrap();
-            let user_strategies = strategies.entry("mock_user".to_string()).or_insert_with(Vec::new);
+            let user_strategies = strategies.entry("mock_user".to_string()).or_default();
             user_strategies.push(strategy);
         }
         
@@ -380,7 +395,6 @@ impl ContractService {
         ])
     }
 
-    #[allow(dead_code)]
     async fn mock_get_strategy_details(&self, strategy_id: u32) -> Result<Option<ContractStrategy>> {
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
         
@@ -425,7 +439,6 @@ impl ContractService {
         ))
     }
 
-    #[allow(dead_code)]
     async fn mock_get_user_investment(&self, _user_account: &str, _strategy_id: u32) -> Result<u128> {
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
         
@@ -476,7 +489,6 @@ impl ContractService {
         Ok(())
     }
 
-    #[allow(dead_code)]
     pub fn format_balance_for_display(balance: u128) -> String {
         // Convert from planck to DOT (assuming 12 decimal places)
         let dot_balance = balance as f64 / 1_000_000_000_000.0;
@@ -501,6 +513,7 @@ mod tests {
             risk_level: 5,
             parameters: "{}".to_string(),
             initial_investment: Some(1000000000000),
+            account: None,
         };
         
         assert!(ContractService::validate_strategy_params(&valid_params).is_ok());
@@ -510,6 +523,7 @@ mod tests {
             risk_level: 11,
             parameters: "".to_string(),
             initial_investment: None,
+            account: None,
         };
         
         assert!(ContractService::validate_strategy_params(&invalid_params).is_err());
@@ -520,6 +534,7 @@ mod tests {
         let valid_params = InvestmentParams {
             strategy_id: 1,
             amount: 1000000000000,
+            account: None,
         };
         
         assert!(ContractService::validate_investment_params(&valid_params).is_ok());
@@ -527,6 +542,7 @@ mod tests {
         let invalid_params = InvestmentParams {
             strategy_id: 1,
             amount: 0,
+            account: None,
         };
         
         assert!(ContractService::validate_investment_params(&invalid_params).is_err());
@@ -554,6 +570,15 @@ mod tests {
         assert!(ContractService::parse_strategy_parameters(invalid_json).is_err());
     }
 
+    #[tokio::test]
+    async fn test_connect_tries_every_endpoint_before_going_offline() {
+        let network = NetworkConfig {
+            rpc_urls: vec!["ws://127.0.0.1:9".to_string(), "ws://127.0.0.1:10".to_string()],
+            ..NetworkConfig::default()
+        };
+        assert!(ContractService::connect(&network).await.unwrap().is_mock());
+    }
+
     #[tokio::test]
     async fn test_mock_create_strategy() {
         let service = ContractService::new().await.unwrap();
@@ -563,6 +588,7 @@ mod tests {
             risk_level: 5,
             parameters: "{}".to_string(),
             initial_investment: Some(1000000000000),
+            account: None,
         };
         
         let strategy_id = service.mock_create_strategy(params).await.unwrap();
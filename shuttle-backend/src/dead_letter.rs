@@ -0,0 +1,206 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use std::future::Future;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::rag_system::RAGSystem;
+use crate::training_embedder::content_hash;
+
+/// An embedding that failed, kept with everything needed to retry it.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct FailedEmbedding {
+    pub id: Uuid,
+    /// Where the content came from, e.g. `training:official/SimpleERC20`
+    pub source_id: String,
+    pub content_hash: String,
+    pub content: String,
+    pub metadata: Json<HashMap<String, String>>,
+    pub error: String,
+    pub attempts: i32,
+    pub failed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetryReport {
+    pub retried: usize,
+    pub succeeded: usize,
+    pub document_ids: Vec<String>,
+    /// Source ids that failed again and remain in the log
+    pub still_failing: Vec<String>,
+}
+
+/// Source id for a bulk-inserted document, named by its `title` or
+/// `source` metadata.
+pub fn document_source_id(metadata: &HashMap<String, String>) -> String {
+    let name = metadata
+        .get("title")
+        .or_else(|| metadata.get("source"))
+        .map(|s| s.as_str())
+        .unwrap_or("unknown");
+    format!("document:{}", name)
+}
+
+/// Durable record of failed embeddings in the `failed_embeddings` table.
+#[derive(Debug, Clone)]
+pub struct DeadLetterLog {
+    db: PgPool,
+}
+
+impl DeadLetterLog {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    pub async fn migrate(db: &PgPool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS failed_embeddings (
+                id UUID PRIMARY KEY,
+                source_id TEXT NOT NULL,
+                content_hash TEXT NOT NULL,
+                content TEXT NOT NULL,
+                metadata JSONB NOT NULL,
+                error TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 1,
+                failed_at TIMESTAMP WITH TIME ZONE NOT NULL,
+                UNIQUE (source_id, content_hash)
+            )
+            "#,
+        )
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record a failure; repeated failures of the same content bump `attempts`.
+    pub async fn record(
+        &self,
+        source_id: &str,
+        content: &str,
+        metadata: &HashMap<String, String>,
+        error: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO failed_embeddings (id, source_id, content_hash, content, metadata, error, attempts, failed_at)
+            VALUES ($1, $2, $3, $4, $5, $6, 1, $7)
+            ON CONFLICT (source_id, content_hash) DO UPDATE
+            SET error = EXCLUDED.error,
+                attempts = failed_embeddings.attempts + 1,
+                failed_at = EXCLUDED.failed_at
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(source_id)
+        .bind(format!("{:016x}", content_hash(content)))
+        .bind(content)
+        .bind(Json(metadata))
+        .bind(error)
+        .bind(Utc::now())
+        .execute(&self.db)
+        .await?;
+
+        warn!("Recorded failed embedding for {}: {}", source_id, error);
+        Ok(())
+    }
+
+    pub async fn list(&self) -> Result<Vec<FailedEmbedding>, sqlx::Error> {
+        sqlx::query_as::<_, FailedEmbedding>("SELECT * FROM failed_embeddings ORDER BY failed_at ASC")
+            .fetch_all(&self.db)
+            .await
+    }
+
+    /// Re-embed every logged failure into the RAG system.
+    pub async fn retry(&self, rag_system: &RAGSystem) -> Result<RetryReport> {
+        self.retry_with(|failed| async move {
+            rag_system.add_document(&failed.content, failed.metadata.0).await
+        })
+        .await
+    }
+
+    /// Run `embed` for each logged failure, clearing the ones that succeed.
+    pub async fn retry_with<F, Fut>(&self, mut embed: F) -> Result<RetryReport>
+    where
+        F: FnMut(FailedEmbedding) -> Fut,
+        Fut: Future<Output = Result<String>>,
+    {
+        let mut report = RetryReport::default();
+
+        for failed in self.list().await? {
+            report.retried += 1;
+            let (id, source_id, content, metadata) =
+                (failed.id, failed.source_id.clone(), failed.content.clone(), failed.metadata.0.clone());
+
+            match embed(failed).await {
+                Ok(document_id) => {
+                    sqlx::query("DELETE FROM failed_embeddings WHERE id = $1")
+                        .bind(id)
+                        .execute(&self.db)
+                        .await?;
+                    report.succeeded += 1;
+                    report.document_ids.push(document_id);
+                }
+                Err(e) => {
+                    self.record(&source_id, &content, &metadata, &e.to_string()).await?;
+                    report.still_failing.push(source_id);
+                }
+            }
+        }
+
+        info!("Retried {} failed embeddings, {} succeeded", report.retried, report.succeeded);
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qdrant_client::Qdrant;
+
+    #[test]
+    fn test_document_source_id_prefers_title() {
+        let metadata = |pairs: &[(&str, &str)]| {
+            pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<HashMap<_, _>>()
+        };
+        assert_eq!(document_source_id(&metadata(&[("title", "Flipper"), ("source", "ink")])), "document:Flipper");
+        assert_eq!(document_source_id(&metadata(&[("source", "ink")])), "document:ink");
+        assert_eq!(document_source_id(&HashMap::new()), "document:unknown");
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a scratch Postgres database at TEST_DATABASE_URL"]
+    async fn test_failed_embedding_is_logged_and_cleared_on_retry() {
+        let database_url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL");
+        let db = PgPool::connect(&database_url).await.unwrap();
+        DeadLetterLog::migrate(&db).await.unwrap();
+        let log = DeadLetterLog::new(db);
+
+        // Nothing listens on this port, so the upsert fails
+        let rag = RAGSystem::new(Qdrant::from_url("http://127.0.0.1:1").build().unwrap(), "test-key".to_string())
+            .with_dead_letters(log.clone());
+
+        let title = Uuid::new_v4().to_string();
+        let metadata = HashMap::from([("title".to_string(), title.clone())]);
+        let inserted = rag.bulk_insert_documents(vec![("flipper docs".to_string(), metadata)]).await.unwrap();
+        assert!(inserted.is_empty());
+
+        let source_id = format!("document:{}", title);
+        let logged = log.list().await.unwrap();
+        let row = logged.iter().find(|f| f.source_id == source_id).unwrap();
+        assert_eq!(row.content, "flipper docs");
+        assert!(!row.error.is_empty());
+
+        let report = log
+            .retry_with(|failed| async move { Ok(format!("doc-{}", failed.source_id)) })
+            .await
+            .unwrap();
+        assert!(report.document_ids.contains(&format!("doc-{}", source_id)));
+        assert!(log.list().await.unwrap().iter().all(|f| f.source_id != source_id));
+    }
+}
@@ -1,10 +1,13 @@
-use crate::chat::ChatService;
+use crate::chat::{ChatResponse, ChatService};
 use crate::polkadot::PolkadotClient;
+use crate::prompts::{render, Prompt};
+use crate::retry::{parse_retry_after, RetryPolicy};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tracing::{info, warn};
 use utoipa::ToSchema;
 
@@ -12,6 +15,12 @@ use utoipa::ToSchema;
 pub struct DefiInfoRequest {
     pub input_text: String,
     pub user_address: Option<String>,
+    /// `low`, `medium` or `high`; overrides any risk mentioned in `input_text`
+    #[serde(default)]
+    pub risk_level: Option<String>,
+    /// Amount to invest in USD; overrides any amount mentioned in `input_text`
+    #[serde(default)]
+    pub amount: Option<f64>,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -39,7 +48,15 @@ pub struct StrategyData {
     pub protocols: Vec<String>,
 }
 
+/// Chat reply plus any strategy recommendation found in it
 #[derive(Debug, Serialize)]
+pub struct StructuredChatResponse {
+    #[serde(flatten)]
+    pub chat: ChatResponse,
+    pub strategy: Option<StrategyData>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CryptoPriceData {
     pub symbol: String,
     pub price_usd: f64,
@@ -84,10 +101,394 @@ pub struct PerformanceMetrics {
     pub sharpe_ratio: Option<f64>,
 }
 
+/// Error returned once CoinGecko calls have exhausted their retries
+#[derive(Debug)]
+pub enum CoinGeckoError {
+    RateLimited { attempts: u32 },
+    Http { status: u16, attempts: u32 },
+    Transport { attempts: u32, message: String },
+    Decode(String),
+}
+
+impl std::fmt::Display for CoinGeckoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CoinGeckoError::RateLimited { attempts } => {
+                write!(f, "CoinGecko rate limit still exceeded after {} attempts", attempts)
+            }
+            CoinGeckoError::Http { status, attempts } => {
+                write!(f, "CoinGecko returned HTTP {} after {} attempts", status, attempts)
+            }
+            CoinGeckoError::Transport { attempts, message } => {
+                write!(f, "CoinGecko request failed after {} attempts: {}", attempts, message)
+            }
+            CoinGeckoError::Decode(message) => {
+                write!(f, "Failed to decode CoinGecko response: {}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CoinGeckoError {}
+
+/// GET a CoinGecko URL, retrying 429s and 5xx responses with backoff.
+///
+/// A `Retry-After` header on the failed response overrides the computed delay.
+pub async fn fetch_coingecko_json(
+    client: &reqwest::Client,
+    url: &str,
+    policy: &RetryPolicy,
+) -> std::result::Result<serde_json::Value, CoinGeckoError> {
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+
+        let retry_after = match client.get(url).send().await {
+            Ok(response) if response.status().is_success() => {
+                return response
+                    .json::<serde_json::Value>()
+                    .await
+                    .map_err(|e| CoinGeckoError::Decode(e.to_string()));
+            }
+            Ok(response) => {
+                let status = response.status();
+                let rate_limited = status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+
+                if !(rate_limited || status.is_server_error()) || attempt >= policy.max_attempts {
+                    return Err(if rate_limited {
+                        CoinGeckoError::RateLimited { attempts: attempt }
+                    } else {
+                        CoinGeckoError::Http { status: status.as_u16(), attempts: attempt }
+                    });
+                }
+
+                response
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(parse_retry_after)
+            }
+            Err(e) => {
+                if attempt >= policy.max_attempts {
+                    return Err(CoinGeckoError::Transport {
+                        attempts: attempt,
+                        message: e.to_string(),
+                    });
+                }
+                None
+            }
+        };
+
+        let delay = policy.delay_for(attempt, retry_after);
+        warn!(
+            "CoinGecko request failed (attempt {}/{}), retrying in {:?}",
+            attempt, policy.max_attempts, delay
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Where `get_crypto_prices` gets its data from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PriceSource {
+    /// Query CoinGecko, falling back to the last known price on failure
+    Live,
+    /// Serve only previously cached prices, never touching the network
+    CachedOnly,
+    /// Deterministic fixtures for known symbols
+    Mock,
+}
+
+impl PriceSource {
+    /// Read from `PRICE_SOURCE` (`live`, `cached` or `mock`), defaulting to `live`.
+    pub fn from_env() -> Self {
+        match std::env::var("PRICE_SOURCE").unwrap_or_default().to_lowercase().as_str() {
+            "cached" | "cached_only" => PriceSource::CachedOnly,
+            "mock" => PriceSource::Mock,
+            _ => PriceSource::Live,
+        }
+    }
+}
+
+/// How long live prices for a set of coins are reused before CoinGecko is asked again
+pub const PRICE_BATCH_TTL: Duration = Duration::from_secs(60);
+
+/// Live prices fetched together, and when
+type PriceBatch = (Instant, Vec<CryptoPriceData>);
+
+/// Price lookups by CoinGecko coin id, backed by the configured `PriceSource`.
+pub struct PriceFeed {
+    source: PriceSource,
+    http_client: reqwest::Client,
+    coingecko_base_url: String,
+    retry_policy: RetryPolicy,
+    cache: Mutex<HashMap<String, CryptoPriceData>>,
+    /// Recent live batches, keyed by their sorted, deduplicated coin ids
+    batches: Mutex<HashMap<Vec<String>, PriceBatch>>,
+}
+
+impl PriceFeed {
+    pub fn new(source: PriceSource) -> Self {
+        let feed = Self {
+            source,
+            http_client: reqwest::Client::new(),
+            coingecko_base_url: std::env::var("COINGECKO_API_URL")
+                .unwrap_or_else(|_| "https://api.coingecko.com/api/v3".to_string()),
+            retry_policy: RetryPolicy::default(),
+            cache: Mutex::new(HashMap::new()),
+            batches: Mutex::new(HashMap::new()),
+        };
+
+        // Offline runs can seed the cache from a JSON array of CryptoPriceData
+        if let Ok(path) = std::env::var("PRICE_CACHE_FILE") {
+            match std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|raw| serde_json::from_str::<Vec<CryptoPriceData>>(&raw).map_err(anyhow::Error::from))
+            {
+                Ok(prices) => feed.seed(prices),
+                Err(e) => warn!("Failed to load price cache from {}: {}", path, e),
+            }
+        }
+
+        feed
+    }
+
+    #[allow(dead_code)]
+    pub fn with_base_url(mut self, coingecko_base_url: String) -> Self {
+        self.coingecko_base_url = coingecko_base_url;
+        self
+    }
+
+    /// Add prices to the cache, keyed by their symbol.
+    pub fn seed(&self, prices: Vec<CryptoPriceData>) {
+        let mut cache = self.cache.lock().unwrap();
+        for price in prices {
+            cache.insert(price.symbol.clone(), price);
+        }
+    }
+
+    pub async fn get_price(&self, coin_id: &str) -> Result<CryptoPriceData> {
+        let symbol = coin_id.to_uppercase();
+        let cached = || self.cache.lock().unwrap().get(&symbol).cloned();
+
+        match self.source {
+            PriceSource::Mock => {
+                mock_price(coin_id).ok_or_else(|| anyhow::anyhow!("No mock price for {}", coin_id))
+            }
+            PriceSource::CachedOnly => {
+                cached().ok_or_else(|| anyhow::anyhow!("No cached price for {}", coin_id))
+            }
+            PriceSource::Live => match self.fetch_price_from_coingecko(coin_id).await {
+                Ok(price) => {
+                    self.cache.lock().unwrap().insert(symbol.clone(), price.clone());
+                    Ok(price)
+                }
+                Err(e) => cached().ok_or(e),
+            },
+        }
+    }
+
+    /// Prices for `coin_ids` in the order given, leaving out any without one.
+    ///
+    /// Live prices come from a single CoinGecko request for the whole set,
+    /// reused for `PRICE_BATCH_TTL` when the same coins are asked for again.
+    /// If that request fails, the last known price of each coin is served.
+    pub async fn get_prices(&self, coin_ids: &[&str]) -> Vec<CryptoPriceData> {
+        if coin_ids.is_empty() {
+            return Vec::new();
+        }
+        if self.source != PriceSource::Live {
+            let mut prices = Vec::new();
+            for coin_id in coin_ids {
+                match self.get_price(coin_id).await {
+                    Ok(price) => prices.push(price),
+                    Err(e) => warn!("Failed to fetch price for {}: {}", coin_id, e),
+                }
+            }
+            return prices;
+        }
+
+        let mut key: Vec<String> = coin_ids.iter().map(|id| id.to_string()).collect();
+        key.sort();
+        key.dedup();
+        if let Some((fetched_at, prices)) = self.batches.lock().unwrap().get(&key) {
+            if fetched_at.elapsed() < PRICE_BATCH_TTL {
+                return in_order(coin_ids, prices);
+            }
+        }
+
+        match self.fetch_prices_from_coingecko(&key).await {
+            Ok(prices) => {
+                self.seed(prices.clone());
+                let ordered = in_order(coin_ids, &prices);
+                self.batches.lock().unwrap().insert(key, (Instant::now(), prices));
+                ordered
+            }
+            Err(e) => {
+                warn!("Failed to fetch prices for {}: {}", key.join(","), e);
+                let cache = self.cache.lock().unwrap();
+                coin_ids.iter().filter_map(|id| cache.get(&id.to_uppercase()).cloned()).collect()
+            }
+        }
+    }
+
+    async fn fetch_price_from_coingecko(&self, coin_id: &str) -> Result<CryptoPriceData> {
+        self.fetch_prices_from_coingecko(&[coin_id.to_string()])
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("Price data not found for {}", coin_id))
+    }
+
+    async fn fetch_prices_from_coingecko(&self, coin_ids: &[String]) -> Result<Vec<CryptoPriceData>> {
+        let url = format!(
+            "{}/simple/price?ids={}&vs_currencies=usd&include_24hr_change=true&include_market_cap=true&include_24hr_vol=true",
+            self.coingecko_base_url,
+            coin_ids.join(",")
+        );
+
+        let data = fetch_coingecko_json(&self.http_client, &url, &self.retry_policy).await?;
+        let coin_ids: Vec<&str> = coin_ids.iter().map(String::as_str).collect();
+        Ok(parse_simple_prices(&data, &coin_ids))
+    }
+}
+
+/// Prices for each of `coin_ids` found in a `/simple/price` response, in
+/// the order given. Coins missing from the response are skipped.
+pub fn parse_simple_prices(data: &serde_json::Value, coin_ids: &[&str]) -> Vec<CryptoPriceData> {
+    let last_updated = chrono::Utc::now().to_rfc3339();
+    coin_ids
+        .iter()
+        .filter_map(|coin_id| {
+            let coin_data = data.get(*coin_id)?;
+            Some(CryptoPriceData {
+                symbol: coin_id.to_uppercase(),
+                price_usd: coin_data["usd"].as_f64().unwrap_or(0.0),
+                change_24h: coin_data["usd_24h_change"].as_f64().unwrap_or(0.0),
+                market_cap: coin_data["usd_market_cap"].as_f64(),
+                volume_24h: coin_data["usd_24h_vol"].as_f64(),
+                last_updated: last_updated.clone(),
+            })
+        })
+        .collect()
+}
+
+/// `prices` rearranged to follow `coin_ids`.
+fn in_order(coin_ids: &[&str], prices: &[CryptoPriceData]) -> Vec<CryptoPriceData> {
+    coin_ids
+        .iter()
+        .filter_map(|id| prices.iter().find(|p| p.symbol == id.to_uppercase()).cloned())
+        .collect()
+}
+
+/// Fixed fixture prices used by `PriceSource::Mock`.
+fn mock_price(coin_id: &str) -> Option<CryptoPriceData> {
+    let (price_usd, change_24h, market_cap) = match coin_id {
+        "bitcoin" => (65_000.0, 1.5, 1_280_000_000_000.0),
+        "ethereum" => (3_500.0, 2.1, 420_000_000_000.0),
+        "polkadot" => (7.5, -0.8, 10_500_000_000.0),
+        "usd-coin" => (1.0, 0.0, 33_000_000_000.0),
+        "tether" => (1.0, 0.0, 110_000_000_000.0),
+        "binancecoin" => (600.0, 0.4, 88_000_000_000.0),
+        "cardano" => (0.45, -1.2, 16_000_000_000.0),
+        "solana" => (150.0, 3.4, 68_000_000_000.0),
+        "avalanche-2" => (35.0, 1.1, 14_000_000_000.0),
+        "matic-network" => (0.7, -0.5, 6_500_000_000.0),
+        _ => return None,
+    };
+
+    Some(CryptoPriceData {
+        symbol: coin_id.to_uppercase(),
+        price_usd,
+        change_24h,
+        market_cap: Some(market_cap),
+        volume_24h: Some(market_cap / 50.0),
+        last_updated: "2024-01-01T00:00:00+00:00".to_string(),
+    })
+}
+
+/// Risk level and amount used for a Polkadot strategy when the request
+/// doesn't give them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolkadotStrategyDefaults {
+    pub risk_level: String,
+    pub amount: f64,
+}
+
+impl Default for PolkadotStrategyDefaults {
+    fn default() -> Self {
+        Self {
+            risk_level: "medium".to_string(),
+            amount: 10_000.0,
+        }
+    }
+}
+
+impl PolkadotStrategyDefaults {
+    /// Read `POLKADOT_DEFAULT_RISK` (`low`, `medium` or `high`) and
+    /// `POLKADOT_DEFAULT_AMOUNT`, falling back to medium and 10000.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            risk_level: std::env::var("POLKADOT_DEFAULT_RISK")
+                .ok()
+                .map(|v| v.trim().to_lowercase())
+                .filter(|v| risk_score(v).is_some())
+                .unwrap_or(defaults.risk_level),
+            amount: std::env::var("POLKADOT_DEFAULT_AMOUNT")
+                .ok()
+                .and_then(|v| v.trim().parse().ok())
+                .filter(|&amount: &f64| amount > 0.0)
+                .unwrap_or(defaults.amount),
+        }
+    }
+}
+
+/// Position on the 1-10 scale used by `get_polkadot_strategy_recommendation`.
+fn risk_score(risk_level: &str) -> Option<u8> {
+    match risk_level {
+        "low" => Some(2),
+        "medium" => Some(5),
+        "high" => Some(8),
+        _ => None,
+    }
+}
+
+/// Risk level named in free text, if any.
+fn risk_level_in(text: &str) -> Option<&'static str> {
+    let text_lower = text.to_lowercase();
+    if text_lower.contains("high risk") || text_lower.contains("aggressive") {
+        Some("high")
+    } else if text_lower.contains("low risk") || text_lower.contains("conservative") {
+        Some("low")
+    } else if text_lower.contains("medium risk") || text_lower.contains("moderate") {
+        Some("medium")
+    } else {
+        None
+    }
+}
+
+/// Investment amount in free text: a number after `$`, `for` or `invest`, or
+/// before `usd`/`dollars`, with an optional `k` suffix.
+fn amount_in(text: &str) -> Option<f64> {
+    let amount_re = regex::Regex::new(
+        r"(?i)(?:\$\s*|\bfor\s+\$?|\binvest(?:ing)?\s+\$?)(\d[\d,]*(?:\.\d+)?)\s*(k\b)?|(\d[\d,]*(?:\.\d+)?)\s*(k\b)?\s*(?:usd|dollars)\b",
+    )
+    .ok()?;
+    let captures = amount_re.captures(text)?;
+    let number = captures.get(1).or_else(|| captures.get(3))?;
+    let thousands = captures.get(2).or_else(|| captures.get(4)).is_some();
+
+    let amount: f64 = number.as_str().replace(',', "").parse().ok()?;
+    Some(if thousands { amount * 1_000.0 } else { amount })
+}
+
 pub struct DefiService {
     chat_service: Arc<ChatService>,
     polkadot_client: Arc<PolkadotClient>,
     db: PgPool,
+    price_feed: PriceFeed,
+    polkadot_defaults: PolkadotStrategyDefaults,
 }
 
 impl DefiService {
@@ -100,9 +501,22 @@ impl DefiService {
             chat_service,
             polkadot_client,
             db,
+            price_feed: PriceFeed::new(PriceSource::Live),
+            polkadot_defaults: PolkadotStrategyDefaults::from_env(),
         }
     }
 
+    #[allow(dead_code)]
+    pub fn with_polkadot_defaults(mut self, polkadot_defaults: PolkadotStrategyDefaults) -> Self {
+        self.polkadot_defaults = polkadot_defaults;
+        self
+    }
+
+    pub fn with_price_source(mut self, source: PriceSource) -> Self {
+        self.price_feed = PriceFeed::new(source);
+        self
+    }
+
     pub async fn handle_defi_info(&self, request: DefiInfoRequest) -> Result<DefiResponse> {
         info!("Processing DeFi info request: {}", request.input_text);
 
@@ -125,14 +539,7 @@ impl DefiService {
 
     async fn classify_intent(&self, input_text: &str) -> Result<String> {
         // Use the existing chat service to classify intent
-        let classification_prompt = format!(
-            "Classify the following user input into one of these categories: strategies, analyze_portfolio, build_portfolio, question, price_check. 
-            
-            Input: {}
-            
-            Return only the category name.", 
-            input_text
-        );
+        let classification_prompt = render(Prompt::Classification, &[("input", input_text)]);
 
         let response = self.chat_service.generate_response(&classification_prompt, &[]).await
             .map_err(|e| anyhow::anyhow!("Chat service error: {}", e))?;
@@ -157,14 +564,32 @@ impl DefiService {
            input_lower.contains("acala") || input_lower.contains("bifrost") || 
            input_lower.contains("hydradx") {
             
+            // Request fields win over what the text says; defaults fill the rest
+            let risk_level = request
+                .risk_level
+                .as_deref()
+                .map(|r| r.trim().to_lowercase())
+                .filter(|r| risk_score(r).is_some())
+                .or_else(|| risk_level_in(&request.input_text).map(str::to_string))
+                .unwrap_or_else(|| self.polkadot_defaults.risk_level.clone());
+            let amount = request
+                .amount
+                .filter(|&amount| amount > 0.0)
+                .or_else(|| amount_in(&request.input_text))
+                .unwrap_or(self.polkadot_defaults.amount);
+
             // Use our Polkadot knowledge to generate strategy
-            let strategy_recommendation = crate::polkadot_defi_knowledge::get_polkadot_strategy_recommendation(5, 10000.0);
+            let strategy_recommendation = crate::polkadot_defi_knowledge::get_polkadot_strategy_recommendation(
+                risk_score(&risk_level).unwrap_or(5),
+                amount,
+            );
             
             return Ok(DefiResponse {
                 response_type: "strategies".to_string(),
                 data: serde_json::json!({
                     "answer": strategy_recommendation,
-                    "risk_level": "medium",
+                    "risk_level": risk_level,
+                    "amount": amount,
                     "chain": "Polkadot",
                     "strategies": [
                         {
@@ -203,20 +628,7 @@ impl DefiService {
         }
 
         // Generate strategy using AI for non-Polkadot queries
-        let strategy_prompt = format!(
-            "Based on the user request: '{}', generate a DeFi strategy. 
-            
-            Return a JSON object with:
-            - name: strategy name
-            - risk_level: low, medium, or high
-            - chain: preferred blockchain (Polkadot, Ethereum, Base, etc.)
-            - parameters: detailed strategy parameters
-            - recommended_amount: suggested investment amount
-            - protocols: list of DeFi protocols to use
-            
-            Focus on Polkadot ecosystem when possible.",
-            request.input_text
-        );
+        let strategy_prompt = render(Prompt::Strategy, &[("request", request.input_text.as_str())]);
 
         let ai_response = self.chat_service.generate_response(&strategy_prompt, &[]).await
             .map_err(|e| anyhow::anyhow!("Chat service error: {}", e))?;
@@ -270,19 +682,7 @@ impl DefiService {
     async fn handle_portfolio_building(&self, request: &DefiInfoRequest) -> Result<DefiResponse> {
         info!("Handling portfolio building request");
 
-        let portfolio_prompt = format!(
-            "Based on the user request: '{}', suggest a complete DeFi portfolio strategy.
-            
-            Return suggestions for:
-            - Asset allocation percentages
-            - Risk distribution (low/medium/high)
-            - Recommended protocols
-            - Diversification strategy
-            - Rebalancing schedule
-            
-            Focus on Polkadot ecosystem opportunities.",
-            request.input_text
-        );
+        let portfolio_prompt = render(Prompt::Portfolio, &[("request", request.input_text.as_str())]);
 
         let ai_response = self.chat_service.generate_response(&portfolio_prompt, &[]).await
             .map_err(|e| anyhow::anyhow!("Chat service error: {}", e))?;
@@ -330,8 +730,6 @@ impl DefiService {
     }
 
     pub async fn get_crypto_prices(&self, tokens: &[String]) -> Result<Vec<CryptoPriceData>> {
-        let mut prices = Vec::new();
-        
         // Map of token symbols to CoinGecko IDs
         let token_map: HashMap<&str, &str> = [
             ("BTC", "bitcoin"),
@@ -346,40 +744,12 @@ impl DefiService {
             ("MATIC", "matic-network"),
         ].iter().cloned().collect();
 
-        for token in tokens {
-            if let Some(coin_id) = token_map.get(token.to_uppercase().as_str()) {
-                match self.fetch_price_from_coingecko(coin_id).await {
-                    Ok(price_data) => prices.push(price_data),
-                    Err(e) => warn!("Failed to fetch price for {}: {}", token, e),
-                }
-            }
-        }
-
-        Ok(prices)
-    }
-
-    async fn fetch_price_from_coingecko(&self, coin_id: &str) -> Result<CryptoPriceData> {
-        let url = format!(
-            "https://api.coingecko.com/api/v3/simple/price?ids={}&vs_currencies=usd&include_24hr_change=true&include_market_cap=true&include_24hr_vol=true",
-            coin_id
-        );
+        let coin_ids: Vec<&str> = tokens
+            .iter()
+            .filter_map(|token| token_map.get(token.to_uppercase().as_str()).copied())
+            .collect();
 
-        let client = reqwest::Client::new();
-        let response = client.get(&url).send().await?;
-        let data: serde_json::Value = response.json().await?;
-
-        if let Some(coin_data) = data.get(coin_id) {
-            Ok(CryptoPriceData {
-                symbol: coin_id.to_uppercase(),
-                price_usd: coin_data["usd"].as_f64().unwrap_or(0.0),
-                change_24h: coin_data["usd_24h_change"].as_f64().unwrap_or(0.0),
-                market_cap: coin_data["usd_market_cap"].as_f64(),
-                volume_24h: coin_data["usd_24h_vol"].as_f64(),
-                last_updated: chrono::Utc::now().to_rfc3339(),
-            })
-        } else {
-            Err(anyhow::anyhow!("Price data not found for {}", coin_id))
-        }
+        Ok(self.price_feed.get_prices(&coin_ids).await)
     }
 
     fn extract_tokens_from_text(&self, text: &str) -> Vec<String> {
@@ -403,7 +773,42 @@ impl DefiService {
         found_tokens
     }
 
-    fn parse_strategy_response(&self, ai_response: &str) -> Result<StrategyData> {
+    /// Attach the strategy recommendation contained in a chat reply, if any.
+    pub fn structure_chat_response(&self, chat: ChatResponse) -> StructuredChatResponse {
+        let strategy = self.extract_strategy(&chat.message);
+        StructuredChatResponse { chat, strategy }
+    }
+
+    /// Find a strategy JSON object in `reply` (bare or in a ```json fence)
+    /// and parse it with `parse_strategy_response`.
+    pub fn extract_strategy(&self, reply: &str) -> Option<StrategyData> {
+        let candidate = match reply.find("```json") {
+            Some(start) => {
+                let body = &reply[start + "```json".len()..];
+                &body[..body.find("```").unwrap_or(body.len())]
+            }
+            None => {
+                let start = reply.find('{')?;
+                let end = reply.rfind('}')?;
+                if end < start {
+                    return None;
+                }
+                &reply[start..=end]
+            }
+        };
+
+        let json_value = serde_json::from_str::<serde_json::Value>(candidate.trim()).ok()?;
+        let is_strategy = ["name", "risk_level", "protocols"]
+            .iter()
+            .any(|key| json_value.get(key).is_some());
+        if !is_strategy {
+            return None;
+        }
+
+        self.parse_strategy_response(candidate.trim()).ok()
+    }
+
+    pub fn parse_strategy_response(&self, ai_response: &str) -> Result<StrategyData> {
         // Try to parse as JSON first
         if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(ai_response) {
             return Ok(StrategyData {
@@ -430,14 +835,7 @@ impl DefiService {
     }
 
     fn extract_risk_level(&self, text: &str) -> String {
-        let text_lower = text.to_lowercase();
-        if text_lower.contains("high risk") || text_lower.contains("aggressive") {
-            "high".to_string()
-        } else if text_lower.contains("low risk") || text_lower.contains("conservative") {
-            "low".to_string()
-        } else {
-            "medium".to_string()
-        }
+        risk_level_in(text).unwrap_or("medium").to_string()
     }
 
     fn extract_chain(&self, text: &str) -> String {
@@ -548,10 +946,8 @@ impl DefiService {
             "high"
         } else if input_lower.contains("low") || input_lower.contains("conservative") || input_lower.contains("safe") {
             "low"
-        } else if input_lower.contains("medium") || input_lower.contains("moderate") {
-            "medium"
         } else {
-            "medium" // default
+            "medium" // "medium", "moderate" and the default
         }
     }
 
@@ -587,6 +983,215 @@ impl DefiService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use shuttle_axum::axum::{http::StatusCode, response::IntoResponse, routing::get, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    async fn test_service(llm_reply: &'static str) -> DefiService {
+        let app = Router::new().route(
+            "/models/{model}",
+            shuttle_axum::axum::routing::post(move || async move {
+                shuttle_axum::axum::Json(serde_json::json!({
+                    "candidates": [{"content": {"parts": [{"text": llm_reply}]}}]
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            shuttle_axum::axum::serve(listener, app).await.unwrap();
+        });
+
+        let qdrant = qdrant_client::Qdrant::from_url("http://localhost:6334").build().unwrap();
+        let gemini = crate::gemini_client::GeminiClient::with_base_url("test-key".to_string(), format!("http://{}", addr));
+        let chat_service = Arc::new(ChatService::new(qdrant, "test-key".to_string()).with_gemini_client(gemini));
+        let polkadot_client = Arc::new(PolkadotClient::new_mock().await.unwrap());
+        let db = PgPool::connect_lazy("postgres://localhost/dynavest_test").unwrap();
+
+        DefiService::new(chat_service, polkadot_client, db).with_price_source(PriceSource::Mock)
+    }
+
+    #[tokio::test]
+    async fn test_structured_chat_extracts_strategy() {
+        let service = test_service(
+            "Here is a plan for you:\n```json\n{\"name\": \"DOT Liquid Staking\", \"risk_level\": \"low\", \"chain\": \"Polkadot\", \"recommended_amount\": 1000.0, \"protocols\": [\"Bifrost\", \"Acala\"]}\n```",
+        )
+        .await;
+
+        let reply = service.chat_service.generate_response("Suggest a safe DOT strategy", &[]).await.unwrap();
+        let structured = service.structure_chat_response(reply);
+
+        let strategy = structured.strategy.expect("strategy should be parsed");
+        assert_eq!(strategy.name, "DOT Liquid Staking");
+        assert_eq!(strategy.risk_level, "low");
+        assert_eq!(strategy.chain, "Polkadot");
+        assert_eq!(strategy.recommended_amount, Some(1000.0));
+        assert_eq!(strategy.protocols, vec!["Bifrost", "Acala"]);
+        assert!(structured.chat.message.starts_with("Here is a plan"));
+    }
+
+    #[tokio::test]
+    async fn test_polkadot_strategy_uses_requested_risk_and_amount() {
+        let service = test_service("unused").await.with_polkadot_defaults(PolkadotStrategyDefaults::default());
+        let request = |input_text: &str| DefiInfoRequest {
+            input_text: input_text.to_string(),
+            user_address: None,
+            risk_level: None,
+            amount: None,
+        };
+
+        let response = service.handle_strategies(&request("high risk polkadot strategy for 50000")).await.unwrap();
+        assert_eq!(response.data["risk_level"], "high");
+        assert_eq!(response.data["amount"], 50000.0);
+        let answer = response.data["answer"].as_str().unwrap();
+        assert!(answer.contains("Risk Level: 8/10"));
+        assert!(answer.contains("For $50000.00 investment"));
+
+        // Nothing in the text: configured defaults
+        let response = service.handle_strategies(&request("polkadot strategy please")).await.unwrap();
+        assert_eq!(response.data["risk_level"], "medium");
+        assert_eq!(response.data["amount"], 10000.0);
+
+        // Request fields override the text
+        let explicit = DefiInfoRequest {
+            risk_level: Some("low".to_string()),
+            amount: Some(2500.0),
+            ..request("aggressive polkadot strategy for $20k")
+        };
+        let response = service.handle_strategies(&explicit).await.unwrap();
+        assert_eq!(response.data["risk_level"], "low");
+        assert_eq!(response.data["amount"], 2500.0);
+    }
+
+    #[test]
+    fn test_amount_in_text() {
+        assert_eq!(amount_in("invest $1,500 in DOT"), Some(1500.0));
+        assert_eq!(amount_in("a strategy for 20k"), Some(20000.0));
+        assert_eq!(amount_in("put 750 usd to work"), Some(750.0));
+        assert_eq!(amount_in("risk level 7 please"), None);
+    }
+
+    #[tokio::test]
+    async fn test_structured_chat_without_strategy() {
+        let service = test_service("Staking locks tokens to secure the network.").await;
+
+        let reply = service.chat_service.generate_response("What is staking?", &[]).await.unwrap();
+        assert!(service.structure_chat_response(reply).strategy.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_mock_price_source_is_deterministic() {
+        let feed = PriceFeed::new(PriceSource::Mock);
+
+        let first = feed.get_price("polkadot").await.unwrap();
+        let second = feed.get_price("polkadot").await.unwrap();
+
+        assert_eq!(first.symbol, "POLKADOT");
+        assert_eq!(first.price_usd, 7.5);
+        assert_eq!(first.change_24h, -0.8);
+        assert_eq!(first.last_updated, "2024-01-01T00:00:00+00:00");
+        assert_eq!(serde_json::to_value(&first).unwrap(), serde_json::to_value(&second).unwrap());
+        assert!(feed.get_price("unknown-coin").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cached_only_source_serves_seeded_prices() {
+        let feed = PriceFeed::new(PriceSource::CachedOnly);
+        assert!(feed.get_price("bitcoin").await.is_err());
+
+        feed.seed(vec![mock_price("bitcoin").unwrap()]);
+        assert_eq!(feed.get_price("bitcoin").await.unwrap().price_usd, 65_000.0);
+    }
+
+    #[tokio::test]
+    async fn test_coingecko_retries_after_rate_limit() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let app = Router::new().route(
+            "/simple/price",
+            get(move || {
+                let counter = counter.clone();
+                async move {
+                    if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                        (StatusCode::TOO_MANY_REQUESTS, [("retry-after", "0")], "slow down").into_response()
+                    } else {
+                        shuttle_axum::axum::Json(serde_json::json!({"polkadot": {"usd": 4.2}})).into_response()
+                    }
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            shuttle_axum::axum::serve(listener, app).await.unwrap();
+        });
+
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            base_delay: std::time::Duration::from_millis(10),
+            max_delay: std::time::Duration::from_millis(50),
+        };
+        let url = format!("http://{}/simple/price?ids=polkadot", addr);
+        let data = fetch_coingecko_json(&reqwest::Client::new(), &url, &policy).await.unwrap();
+
+        assert_eq!(data["polkadot"]["usd"].as_f64(), Some(4.2));
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    const MULTI_COIN_PRICES: &str = r#"{
+        "bitcoin": {"usd": 65000.0, "usd_market_cap": 1280000000000.0, "usd_24h_vol": 25000000000.0, "usd_24h_change": 1.5},
+        "polkadot": {"usd": 7.5, "usd_market_cap": 10500000000.0, "usd_24h_vol": 210000000.0, "usd_24h_change": -0.8},
+        "solana": {"usd": 150.0}
+    }"#;
+
+    #[test]
+    fn test_parse_multi_coin_prices() {
+        let data: serde_json::Value = serde_json::from_str(MULTI_COIN_PRICES).unwrap();
+        let prices = parse_simple_prices(&data, &["polkadot", "cardano", "bitcoin", "solana"]);
+
+        let symbols: Vec<&str> = prices.iter().map(|p| p.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["POLKADOT", "BITCOIN", "SOLANA"]);
+        assert_eq!(prices[0].price_usd, 7.5);
+        assert_eq!(prices[0].change_24h, -0.8);
+        assert_eq!(prices[0].volume_24h, Some(210_000_000.0));
+        assert_eq!(prices[1].market_cap, Some(1_280_000_000_000.0));
+        assert_eq!(prices[2].change_24h, 0.0);
+        assert_eq!(prices[2].market_cap, None);
+    }
+
+    #[tokio::test]
+    async fn test_live_prices_fetched_in_one_request_and_cached() {
+        let queries = Arc::new(Mutex::new(Vec::new()));
+        let seen = queries.clone();
+        let app = Router::new().route(
+            "/simple/price",
+            get(move |shuttle_axum::axum::extract::RawQuery(query): shuttle_axum::axum::extract::RawQuery| {
+                let seen = seen.clone();
+                async move {
+                    seen.lock().unwrap().push(query.unwrap_or_default());
+                    shuttle_axum::axum::Json(serde_json::from_str::<serde_json::Value>(MULTI_COIN_PRICES).unwrap())
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            shuttle_axum::axum::serve(listener, app).await.unwrap();
+        });
+
+        let feed = PriceFeed::new(PriceSource::Live).with_base_url(format!("http://{}", addr));
+        let prices = feed.get_prices(&["polkadot", "bitcoin"]).await;
+        assert_eq!(prices.iter().map(|p| p.price_usd).collect::<Vec<_>>(), vec![7.5, 65_000.0]);
+
+        // Same set of coins within the TTL, in another order: no new request
+        let again = feed.get_prices(&["bitcoin", "polkadot"]).await;
+        assert_eq!(again.iter().map(|p| p.symbol.as_str()).collect::<Vec<_>>(), vec!["BITCOIN", "POLKADOT"]);
+
+        let queries = queries.lock().unwrap();
+        assert_eq!(queries.len(), 1);
+        assert!(queries[0].starts_with("ids=bitcoin,polkadot&vs_currencies=usd"));
+    }
     
     #[test]
     fn test_extract_risk_level() {
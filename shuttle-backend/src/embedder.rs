@@ -0,0 +1,333 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+/// Vector size every embedder must produce, matching the collections
+pub const EMBEDDING_DIMENSIONS: usize = 384;
+
+/// One entry in the embedder fallback chain.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EmbedderSpec {
+    /// A sentence-transformers model served over HTTP, answering
+    /// `POST {"text": ...}` with `{"embedding": [...]}`
+    Service { url: String },
+    /// Deterministic hash embedding; always loads
+    Hash,
+}
+
+impl EmbedderSpec {
+    /// `hash` or `service:<url>`.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let spec = spec.trim();
+        if spec.eq_ignore_ascii_case("hash") {
+            return Some(EmbedderSpec::Hash);
+        }
+        spec.strip_prefix("service:")
+            .filter(|url| !url.is_empty())
+            .map(|url| EmbedderSpec::Service { url: url.to_string() })
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            EmbedderSpec::Service { url } => format!("service:{}", url),
+            EmbedderSpec::Hash => "hash".to_string(),
+        }
+    }
+}
+
+/// Embedders to try in order, from the comma-separated `RAG_EMBEDDERS`.
+/// Unrecognised entries are skipped, and the hash embedder is appended
+/// when missing so startup never fails for want of an embedder.
+pub fn chain_from_env() -> Vec<EmbedderSpec> {
+    let mut chain: Vec<EmbedderSpec> = std::env::var("RAG_EMBEDDERS")
+        .unwrap_or_default()
+        .split(',')
+        .filter(|spec| !spec.trim().is_empty())
+        .filter_map(|spec| {
+            let parsed = EmbedderSpec::parse(spec);
+            if parsed.is_none() {
+                warn!("Ignoring unrecognised embedder '{}' in RAG_EMBEDDERS", spec.trim());
+            }
+            parsed
+        })
+        .collect();
+    if !chain.contains(&EmbedderSpec::Hash) {
+        chain.push(EmbedderSpec::Hash);
+    }
+    chain
+}
+
+/// Whether to run a warm-up embedding at startup, from `EMBEDDER_WARM_UP`.
+/// On unless set to `false`, `0` or `off`.
+pub fn warm_up_from_env() -> bool {
+    !matches!(
+        std::env::var("EMBEDDER_WARM_UP").unwrap_or_default().trim().to_lowercase().as_str(),
+        "false" | "0" | "off"
+    )
+}
+
+/// A loaded embedder, ready to turn text into vectors.
+#[derive(Debug, Clone)]
+pub enum Embedder {
+    Service { url: String, client: reqwest::Client },
+    Hash,
+}
+
+#[derive(Deserialize)]
+struct ServiceEmbedding {
+    embedding: Vec<f32>,
+}
+
+impl Embedder {
+    /// Load `spec`, probing a service with a sample text so an unreachable
+    /// or misconfigured model fails here rather than on the first request.
+    pub async fn load(spec: &EmbedderSpec) -> Result<Self> {
+        let embedder = match spec {
+            EmbedderSpec::Service { url } => Embedder::Service {
+                url: url.clone(),
+                client: reqwest::Client::new(),
+            },
+            EmbedderSpec::Hash => Embedder::Hash,
+        };
+        embedder.embed("ink! smart contract", false).await?;
+        Ok(embedder)
+    }
+
+    /// Run one throwaway embedding so model loading and the first, slowest
+    /// inference happen before real traffic does. Returns how long it took.
+    pub async fn warm_up(&self) -> Result<Duration> {
+        let started = Instant::now();
+        self.embed("warm-up: contract Flipper { bool value; }", false).await?;
+        Ok(started.elapsed())
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Embedder::Service { url, .. } => format!("service:{}", url),
+            Embedder::Hash => "hash".to_string(),
+        }
+    }
+
+    /// Embed `text`, scaled to unit length when `normalize` is set.
+    pub async fn embed(&self, text: &str, normalize: bool) -> Result<Vec<f32>> {
+        let mut embedding = match self {
+            Embedder::Service { url, client } => {
+                let response = client
+                    .post(url)
+                    .json(&serde_json::json!({ "text": text }))
+                    .send()
+                    .await?
+                    .error_for_status()?;
+                response.json::<ServiceEmbedding>().await?.embedding
+            }
+            Embedder::Hash => hash_embedding(text),
+        };
+
+        if embedding.len() != EMBEDDING_DIMENSIONS {
+            return Err(anyhow!(
+                "{} returned {} dimensions, expected {}",
+                self.name(),
+                embedding.len(),
+                EMBEDDING_DIMENSIONS
+            ));
+        }
+        if normalize {
+            let magnitude: f32 = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+            if magnitude > 0.0 {
+                for val in &mut embedding {
+                    *val /= magnitude;
+                }
+            }
+        }
+        Ok(embedding)
+    }
+}
+
+/// Which embedder the service is running with, for `/rag/health`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct EmbedderStatus {
+    /// The embedder in use, e.g. `hash` or `service:<url>`
+    pub active: String,
+    /// Earlier entries in the chain that failed to load, with the reason
+    pub skipped: Vec<String>,
+    /// Whether the startup warm-up embedding has completed
+    pub ready: bool,
+    /// How long the warm-up embedding took, once it has run
+    pub warm_up_ms: Option<u64>,
+}
+
+impl Default for EmbedderStatus {
+    fn default() -> Self {
+        Self {
+            active: Embedder::Hash.name(),
+            skipped: Vec::new(),
+            ready: false,
+            warm_up_ms: None,
+        }
+    }
+}
+
+impl EmbedderStatus {
+    pub fn record_warm_up(&mut self, duration: Duration) {
+        self.ready = true;
+        self.warm_up_ms = Some(duration.as_millis() as u64);
+    }
+}
+
+/// Try each embedder in `chain` in order and use the first that loads,
+/// falling back to the hash embedder if none do.
+pub async fn load_chain(chain: &[EmbedderSpec]) -> (Embedder, EmbedderStatus) {
+    let mut skipped = Vec::new();
+    for spec in chain {
+        match Embedder::load(spec).await {
+            Ok(embedder) => {
+                info!("Using embedder {}", embedder.name());
+                let status = EmbedderStatus {
+                    active: embedder.name(),
+                    skipped,
+                    ..Default::default()
+                };
+                return (embedder, status);
+            }
+            Err(e) => {
+                warn!("Embedder {} failed to load, trying the next one: {}", spec.name(), e);
+                skipped.push(format!("{}: {}", spec.name(), e));
+            }
+        }
+    }
+
+    warn!("No configured embedder loaded; using the hash embedder");
+    let status = EmbedderStatus {
+        active: Embedder::Hash.name(),
+        skipped,
+        ..Default::default()
+    };
+    (Embedder::Hash, status)
+}
+
+/// Deterministic embedding derived from the text's hash.
+fn hash_embedding(text: &str) -> Vec<f32> {
+    // Simple hash-based embedding for demo (384 dimensions to match Python model)
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    let hash = hasher.finish();
+
+    // Create a deterministic but pseudo-random embedding
+    let mut embedding = Vec::with_capacity(EMBEDDING_DIMENSIONS);
+    let mut seed = hash;
+    for _ in 0..EMBEDDING_DIMENSIONS {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        embedding.push((seed as f32 / u64::MAX as f32) * 2.0 - 1.0);
+    }
+    embedding
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shuttle_axum::axum::{routing::post, Json, Router};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    /// Serve a fake embedding model returning `dimensions` values per text.
+    async fn mock_service_url(dimensions: usize) -> String {
+        let app = Router::new().route(
+            "/embed",
+            post(move || async move { Json(serde_json::json!({ "embedding": vec![0.5f32; dimensions] })) }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            shuttle_axum::axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{}/embed", addr)
+    }
+
+    #[tokio::test]
+    async fn test_broken_primary_falls_back_to_working_embedder() {
+        let working = mock_service_url(EMBEDDING_DIMENSIONS).await;
+        let chain = vec![
+            // Nothing listens on the discard port
+            EmbedderSpec::Service { url: "http://127.0.0.1:9/embed".to_string() },
+            EmbedderSpec::Service { url: working.clone() },
+            EmbedderSpec::Hash,
+        ];
+
+        let (embedder, status) = load_chain(&chain).await;
+        assert_eq!(embedder.name(), format!("service:{}", working));
+        assert_eq!(status.active, embedder.name());
+        assert_eq!(status.skipped.len(), 1);
+        assert!(status.skipped[0].starts_with("service:http://127.0.0.1:9/embed: "));
+
+        let vector = embedder.embed("flipper", true).await.unwrap();
+        let magnitude = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((magnitude - 1.0).abs() < 1e-4);
+    }
+
+    #[tokio::test]
+    async fn test_wrong_dimensions_fall_through_to_hash() {
+        let chain = vec![EmbedderSpec::Service { url: mock_service_url(768).await }];
+
+        let (embedder, status) = load_chain(&chain).await;
+        assert_eq!(embedder.name(), "hash");
+        assert_eq!(status.active, "hash");
+        assert!(status.skipped[0].contains("returned 768 dimensions, expected 384"));
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_pays_cold_start_once() {
+        // The first request stands in for loading the model
+        let cold = Arc::new(AtomicBool::new(true));
+        let app = Router::new().route(
+            "/embed",
+            post(move || {
+                let cold = cold.clone();
+                async move {
+                    if cold.swap(false, Ordering::SeqCst) {
+                        tokio::time::sleep(Duration::from_millis(300)).await;
+                    }
+                    Json(serde_json::json!({ "embedding": vec![0.5f32; EMBEDDING_DIMENSIONS] }))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            shuttle_axum::axum::serve(listener, app).await.unwrap();
+        });
+        let embedder = Embedder::Service {
+            url: format!("http://{}/embed", addr),
+            client: reqwest::Client::new(),
+        };
+
+        let mut status = EmbedderStatus {
+            active: embedder.name(),
+            ..Default::default()
+        };
+        assert!(!status.ready);
+        let warm_up = embedder.warm_up().await.unwrap();
+        status.record_warm_up(warm_up);
+        assert!(status.ready);
+        assert!(status.warm_up_ms.unwrap() >= 300);
+
+        let started = Instant::now();
+        embedder.embed("flipper", true).await.unwrap();
+        assert!(started.elapsed() < warm_up / 2);
+    }
+
+    #[test]
+    fn test_parse_embedder_specs() {
+        assert_eq!(EmbedderSpec::parse(" hash "), Some(EmbedderSpec::Hash));
+        assert_eq!(
+            EmbedderSpec::parse("service:http://embedder:8000/embed"),
+            Some(EmbedderSpec::Service { url: "http://embedder:8000/embed".to_string() })
+        );
+        assert_eq!(EmbedderSpec::parse("service:"), None);
+        assert_eq!(EmbedderSpec::parse("onnx:/models/minilm.onnx"), None);
+    }
+}
@@ -1,15 +1,22 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use tracing::{info, error};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::{mpsc, Semaphore};
+use tracing::{info, error, warn};
+
+use crate::prompts::{render, Prompt};
+use crate::retry::{parse_retry_after, RetryPolicy};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GeminiRequest {
     pub contents: Vec<GeminiContent>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct GeminiContent {
+    #[serde(default)]
     pub parts: Vec<GeminiPart>,
 }
 
@@ -19,42 +26,198 @@ pub struct GeminiPart {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GeminiResponse {
+    #[serde(default)]
     pub candidates: Vec<GeminiCandidate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_feedback: Option<GeminiPromptFeedback>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GeminiCandidate {
+    #[serde(default)]
     pub content: GeminiContent,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiPromptFeedback {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_reason: Option<String>,
+}
+
+/// Finish reasons meaning Gemini withheld the answer rather than running out of tokens
+const BLOCKING_FINISH_REASONS: &[&str] = &["SAFETY", "RECITATION", "BLOCKLIST", "PROHIBITED_CONTENT", "SPII"];
+
+/// Failures callers may want to tell apart from transport errors; returned
+/// inside `anyhow::Error`, so check with `downcast_ref::<GeminiError>()`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeminiError {
+    /// The prompt or answer was blocked, with Gemini's reason (e.g. `SAFETY`)
+    Blocked(String),
+}
+
+impl std::fmt::Display for GeminiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GeminiError::Blocked(reason) => write!(f, "Gemini blocked the response ({})", reason),
+        }
+    }
+}
+
+impl std::error::Error for GeminiError {}
+
+impl GeminiResponse {
+    /// Text of the first candidate, or `GeminiError::Blocked` when the prompt
+    /// or the candidate was stopped by Gemini's safety filters.
+    pub fn into_text(self) -> Result<String> {
+        if let Some(reason) = self.prompt_feedback.and_then(|feedback| feedback.block_reason) {
+            return Err(GeminiError::Blocked(reason).into());
+        }
+
+        let candidate = self
+            .candidates
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("No valid response content from Gemini"))?;
+
+        match candidate.finish_reason {
+            Some(reason) if BLOCKING_FINISH_REASONS.contains(&reason.as_str()) => {
+                Err(GeminiError::Blocked(reason).into())
+            }
+            finish_reason => candidate
+                .content
+                .parts
+                .into_iter()
+                .next()
+                .map(|part| part.text)
+                .ok_or_else(|| match finish_reason {
+                    Some(reason) => GeminiError::Blocked(reason).into(),
+                    None => anyhow!("No valid response content from Gemini"),
+                }),
+        }
+    }
+
+    /// Text of one `streamGenerateContent` chunk, `None` when it carries
+    /// none (e.g. the final chunk with only a finish reason). Blocking is
+    /// reported as in `into_text`.
+    pub fn into_chunk_text(self) -> Result<Option<String>> {
+        if let Some(reason) = self.prompt_feedback.and_then(|feedback| feedback.block_reason) {
+            return Err(GeminiError::Blocked(reason).into());
+        }
+        let Some(candidate) = self.candidates.into_iter().next() else {
+            return Ok(None);
+        };
+        if let Some(reason) = candidate.finish_reason.filter(|r| BLOCKING_FINISH_REASONS.contains(&r.as_str())) {
+            return Err(GeminiError::Blocked(reason).into());
+        }
+
+        let text: String = candidate.content.parts.into_iter().map(|part| part.text).collect();
+        Ok((!text.is_empty()).then_some(text))
+    }
+}
+
+/// Text of a streamed answer in the order Gemini produced it. An `Err` is
+/// the last item; the channel closes when the answer is complete.
+pub type ResponseChunks = mpsc::Receiver<Result<String>>;
+
+/// Total time a streamed answer may take, longer than the client's
+/// default since the whole answer is read through one request
+const STREAM_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Process-wide limit on in-flight Gemini calls, sized by
+/// `GEMINI_MAX_CONCURRENT` (default 4).
+fn shared_permits() -> Arc<Semaphore> {
+    static PERMITS: OnceLock<Arc<Semaphore>> = OnceLock::new();
+    PERMITS
+        .get_or_init(|| {
+            let permits = std::env::var("GEMINI_MAX_CONCURRENT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|permits| *permits > 0)
+                .unwrap_or(4);
+            Arc::new(Semaphore::new(permits))
+        })
+        .clone()
 }
 
 pub struct GeminiClient {
     client: Client,
     api_key: String,
+    base_url: String,
+    permits: Arc<Semaphore>,
+    /// How long a call may wait for a free permit before giving up
+    queue_timeout: Duration,
+    retry_policy: RetryPolicy,
 }
 
 impl GeminiClient {
     pub fn new(api_key: String) -> Self {
+        let base_url = std::env::var("GEMINI_API_URL")
+            .unwrap_or_else(|_| "https://generativelanguage.googleapis.com/v1beta".to_string());
+        Self::with_base_url(api_key, base_url)
+    }
+
+    pub fn with_base_url(api_key: String, base_url: String) -> Self {
         let client = Client::builder()
             .timeout(std::time::Duration::from_secs(30))
             .build()
             .expect("Failed to create HTTP client");
-        Self { client, api_key }
+        let queue_timeout = std::env::var("GEMINI_QUEUE_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(30));
+
+        Self {
+            client,
+            api_key,
+            base_url,
+            permits: shared_permits(),
+            queue_timeout,
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+
+    /// Use a dedicated semaphore instead of the process-wide one.
+    #[allow(dead_code)]
+    pub fn with_permits(mut self, permits: Arc<Semaphore>) -> Self {
+        self.permits = permits;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
     }
 
+    /// Like `try_generate_response`, but turns failures into an apology
+    /// message so callers can always show something to the user.
     pub async fn generate_response(&self, prompt: &str, context: &[String]) -> Result<String> {
-        // Build the complete prompt with context
-        let context_text = if context.is_empty() {
-            String::new()
-        } else {
-            format!("Context:\n{}\n\n", context.join("\n\n"))
-        };
+        match self.try_generate_response(prompt, context).await {
+            Ok(text) => Ok(text),
+            Err(e) if e.downcast_ref::<GeminiError>().is_some() => {
+                warn!("{}", e);
+                Ok("I'm sorry, but I can't answer that: the request was blocked by the AI provider's safety filters. Please try rephrasing your question.".to_string())
+            }
+            Err(e) if e.downcast_ref::<reqwest::Error>().is_some() => {
+                error!("Gemini API request failed: {}", e);
+                Ok("I apologize, but the Gemini API is currently slow or unavailable. Please try again later, or check that the API key is correct.".to_string())
+            }
+            Err(e) => {
+                error!("{}", e);
+                Ok("I apologize, but I couldn't generate a proper response at this time.".to_string())
+            }
+        }
+    }
 
-        let full_prompt = format!(
-            "{}You are a helpful developer assistant that answers questions about codebases. Use the provided context to answer the user's question accurately.\n\nQuestion: {}\n\nAnswer:",
-            context_text,
-            prompt
-        );
+    pub async fn try_generate_response(&self, prompt: &str, context: &[String]) -> Result<String> {
+        let full_prompt = build_prompt(prompt, context);
 
         info!("Generating response with Gemini API for prompt length: {}", full_prompt.len());
 
@@ -68,48 +231,129 @@ impl GeminiClient {
         };
 
         let url = format!(
-            "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-flash:generateContent?key={}",
-            self.api_key
+            "{}/models/gemini-2.5-flash:generateContent?key={}",
+            self.base_url, self.api_key
         );
 
-        // Make the API call
-        match self.client
-            .post(&url)
-            .json(&request)
-            .send()
+        // Wait for a free slot so bursts don't blow through the quota
+        let _permit = tokio::time::timeout(self.queue_timeout, self.permits.acquire())
             .await
-        {
-            Ok(response) => {
-                if response.status().is_success() {
-                    match response.json::<GeminiResponse>().await {
-                        Ok(gemini_response) => {
-                            if let Some(candidate) = gemini_response.candidates.first() {
-                                if let Some(part) = candidate.content.parts.first() {
-                                    info!("Successfully generated response from Gemini");
-                                    return Ok(part.text.clone());
-                                }
+            .map_err(|_| anyhow!("Timed out waiting for a free Gemini request slot"))??;
+
+        let response = self.post_with_retry(&url, &request, None).await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("Gemini API returned error status: {}", response.status()));
+        }
+
+        let gemini_response = response
+            .json::<GeminiResponse>()
+            .await
+            .map_err(|e| anyhow!("Failed to parse Gemini response: {}", e))?;
+
+        let text = gemini_response.into_text()?;
+
+        info!("Successfully generated response from Gemini");
+        Ok(text)
+    }
+
+    /// Like `try_generate_response`, but returns the answer as it is
+    /// generated, via `streamGenerateContent`. Errors before the first
+    /// chunk, such as an error status, are returned directly.
+    pub async fn stream_response(&self, prompt: &str, context: &[String]) -> Result<ResponseChunks> {
+        let full_prompt = build_prompt(prompt, context);
+
+        info!("Streaming response with Gemini API for prompt length: {}", full_prompt.len());
+
+        let request = GeminiRequest {
+            contents: vec![GeminiContent {
+                parts: vec![GeminiPart {
+                    text: full_prompt,
+                }],
+            }],
+        };
+
+        let url = format!(
+            "{}/models/gemini-2.5-flash:streamGenerateContent?alt=sse&key={}",
+            self.base_url, self.api_key
+        );
+
+        // The slot is held until the whole answer has been read
+        let permit = tokio::time::timeout(self.queue_timeout, self.permits.clone().acquire_owned())
+            .await
+            .map_err(|_| anyhow!("Timed out waiting for a free Gemini request slot"))??;
+
+        let mut response = self.post_with_retry(&url, &request, Some(STREAM_TIMEOUT)).await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Gemini API returned error status: {}", response.status()));
+        }
+
+        let (sender, chunks) = mpsc::channel(32);
+        tokio::spawn(async move {
+            let _permit = permit;
+            let mut buffer: Vec<u8> = Vec::new();
+            loop {
+                match response.chunk().await {
+                    Ok(Some(bytes)) => buffer.extend_from_slice(&bytes),
+                    Ok(None) => break,
+                    Err(e) => {
+                        let _ = sender.send(Err(e.into())).await;
+                        return;
+                    }
+                }
+
+                // Events are `data: <json>` lines; only complete lines are parsed
+                while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+                    let line: Vec<u8> = buffer.drain(..=end).collect();
+                    match parse_stream_line(&String::from_utf8_lossy(&line)) {
+                        Ok(Some(text)) => {
+                            if sender.send(Ok(text)).await.is_err() {
+                                // The caller stopped listening
+                                return;
                             }
-                            
-                            error!("No valid response content from Gemini");
-                            Ok("I apologize, but I couldn't generate a proper response at this time.".to_string())
                         }
+                        Ok(None) => {}
                         Err(e) => {
-                            error!("Failed to parse Gemini response: {}", e);
-                            Ok("I apologize, but I couldn't generate a proper response at this time.".to_string())
+                            let _ = sender.send(Err(e)).await;
+                            return;
                         }
                     }
-                } else {
-                    error!("Gemini API returned error status: {}", response.status());
-                    Ok("I apologize, but I couldn't generate a proper response at this time.".to_string())
                 }
             }
-            Err(e) => {
-                error!("Gemini API request failed: {}", e);
-                Ok("I apologize, but the Gemini API is currently slow or unavailable. Please try again later, or check that the API key is correct.".to_string())
+            info!("Finished streaming response from Gemini");
+        });
+
+        Ok(chunks)
+    }
+
+    /// POST `request` to `url`, retrying rate limits and server errors.
+    async fn post_with_retry(&self, url: &str, request: &GeminiRequest, timeout: Option<Duration>) -> Result<reqwest::Response> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let mut builder = self.client.post(url).json(request);
+            if let Some(timeout) = timeout {
+                builder = builder.timeout(timeout);
             }
+            let response = builder.send().await?;
+            let status = response.status();
+            let retryable = status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+            if !retryable || attempt >= self.retry_policy.max_attempts {
+                return Ok(response);
+            }
+
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_retry_after);
+            let delay = self.retry_policy.delay_for(attempt, retry_after);
+            warn!("Gemini API returned {} (attempt {}/{}), retrying in {:?}", status, attempt, self.retry_policy.max_attempts, delay);
+            tokio::time::sleep(delay).await;
         }
     }
 
+    #[allow(dead_code)]
     pub async fn generate_rag_response(&self, query: &str, retrieved_chunks: &[String]) -> Result<String> {
         if retrieved_chunks.is_empty() {
             return Ok("I don't have enough information in the codebase to answer that question.".to_string());
@@ -126,6 +370,41 @@ impl GeminiClient {
     }
 }
 
+/// Text in one line of a `streamGenerateContent?alt=sse` body; `None` for
+/// blank lines, other SSE fields and chunks without text.
+fn parse_stream_line(line: &str) -> Result<Option<String>> {
+    let Some(data) = line.trim_end().strip_prefix("data:") else {
+        return Ok(None);
+    };
+    let data = data.trim();
+    if data.is_empty() {
+        return Ok(None);
+    }
+    serde_json::from_str::<GeminiResponse>(data)
+        .map_err(|e| anyhow!("Failed to parse Gemini stream chunk: {}", e))?
+        .into_chunk_text()
+}
+
+/// Default instruction placed between the context and the question in
+/// every prompt, overridable as the `system` prompt template
+pub const SYSTEM_PROMPT: &str = "You are a helpful developer assistant that answers questions about codebases. Use the provided context to answer the user's question accurately.";
+
+/// The exact text sent to Gemini for `prompt` with `context`.
+pub fn build_prompt(prompt: &str, context: &[String]) -> String {
+    let context_text = if context.is_empty() {
+        String::new()
+    } else {
+        format!("Context:\n{}\n\n", context.join("\n\n"))
+    };
+
+    format!(
+        "{}{}\n\nQuestion: {}\n\nAnswer:",
+        context_text,
+        render(Prompt::System, &[]),
+        prompt
+    )
+}
+
 impl Default for GeminiClient {
     fn default() -> Self {
         let api_key = std::env::var("GEMINI_API_KEY")
@@ -187,6 +466,35 @@ mod tests {
         assert_eq!(response.candidates[0].content.parts[0].text, "Test response");
     }
 
+    #[test]
+    fn test_safety_blocked_response_is_blocked_error() {
+        let blocked_candidate = r#"
+        {
+            "candidates": [
+                {
+                    "finishReason": "SAFETY",
+                    "safetyRatings": [{"category": "HARM_CATEGORY_DANGEROUS_CONTENT", "probability": "HIGH"}]
+                }
+            ]
+        }
+        "#;
+        let response: GeminiResponse = serde_json::from_str(blocked_candidate).unwrap();
+        let err = response.into_text().unwrap_err();
+        assert_eq!(err.downcast_ref::<GeminiError>(), Some(&GeminiError::Blocked("SAFETY".to_string())));
+
+        let blocked_prompt = r#"{"promptFeedback": {"blockReason": "PROHIBITED_CONTENT"}}"#;
+        let response: GeminiResponse = serde_json::from_str(blocked_prompt).unwrap();
+        let err = response.into_text().unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<GeminiError>(),
+            Some(&GeminiError::Blocked("PROHIBITED_CONTENT".to_string()))
+        );
+
+        let answered = r#"{"candidates": [{"content": {"parts": [{"text": "ok"}]}, "finishReason": "STOP"}]}"#;
+        let response: GeminiResponse = serde_json::from_str(answered).unwrap();
+        assert_eq!(response.into_text().unwrap(), "ok");
+    }
+
     #[tokio::test]
     async fn test_generate_rag_response_empty_chunks() {
         let client = GeminiClient::new("test-key".to_string());
@@ -197,6 +505,84 @@ mod tests {
         assert!(response.contains("don't have enough information"));
     }
 
+    #[tokio::test]
+    async fn test_single_permit_serializes_calls() {
+        use shuttle_axum::axum::{routing::post, Json, Router};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let (current, max) = (in_flight.clone(), max_in_flight.clone());
+        let app = Router::new().route(
+            "/models/{model}",
+            post(move || {
+                let (current, max) = (current.clone(), max.clone());
+                async move {
+                    let now = current.fetch_add(1, Ordering::SeqCst) + 1;
+                    max.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    current.fetch_sub(1, Ordering::SeqCst);
+                    Json(serde_json::json!({
+                        "candidates": [{"content": {"parts": [{"text": "ok"}]}}]
+                    }))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            shuttle_axum::axum::serve(listener, app).await.unwrap();
+        });
+
+        let client = GeminiClient::with_base_url("test-key".to_string(), format!("http://{}", addr))
+            .with_permits(Arc::new(Semaphore::new(1)));
+
+        let (first, second) = tokio::join!(
+            client.try_generate_response("first", &[]),
+            client.try_generate_response("second", &[]),
+        );
+
+        assert_eq!(first.unwrap(), "ok");
+        assert_eq!(second.unwrap(), "ok");
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_stream_response_yields_chunks_in_order() {
+        use shuttle_axum::axum::{http::header, routing::post, Router};
+
+        // Gemini's SSE framing: CRLF line ends, a text-less final chunk
+        let body = [
+            r#"{"candidates": [{"content": {"parts": [{"text": "Use "}]}}]}"#,
+            r#"{"candidates": [{"content": {"parts": [{"text": "Mapping"}]}}]}"#,
+            r#"{"candidates": [{"content": {"parts": [{"text": " in ink!"}]}, "finishReason": "STOP"}]}"#,
+            r#"{"candidates": [{"finishReason": "STOP"}]}"#,
+        ]
+        .iter()
+        .map(|chunk| format!("data: {}\r\n\r\n", chunk))
+        .collect::<String>();
+        let app = Router::new().route(
+            "/models/{model}",
+            post(move || async move { ([(header::CONTENT_TYPE, "text/event-stream")], body) }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            shuttle_axum::axum::serve(listener, app).await.unwrap();
+        });
+
+        let client = GeminiClient::with_base_url("test-key".to_string(), format!("http://{}", addr));
+        let mut chunks = client.stream_response("storage?", &[]).await.unwrap();
+        let mut received = Vec::new();
+        while let Some(chunk) = chunks.recv().await {
+            received.push(chunk.unwrap());
+        }
+        assert_eq!(received, vec!["Use ", "Mapping", " in ink!"]);
+
+        let blocked = parse_stream_line(r#"data: {"candidates": [{"finishReason": "SAFETY"}]}"#).unwrap_err();
+        assert_eq!(blocked.downcast_ref::<GeminiError>(), Some(&GeminiError::Blocked("SAFETY".to_string())));
+    }
+
     #[tokio::test]
     async fn test_generate_response_with_context() {
         let client = GeminiClient::new("test-key".to_string());
@@ -60,6 +60,12 @@ pub struct HyperbridgeClient {
     uniswap_subgraph_url: String,
 }
 
+impl Default for HyperbridgeClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl HyperbridgeClient {
     pub fn new() -> Self {
         Self {
@@ -237,7 +243,7 @@ impl HyperbridgeClient {
         }
         
         // Ensure score is in valid range (1-10)
-        risk_score.max(1).min(10)
+        risk_score.clamp(1, 10)
     }
 
     /// Filter LP data by risk level
@@ -0,0 +1,71 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use uuid::Uuid;
+
+/// Source of ids for newly created records.
+pub trait IdGenerator: Send + Sync {
+    fn next_id(&self) -> Uuid;
+}
+
+/// Random v4 ids; the default everywhere outside tests.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct UuidV4Generator;
+
+impl IdGenerator for UuidV4Generator {
+    fn next_id(&self) -> Uuid {
+        Uuid::new_v4()
+    }
+}
+
+/// Predictable ids `00000000-0000-0000-0000-000000000001`, `...02`, ... so
+/// tests can assert on returned ids without querying them back.
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct SequentialIdGenerator {
+    next: AtomicU64,
+}
+
+#[allow(dead_code)]
+impl SequentialIdGenerator {
+    pub fn new() -> Self {
+        Self::starting_at(1)
+    }
+
+    pub fn starting_at(first: u64) -> Self {
+        Self {
+            next: AtomicU64::new(first),
+        }
+    }
+}
+
+impl Default for SequentialIdGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IdGenerator for SequentialIdGenerator {
+    fn next_id(&self) -> Uuid {
+        Uuid::from_u128(self.next.fetch_add(1, Ordering::SeqCst) as u128)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sequential_ids_are_predictable() {
+        let generator = SequentialIdGenerator::new();
+        assert_eq!(generator.next_id().to_string(), "00000000-0000-0000-0000-000000000001");
+        assert_eq!(generator.next_id().to_string(), "00000000-0000-0000-0000-000000000002");
+
+        let generator = SequentialIdGenerator::starting_at(255);
+        assert_eq!(generator.next_id(), Uuid::from_u128(255));
+    }
+
+    #[test]
+    fn test_v4_ids_are_unique() {
+        let generator = UuidV4Generator;
+        assert_ne!(generator.next_id(), generator.next_id());
+    }
+}
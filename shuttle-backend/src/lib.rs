@@ -1,20 +1,43 @@
 // Library exports for dynavest-shuttle-backend
 pub mod contract_matcher;
 pub mod training_embedder;
+pub mod migration_guide;
 pub mod rag_system;
+pub mod embedder;
+pub mod single_flight;
+pub mod concurrency;
 pub mod gemini_client;
+pub mod prompts;
 pub mod parsers;
+pub mod contract_classifier;
 pub mod sample_data;
 pub mod hyperbridge;
+pub mod rebalance;
 pub mod chat;
+pub mod session_store;
 pub mod polkadot;
 pub mod polkadot_defi_knowledge;
+pub mod retry;
+pub mod id_generator;
+pub mod dead_letter;
+pub mod chains;
+pub mod address;
+pub mod analytics;
+pub mod audit;
+pub mod rag_feedback;
+pub mod readiness;
+pub mod tenant;
+pub mod rate_limit;
+pub mod qdrant_config;
+pub mod qdrant_retry;
+pub mod network_config;
+pub mod reindex_schedule;
 pub mod defi_service;
 pub mod contract_service;
 
 // Re-export commonly used items
 pub use contract_matcher::{ContractMatcher, ContractPair, ContractMatchResult};
-pub use training_embedder::{TrainingEmbedder, TrainingPair, EmbeddingResult};
+pub use training_embedder::{TrainingEmbedder, TrainingPair, EmbeddingLedger, EmbeddingResult, InkVersion};
 pub use rag_system::RAGSystem;
 
 // Common data structures needed by multiple modules
@@ -0,0 +1,246 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// One conceptual difference between the Solidity and ink! versions.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct KeyDifference {
+    /// e.g. `Storage` or `Error Handling`
+    pub topic: String,
+    pub detail: String,
+}
+
+/// A Solidity construct and its ink! equivalent, as code.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct PatternMapping {
+    pub solidity: String,
+    pub ink: String,
+}
+
+/// How to port one kind of example contract from Solidity to ink!.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct MigrationGuide {
+    /// Contract type as paired by the contract matcher, e.g. `SimpleERC20`
+    pub contract_type: String,
+    pub title: String,
+    pub key_differences: Vec<KeyDifference>,
+    /// Inline code is wrapped in backticks
+    pub migration_steps: Vec<String>,
+    pub patterns: Vec<PatternMapping>,
+}
+
+/// Contract types with a written guide.
+pub const GUIDED_CONTRACT_TYPES: [&str; 4] = ["SimpleERC20", "Flipper", "Counter", "SimpleNFT"];
+
+fn difference(topic: &str, detail: &str) -> KeyDifference {
+    KeyDifference {
+        topic: topic.to_string(),
+        detail: detail.to_string(),
+    }
+}
+
+fn pattern(solidity: &str, ink: &str) -> PatternMapping {
+    PatternMapping {
+        solidity: solidity.to_string(),
+        ink: ink.to_string(),
+    }
+}
+
+fn steps(steps: &[&str]) -> Vec<String> {
+    steps.iter().map(|s| s.to_string()).collect()
+}
+
+/// The guide for `contract_type`, if one of `GUIDED_CONTRACT_TYPES`.
+pub fn migration_guide(contract_type: &str) -> Option<MigrationGuide> {
+    let (title, key_differences, migration_steps, patterns) = match contract_type {
+        "SimpleERC20" => (
+            "Solidity ERC20 to ink! ERC20",
+            vec![
+                difference("Storage", "Solidity uses `mapping(address => uint256)` while ink! uses `Mapping<AccountId, Balance>`"),
+                difference("Error Handling", "Solidity uses `require()` statements, ink! uses `Result<T, E>` with custom error enums"),
+                difference("Events", "Solidity events are automatically indexed, ink! requires explicit `#[ink(topic)]` annotations"),
+                difference("Function Modifiers", "Solidity modifiers become explicit checks in ink! functions"),
+                difference("Constructor", "Solidity constructor becomes `#[ink(constructor)]` in ink!"),
+            ],
+            steps(&[
+                "Replace `mapping` with `Mapping` in storage",
+                "Convert `require()` statements to `ensure!()` or explicit error handling",
+                "Add `#[ink(storage)]`, `#[ink(constructor)]`, `#[ink(message)]` annotations",
+                "Define a custom error enum (its derives depend on the ink! version)",
+                "Use `self.env().caller()` instead of `msg.sender`",
+                "Emit events with `self.env().emit_event()`",
+            ]),
+            vec![
+                pattern("require(condition, \"error message\");", "ensure!(condition, Error::CustomError);"),
+                pattern("msg.sender", "self.env().caller()"),
+                pattern("emit Transfer(from, to, value);", "self.env().emit_event(Transfer { from, to, value });"),
+            ],
+        ),
+        "Flipper" => (
+            "Solidity Flipper to ink! Flipper",
+            vec![
+                difference("Storage", "Both use simple boolean storage, but ink! requires `#[ink(storage)]`"),
+                difference("State Access", "Solidity direct access vs ink! `self.value`"),
+                difference("Function Annotations", "ink! requires `#[ink(message)]` for public functions"),
+            ],
+            steps(&[
+                "Wrap storage in struct with `#[ink(storage)]`",
+                "Add `#[ink(constructor)]` and `#[ink(message)]` annotations",
+                "Use `self.value` instead of direct variable access",
+                "Return values explicitly (ink! functions can return values)",
+            ]),
+            vec![
+                pattern("bool public value;", "#[ink(storage)] pub struct Flipper { value: bool }"),
+                pattern(
+                    "function flip() public { value = !value; }",
+                    "#[ink(message)] pub fn flip(&mut self) { self.value = !self.value; }",
+                ),
+            ],
+        ),
+        "Counter" => (
+            "Solidity Counter to ink! Incrementer",
+            vec![
+                difference("Storage", "Solidity `uint256` becomes ink! `i32` or `u32`"),
+                difference("Overflow Protection", "Solidity has built-in overflow protection, ink! uses checked arithmetic"),
+                difference("Access Control", "Both can implement similar patterns"),
+            ],
+            steps(&[
+                "Define storage struct with `#[ink(storage)]`",
+                "Use `saturating_add()` or `checked_add()` for safe arithmetic",
+                "Add proper error handling for overflow/underflow",
+                "Use `#[ink(constructor)]` for initialization",
+            ]),
+            vec![pattern("count++", "self.count = self.count.saturating_add(1)")],
+        ),
+        "SimpleNFT" => (
+            "Solidity ERC721 to ink! ERC721",
+            vec![
+                difference("Token ID Type", "Solidity `uint256` vs ink! `u32` or custom type"),
+                difference("Storage Maps", "Multiple mappings become `Mapping<K, V>` in ink!"),
+                difference("Approval System", "Similar logic but different syntax"),
+                difference("Safe Transfer", "ink! has built-in safety checks"),
+            ],
+            steps(&[
+                "Define `TokenId` type alias",
+                "Convert all mappings to ink! `Mapping<K, V>`",
+                "Implement proper error handling for transfers",
+                "Add `#[ink(event)]` for Transfer and Approval events",
+                "Use `ensure!()` for validation checks",
+            ]),
+            vec![pattern(
+                "mapping(uint256 => address) private _owners;",
+                "token_owner: Mapping<TokenId, AccountId>",
+            )],
+        ),
+        _ => return None,
+    };
+
+    Some(MigrationGuide {
+        contract_type: contract_type.to_string(),
+        title: title.to_string(),
+        key_differences,
+        migration_steps,
+        patterns,
+    })
+}
+
+impl MigrationGuide {
+    /// The guide as the markdown section embedded with each training pair.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("\n## Migration Notes: {}\n\n### Key Differences:\n", self.title);
+        for (i, difference) in self.key_differences.iter().enumerate() {
+            markdown.push_str(&format!("{}. **{}**: {}\n", i + 1, difference.topic, difference.detail));
+        }
+        markdown.push_str("\n### Migration Steps:\n");
+        for (i, step) in self.migration_steps.iter().enumerate() {
+            markdown.push_str(&format!("{}. {}\n", i + 1, step));
+        }
+        markdown.push_str("\n### Common Patterns:\n");
+        let patterns: Vec<String> = self
+            .patterns
+            .iter()
+            .map(|p| format!("- Solidity: `{}`\n- ink!: `{}`\n", p.solidity, p.ink))
+            .collect();
+        markdown.push_str(&patterns.join("\n"));
+        markdown
+    }
+
+    /// The guide as a self-contained HTML fragment for embedding in docs pages.
+    pub fn to_html(&self) -> String {
+        let mut html = format!(
+            "<article class=\"migration-guide\" data-contract-type=\"{}\">\n<h2>Migration Notes: {}</h2>\n<h3>Key Differences</h3>\n<ol>\n",
+            escape_html(&self.contract_type),
+            escape_html(&self.title)
+        );
+        for difference in &self.key_differences {
+            html.push_str(&format!(
+                "<li><strong>{}</strong>: {}</li>\n",
+                escape_html(&difference.topic),
+                inline_html(&difference.detail)
+            ));
+        }
+        html.push_str("</ol>\n<h3>Migration Steps</h3>\n<ol>\n");
+        for step in &self.migration_steps {
+            html.push_str(&format!("<li>{}</li>\n", inline_html(step)));
+        }
+        html.push_str("</ol>\n<h3>Common Patterns</h3>\n<table>\n<thead><tr><th>Solidity</th><th>ink!</th></tr></thead>\n<tbody>\n");
+        for pattern in &self.patterns {
+            html.push_str(&format!(
+                "<tr><td><code>{}</code></td><td><code>{}</code></td></tr>\n",
+                escape_html(&pattern.solidity),
+                escape_html(&pattern.ink)
+            ));
+        }
+        html.push_str("</tbody>\n</table>\n</article>\n");
+        html
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Escape `text`, turning backtick spans into `<code>` elements.
+fn inline_html(text: &str) -> String {
+    text.split('`')
+        .enumerate()
+        .map(|(i, part)| {
+            if i % 2 == 1 {
+                format!("<code>{}</code>", escape_html(part))
+            } else {
+                escape_html(part)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_guided_type_has_a_guide() {
+        for contract_type in GUIDED_CONTRACT_TYPES {
+            let guide = migration_guide(contract_type).unwrap();
+            assert!(!guide.key_differences.is_empty() && !guide.migration_steps.is_empty() && !guide.patterns.is_empty());
+        }
+        assert!(migration_guide("Vault").is_none());
+    }
+
+    #[test]
+    fn test_guide_renders_to_markdown_and_html() {
+        let guide = migration_guide("SimpleERC20").unwrap();
+
+        let markdown = guide.to_markdown();
+        assert!(markdown.starts_with("\n## Migration Notes: Solidity ERC20 to ink! ERC20\n\n### Key Differences:\n1. **Storage**: "));
+        assert!(markdown.contains("- Solidity: `msg.sender`\n- ink!: `self.env().caller()`\n\n- Solidity: `emit"));
+
+        let html = guide.to_html();
+        assert!(html.contains("<li><strong>Storage</strong>: Solidity uses <code>mapping(address =&gt; uint256)</code> while"));
+        assert!(html.contains("<tr><td><code>msg.sender</code></td><td><code>self.env().caller()</code></td></tr>"));
+        assert!(html.contains("<code>require(condition, &quot;error message&quot;);</code>"));
+    }
+}
@@ -0,0 +1,169 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use subxt::utils::AccountId32;
+
+/// Network used when `ACTIVE_NETWORK` is unset and several are configured
+pub const DEFAULT_NETWORK: &str = "rococo";
+
+/// Where the DynaVest contracts live on one network.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct NetworkConfig {
+    /// RPC endpoints in order of preference; later ones are fallbacks
+    pub rpc_urls: Vec<String>,
+    /// SS58 address of the strategy manager contract
+    pub strategy_manager_address: String,
+    /// SS58 address of the DynaVest strategy contract
+    pub dynavest_strategy_address: String,
+}
+
+impl Default for NetworkConfig {
+    /// The Rococo contracts parachain deployment.
+    fn default() -> Self {
+        Self {
+            rpc_urls: vec![
+                "wss://rococo-contracts-rpc.polkadot.io".to_string(),
+                "wss://rpc.polkadot.io".to_string(),
+                "wss://kusama-rpc.polkadot.io".to_string(),
+            ],
+            strategy_manager_address: "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY".to_string(),
+            dynavest_strategy_address: "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty".to_string(),
+        }
+    }
+}
+
+impl NetworkConfig {
+    /// The preferred RPC endpoint.
+    pub fn rpc_url(&self) -> &str {
+        &self.rpc_urls[0]
+    }
+
+    pub fn strategy_manager(&self) -> Result<AccountId32, String> {
+        parse_address(&self.strategy_manager_address, "strategy_manager_address")
+    }
+
+    pub fn dynavest_strategy(&self) -> Result<AccountId32, String> {
+        parse_address(&self.dynavest_strategy_address, "dynavest_strategy_address")
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.rpc_urls.is_empty() {
+            return Err("rpc_urls is empty".to_string());
+        }
+        if self.rpc_urls.iter().any(|url| url.trim().is_empty()) {
+            return Err("rpc_urls contains an empty url".to_string());
+        }
+        self.strategy_manager()?;
+        self.dynavest_strategy()?;
+        Ok(())
+    }
+}
+
+fn parse_address(address: &str, field: &str) -> Result<AccountId32, String> {
+    AccountId32::from_str(address).map_err(|e| format!("{} '{}' is not a valid SS58 address: {:?}", field, address, e))
+}
+
+/// Every configured network and the one the service runs against.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkSettings {
+    pub active: String,
+    pub networks: BTreeMap<String, NetworkConfig>,
+}
+
+impl NetworkSettings {
+    /// Build from a JSON object of network name to `NetworkConfig`, checking
+    /// every address. `active` picks the network; without it the default
+    /// network is used, or the only one when just one is configured.
+    pub fn parse(json: &str, active: Option<&str>) -> Result<Self, String> {
+        let networks: BTreeMap<String, NetworkConfig> =
+            serde_json::from_str(json).map_err(|e| format!("Invalid network config: {}", e))?;
+        Self::new(networks, active)
+    }
+
+    fn new(networks: BTreeMap<String, NetworkConfig>, active: Option<&str>) -> Result<Self, String> {
+        for (name, network) in &networks {
+            network.validate().map_err(|e| format!("Network '{}': {}", name, e))?;
+        }
+
+        let active = match active.map(str::trim).filter(|a| !a.is_empty()) {
+            Some(active) => active.to_string(),
+            None if networks.len() == 1 => networks.keys().next().cloned().unwrap_or_default(),
+            None => DEFAULT_NETWORK.to_string(),
+        };
+        if !networks.contains_key(&active) {
+            return Err(format!(
+                "Active network '{}' is not configured (known: {})",
+                active,
+                networks.keys().cloned().collect::<Vec<_>>().join(", ")
+            ));
+        }
+
+        Ok(Self { active, networks })
+    }
+
+    /// Read networks from the JSON file at `NETWORKS_CONFIG`, selecting
+    /// `ACTIVE_NETWORK`. Without a file only the built-in Rococo network
+    /// is available.
+    pub fn from_env() -> Result<Self, String> {
+        let active = std::env::var("ACTIVE_NETWORK").ok();
+        match std::env::var("NETWORKS_CONFIG") {
+            Ok(path) => {
+                let json = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+                Self::parse(&json, active.as_deref())
+            }
+            Err(_) => Self::new(
+                BTreeMap::from([(DEFAULT_NETWORK.to_string(), NetworkConfig::default())]),
+                active.as_deref(),
+            ),
+        }
+    }
+
+    pub fn active_network(&self) -> &NetworkConfig {
+        &self.networks[&self.active]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TWO_NETWORKS: &str = r#"{
+        "rococo": {
+            "rpc_urls": ["wss://rococo-contracts-rpc.polkadot.io", "wss://rpc.polkadot.io"],
+            "strategy_manager_address": "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY",
+            "dynavest_strategy_address": "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty"
+        },
+        "local": {
+            "rpc_urls": ["ws://127.0.0.1:9944"],
+            "strategy_manager_address": "5FLSigC9HGRKVhB9FiEo4Y3koPsNmBmLJbpXg2mp1hXcS59Y",
+            "dynavest_strategy_address": "5DAAnrj7VHTznn2AWBemMuyBwZWs6FNFjdyVXUeYum3PTXFy"
+        }
+    }"#;
+
+    #[test]
+    fn test_loads_two_networks_and_selects_one() {
+        let settings = NetworkSettings::parse(TWO_NETWORKS, Some("local")).unwrap();
+        assert_eq!(settings.networks.len(), 2);
+        assert_eq!(settings.active, "local");
+        assert_eq!(settings.active_network().rpc_url(), "ws://127.0.0.1:9944");
+        assert!(settings.active_network().strategy_manager().is_ok());
+
+        // Without a selection the default network wins, fallbacks kept in order
+        let settings = NetworkSettings::parse(TWO_NETWORKS, None).unwrap();
+        assert_eq!(settings.active, "rococo");
+        assert_eq!(settings.active_network().rpc_urls, ["wss://rococo-contracts-rpc.polkadot.io", "wss://rpc.polkadot.io"]);
+
+        let unknown = NetworkSettings::parse(TWO_NETWORKS, Some("kusama")).unwrap_err();
+        assert_eq!(unknown, "Active network 'kusama' is not configured (known: local, rococo)");
+    }
+
+    #[test]
+    fn test_invalid_address_is_rejected() {
+        let json = r#"{"dev": {"rpc_urls": ["ws://127.0.0.1:9944"], "strategy_manager_address": "not-an-address", "dynavest_strategy_address": "5DAAnrj7VHTznn2AWBemMuyBwZWs6FNFjdyVXUeYum3PTXFy"}}"#;
+        let error = NetworkSettings::parse(json, None).unwrap_err();
+        assert!(error.starts_with("Network 'dev': strategy_manager_address 'not-an-address' is not a valid SS58 address"));
+
+        let json = r#"{"dev": {"rpc_urls": [], "strategy_manager_address": "5FLSigC9HGRKVhB9FiEo4Y3koPsNmBmLJbpXg2mp1hXcS59Y", "dynavest_strategy_address": "5DAAnrj7VHTznn2AWBemMuyBwZWs6FNFjdyVXUeYum3PTXFy"}}"#;
+        assert_eq!(NetworkSettings::parse(json, None).unwrap_err(), "Network 'dev': rpc_urls is empty");
+    }
+}
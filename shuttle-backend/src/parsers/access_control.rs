@@ -0,0 +1,174 @@
+use super::library::to_snake_case;
+use super::solidity_parser::{ModifierInvocation, SolidityContract};
+
+/// OpenZeppelin bases whose `onlyOwner` / `owner()` come from inheritance
+const OWNERSHIP_BASES: &[&str] = &["Ownable", "Ownable2Step", "OwnableUpgradeable"];
+
+/// OpenZeppelin bases whose `hasRole` / `onlyRole` come from inheritance
+const ROLE_BASES: &[&str] = &[
+    "AccessControl",
+    "AccessControlEnumerable",
+    "AccessControlUpgradeable",
+    "AccessControlDefaultAdminRules",
+];
+
+const OWNERSHIP_NOTE: &str = "## Migration Notes: Ownable to ink!
+
+Solidity (Ownable):
+- `owner()` and the `onlyOwner` modifier are inherited from OpenZeppelin
+- `transferOwnership` / `renounceOwnership` come with the base contract
+
+ink! Equivalent:
+- Store the owner yourself: `owner: AccountId` in `#[ink(storage)]`, set to `Self::env().caller()` in the constructor
+- Replace `onlyOwner` with a check at the top of each message: `if self.env().caller() != self.owner { return Err(Error::NotOwner); }`
+- Add an explicit `transfer_ownership(new_owner: AccountId)` message guarded by the same check";
+
+const ROLES_NOTE: &str = "## Migration Notes: AccessControl to ink!
+
+Solidity (AccessControl):
+- `hasRole`, `grantRole`, `revokeRole` and the `onlyRole` modifier are inherited from OpenZeppelin
+- Roles are `bytes32` constants such as `keccak256(\"MINTER_ROLE\")`
+
+ink! Equivalent:
+- Define roles as a `u32` or enum type `Role` and store grants in `roles: Mapping<(Role, AccountId), ()>`
+- `has_role` becomes `self.roles.contains((role, account))`; grant with `insert`, revoke with `remove`
+- Replace `onlyRole(ROLE)` with `if !self.has_role(ROLE, self.env().caller()) { return Err(Error::MissingRole); }`
+- Grant the admin role to the caller in the constructor";
+
+/// The ink! check to inline at the top of a message for one applied
+/// modifier, with the modifier's arguments threaded through.
+pub fn ink_guard(invocation: &ModifierInvocation) -> String {
+    match (invocation.name.as_str(), invocation.args.as_slice()) {
+        ("onlyRole", [role]) => format!(
+            "if !self.has_role({}, self.env().caller()) {{ return Err(Error::MissingRole); }}",
+            role
+        ),
+        ("onlyOwner", []) => "if self.env().caller() != self.owner { return Err(Error::NotOwner); }".to_string(),
+        // Anything else becomes a helper holding the modifier's pre-`_;` checks
+        (name, args) => format!("self.{}({})?;", to_snake_case(name), args.join(", ")),
+    }
+}
+
+/// Guards for every modifier applied to a contract function, as
+/// `(function, guard)` pairs in declaration order.
+#[allow(dead_code)]
+pub fn function_guards(contract: &SolidityContract) -> Vec<(String, String)> {
+    contract
+        .functions
+        .iter()
+        .flat_map(|function| function.modifiers.iter().map(|m| (function.name.clone(), ink_guard(m))))
+        .collect()
+}
+
+/// Migration notes for access control the contract gets from well-known
+/// OpenZeppelin bases, which the parser can't see without the base source,
+/// and for `onlyRole(...)` guards applied to its functions.
+pub fn access_control_notes(contract: &SolidityContract) -> Vec<String> {
+    let inherits_any = |bases: &[&str]| contract.inherits.iter().any(|b| bases.contains(&b.as_str()));
+    let role_guards: Vec<String> = contract
+        .functions
+        .iter()
+        .flat_map(|function| {
+            function
+                .modifiers
+                .iter()
+                .filter(|m| m.name == "onlyRole")
+                .map(move |m| format!("- `{}`: `{}`", function.name, ink_guard(m)))
+        })
+        .collect();
+
+    let mut notes = Vec::new();
+    if inherits_any(OWNERSHIP_BASES) {
+        notes.push(OWNERSHIP_NOTE.to_string());
+    }
+    if inherits_any(ROLE_BASES) || !role_guards.is_empty() {
+        let mut note = ROLES_NOTE.to_string();
+        if !role_guards.is_empty() {
+            note.push_str("\n\nGuards for this contract's messages:\n");
+            note.push_str(&role_guards.join("\n"));
+        }
+        notes.push(note);
+    }
+    notes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::solidity_parser::SolidityParser;
+
+    #[test]
+    fn should_emit_ownership_note_for_ownable() {
+        let contract = SolidityParser::new()
+            .parse_contract(
+                r#"
+contract Vault is Ownable {
+    function withdraw() public onlyOwner {}
+}
+"#,
+            )
+            .unwrap();
+
+        let notes = access_control_notes(&contract);
+        assert_eq!(notes.len(), 1);
+        assert!(notes[0].contains("Ownable to ink!"));
+        assert!(notes[0].contains("owner: AccountId"));
+    }
+
+    #[test]
+    fn should_thread_modifier_arguments_into_guards() {
+        let contract = SolidityParser::new()
+            .parse_contract(
+                r#"
+contract Treasury {
+    bytes32 public constant ADMIN = keccak256("ADMIN");
+
+    modifier onlyRole(bytes32 role) {
+        require(hasRole(role, msg.sender), "missing role");
+        _;
+    }
+
+    function setFee(uint256 fee) external onlyRole(ADMIN) {
+        feeBps = fee;
+    }
+
+    function sweep(address to) public onlyRole(ADMIN) minBalance(1 ether, to) returns (bool) {
+        return true;
+    }
+}
+"#,
+            )
+            .unwrap();
+
+        let set_fee = contract.functions.iter().find(|f| f.name == "setFee").unwrap();
+        assert_eq!(set_fee.modifiers[0].name, "onlyRole");
+        assert_eq!(set_fee.modifiers[0].args, vec!["ADMIN"]);
+
+        assert_eq!(
+            function_guards(&contract),
+            vec![
+                (
+                    "setFee".to_string(),
+                    "if !self.has_role(ADMIN, self.env().caller()) { return Err(Error::MissingRole); }".to_string()
+                ),
+                (
+                    "sweep".to_string(),
+                    "if !self.has_role(ADMIN, self.env().caller()) { return Err(Error::MissingRole); }".to_string()
+                ),
+                ("sweep".to_string(), "self.min_balance(1 ether, to)?;".to_string()),
+            ]
+        );
+
+        // No AccessControl base, but the applied guards still need role storage
+        let notes = access_control_notes(&contract);
+        assert_eq!(notes.len(), 1);
+        assert!(notes[0].contains("Mapping<(Role, AccountId), ()>"));
+        assert!(notes[0].contains("- `setFee`: `if !self.has_role(ADMIN, self.env().caller())"));
+    }
+
+    #[test]
+    fn should_emit_no_notes_without_known_bases() {
+        let contract = SolidityParser::new().parse_contract("contract Plain is Base {}").unwrap();
+        assert!(access_control_notes(&contract).is_empty());
+    }
+}
@@ -0,0 +1,267 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::ink_generator::generate_transfer_stub;
+use super::solidity_parser::SolidityContract;
+
+/// Ways a Solidity contract sends native value out
+const RELEASE_PATTERNS: &[&str] = &[".transfer(", ".send(", ".call{value"];
+
+const DEPOSIT_NOTE: &str = "## Migration Notes: Holding value in ink!
+
+Solidity:
+- `payable` functions (and `receive()`/`fallback()`) accept ETH; the amount is `msg.value`
+- The contract balance is `address(this).balance`
+
+ink! Equivalent:
+- Mark deposit messages `#[ink(message, payable)]`; non-payable messages reject value automatically
+- Read the deposit with `self.env().transferred_value()` and record it in storage
+- The contract's own balance is `self.env().balance()`
+- ink! has no `receive()`: plain transfers to the contract bypass your code, so expose an explicit payable `deposit` message
+
+```rust
+#[ink(message, payable)]
+pub fn deposit(&mut self) {
+    let amount = self.env().transferred_value();
+    let caller = self.env().caller();
+    let balance = self.deposits.get(caller).unwrap_or(0);
+    self.deposits.insert(caller, &(balance + amount));
+}
+```";
+
+const RELEASE_NOTE: &str = "## Migration Notes: Releasing value in ink!
+
+Solidity:
+- `to.transfer(amount)` reverts on failure, `to.send(amount)` returns `false`, `to.call{value: amount}(\"\")` returns `(bool, bytes)`
+
+ink! Equivalent:
+- Use `self.env().transfer(to, amount)`, which returns `Result<(), ink::env::Error>` instead of reverting
+- Map the error into your own `Error` enum and return it; an ignored `Err` silently keeps the funds
+- Update storage *before* transferring, as with checks-effects-interactions in Solidity
+- There is no 2300 gas stipend, so the recipient's code does not limit the transfer; guard against reentrancy explicitly if the recipient is a contract
+
+```rust
+#[ink(message)]
+pub fn release(&mut self, to: AccountId, amount: Balance) -> Result<(), Error> {
+    self.deposits.insert(to, &0);
+    self.env().transfer(to, amount).map_err(|_| Error::TransferFailed)
+}
+```";
+
+/// How a Solidity function sends native value.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferKind {
+    /// `to.transfer(amount)`, reverting on failure
+    Transfer,
+    /// `to.send(amount)`, returning `false` on failure
+    Send,
+    /// `to.call{value: amount}(data)`, which can also run arbitrary code
+    Call,
+}
+
+/// One native value transfer found in a function body.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct ValueTransfer {
+    pub function: String,
+    pub kind: TransferKind,
+    /// Recipient expression as written, without any `payable(...)` wrapper
+    pub recipient: String,
+    /// Amount expression as written
+    pub amount: String,
+}
+
+impl ValueTransfer {
+    /// Low-level calls may forward calldata, so `self.env().transfer` is not
+    /// necessarily a faithful replacement.
+    pub fn needs_manual_review(&self) -> bool {
+        self.kind == TransferKind::Call
+    }
+}
+
+/// Every `transfer`, `send` and `call{value: ...}` of native value, in
+/// function order. `transfer` and `send` must take a single argument, so
+/// ERC20 `token.transfer(to, amount)` calls are not reported.
+pub fn detect_value_transfers(contract: &SolidityContract) -> Result<Vec<ValueTransfer>, String> {
+    let recipient = r"(?:payable\(\s*([^()]+?)\s*\)|([A-Za-z_][\w.\[\]]*))";
+    let single_arg = Regex::new(&format!(r"{}\s*\.\s*(transfer|send)\s*\(\s*((?:[^(),]|\([^()]*\))+?)\s*\)", recipient))
+        .map_err(|e| format!("Regex error: {}", e))?;
+    let call = Regex::new(&format!(r"{}\s*\.\s*call\s*\{{([^}}]*)\}}", recipient))
+        .map_err(|e| format!("Regex error: {}", e))?;
+    let value = Regex::new(r"\bvalue\s*:\s*([^,}]+)").map_err(|e| format!("Regex error: {}", e))?;
+
+    let mut transfers = Vec::new();
+    for function in &contract.functions {
+        let recipient_of = |c: &regex::Captures| c.get(1).or(c.get(2)).map(|m| m.as_str().trim().to_string()).unwrap_or_default();
+        let mut found: Vec<(usize, ValueTransfer)> = single_arg
+            .captures_iter(&function.body)
+            .map(|c| {
+                let kind = if &c[3] == "transfer" { TransferKind::Transfer } else { TransferKind::Send };
+                (
+                    c.get(0).unwrap().start(),
+                    ValueTransfer {
+                        function: function.name.clone(),
+                        kind,
+                        recipient: recipient_of(&c),
+                        amount: c[4].trim().to_string(),
+                    },
+                )
+            })
+            .collect();
+        for c in call.captures_iter(&function.body) {
+            let Some(amount) = value.captures(&c[3]) else {
+                continue;
+            };
+            found.push((
+                c.get(0).unwrap().start(),
+                ValueTransfer {
+                    function: function.name.clone(),
+                    kind: TransferKind::Call,
+                    recipient: recipient_of(&c),
+                    amount: amount[1].trim().to_string(),
+                },
+            ));
+        }
+        found.sort_by_key(|(start, _)| *start);
+        transfers.extend(found.into_iter().map(|(_, transfer)| transfer));
+    }
+    Ok(transfers)
+}
+
+/// One note listing each native transfer with its ink! replacement, or none
+/// when the contract sends no value.
+pub fn transfer_notes(contract: &SolidityContract) -> Vec<String> {
+    let transfers = match detect_value_transfers(contract) {
+        Ok(transfers) if !transfers.is_empty() => transfers,
+        _ => return Vec::new(),
+    };
+
+    let mut note = "## Migration Notes: Native transfers\n\nEach becomes `self.env().transfer(to, amount)`, which returns a `Result` instead of reverting.".to_string();
+    for transfer in &transfers {
+        let solidity = match transfer.kind {
+            TransferKind::Transfer => format!("{}.transfer({})", transfer.recipient, transfer.amount),
+            TransferKind::Send => format!("{}.send({})", transfer.recipient, transfer.amount),
+            TransferKind::Call => format!("{}.call{{value: {}}}(...)", transfer.recipient, transfer.amount),
+        };
+        note.push_str(&format!("\n\n`{}` in `{}`", solidity, transfer.function));
+        if transfer.needs_manual_review() {
+            note.push_str(" (needs manual review: a low-level call can also pass calldata and run the recipient's code; use `build_call` if it does more than send value)");
+        }
+        note.push_str(&format!(":\n```rust\n{}```", generate_transfer_stub(transfer)));
+    }
+    vec![note]
+}
+
+/// Balance custody notes for contracts that accept and/or send native value.
+pub fn custody_notes(contract: &SolidityContract) -> Vec<String> {
+    let accepts_value = contract.accepts_plain_transfers
+        || contract
+            .functions
+            .iter()
+            .any(|f| f.mutability.as_deref() == Some("payable"));
+    let releases_value = contract
+        .functions
+        .iter()
+        .any(|f| RELEASE_PATTERNS.iter().any(|pattern| f.body.contains(pattern)));
+
+    let mut notes = Vec::new();
+    if accepts_value {
+        notes.push(DEPOSIT_NOTE.to_string());
+    }
+    if releases_value {
+        notes.push(RELEASE_NOTE.to_string());
+    }
+    notes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::solidity_parser::SolidityParser;
+
+    #[test]
+    fn should_emit_custody_notes_for_escrow() {
+        let contract = SolidityParser::new()
+            .parse_contract(
+                r#"
+contract SimpleEscrow {
+    mapping(address => uint256) public deposits;
+
+    function deposit() public payable {
+        deposits[msg.sender] += msg.value;
+    }
+
+    function release(address payable payee) public {
+        uint256 amount = deposits[payee];
+        deposits[payee] = 0;
+        payee.transfer(amount);
+    }
+}
+"#,
+            )
+            .unwrap();
+
+        let notes = custody_notes(&contract);
+        assert_eq!(notes.len(), 2);
+        assert!(notes[0].contains("self.env().transferred_value()"));
+        assert!(notes[1].contains("self.env().transfer(to, amount)"));
+        assert!(notes[1].contains("instead of reverting"));
+    }
+
+    #[test]
+    fn should_map_native_transfers_to_ink_transfer() {
+        let contract = SolidityParser::new()
+            .parse_contract(
+                r#"
+contract Payout {
+    IERC20 public token;
+
+    function pay(address payable to, uint256 amount) public {
+        to.transfer(amount);
+        token.transfer(to, amount);
+    }
+
+    function withdraw(uint256 amount) public {
+        (bool ok, ) = payable(msg.sender).call{value: amount}("");
+        require(ok);
+    }
+}
+"#,
+            )
+            .unwrap();
+
+        let transfers = detect_value_transfers(&contract).unwrap();
+        assert_eq!(
+            transfers,
+            vec![
+                ValueTransfer {
+                    function: "pay".to_string(),
+                    kind: TransferKind::Transfer,
+                    recipient: "to".to_string(),
+                    amount: "amount".to_string(),
+                },
+                ValueTransfer {
+                    function: "withdraw".to_string(),
+                    kind: TransferKind::Call,
+                    recipient: "msg.sender".to_string(),
+                    amount: "amount".to_string(),
+                },
+            ]
+        );
+
+        let notes = transfer_notes(&contract);
+        assert_eq!(notes.len(), 1);
+        assert!(notes[0].contains("`to.transfer(amount)` in `pay`:\n```rust\nself.env()\n    .transfer(to, amount)\n    .map_err(|_| Error::TransferFailed)?;\n```"));
+        assert!(notes[0].contains("`msg.sender.call{value: amount}(...)` in `withdraw` (needs manual review"));
+        assert!(notes[0].contains(".transfer(self.env().caller(), amount)"));
+    }
+
+    #[test]
+    fn should_emit_no_custody_notes_without_value_flow() {
+        let contract = SolidityParser::new()
+            .parse_contract("contract Flipper {\n    bool value;\n    function flip() public { value = !value; }\n}")
+            .unwrap();
+        assert!(custody_notes(&contract).is_empty());
+    }
+}
@@ -0,0 +1,103 @@
+use super::solidity_parser::SolidityContract;
+
+/// Solidity features with no direct ink! equivalent
+const UNSUPPORTED_CONSTRUCTS: &[&str] = &[
+    "delegatecall",
+    "selfdestruct",
+    "tx.origin",
+    "msg.data",
+    "abi.encodePacked",
+    "ecrecover",
+    "block.difficulty",
+    "gasleft()",
+];
+
+/// Rough 1-10 estimate of how much hand work porting `contract` to ink! takes:
+/// 1 is a Flipper, 10 needs a redesign.
+pub fn migration_difficulty(contract: &SolidityContract) -> u8 {
+    let bodies: Vec<&str> = contract
+        .functions
+        .iter()
+        .map(|f| f.body.as_str())
+        .chain(contract.modifiers.iter().map(|m| m.body.as_str()))
+        .collect();
+
+    let unsupported = UNSUPPORTED_CONSTRUCTS
+        .iter()
+        .filter(|construct| bodies.iter().any(|body| body.contains(*construct)))
+        .count();
+    let uses_assembly = bodies.iter().any(|body| body.contains("assembly"));
+    let payable = contract
+        .functions
+        .iter()
+        .any(|f| f.mutability.as_deref() == Some("payable"));
+
+    let mut score = 1;
+    score += (unsupported * 2).min(4);
+    // Each base contract's storage and behaviour has to be flattened by hand
+    score += contract.inherits.len().min(3);
+    score += contract.custom_errors.len().div_ceil(3).min(2);
+    if payable {
+        score += 1;
+    }
+    if uses_assembly {
+        score += 4;
+    }
+
+    score.min(10) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::solidity_parser::SolidityParser;
+
+    #[test]
+    fn should_score_flipper_low() {
+        let contract = SolidityParser::new()
+            .parse_contract(
+                r#"
+contract Flipper {
+    bool private value;
+
+    function flip() public {
+        value = !value;
+    }
+
+    function get() public view returns (bool) {
+        return value;
+    }
+}
+"#,
+            )
+            .unwrap();
+
+        assert_eq!(migration_difficulty(&contract), 1);
+    }
+
+    #[test]
+    fn should_score_assembly_and_deep_inheritance_high() {
+        let contract = SolidityParser::new()
+            .parse_contract(
+                r#"
+contract Proxy is Ownable, Pausable, ReentrancyGuard, Initializable {
+    error NotAllowed();
+
+    function forward(address target) public payable returns (bool) {
+        (bool ok, ) = target.delegatecall(msg.data);
+        return ok;
+    }
+
+    function codeSize(address target) public view returns (uint256) {
+        uint256 size;
+        assembly { size := extcodesize(target) }
+        return size;
+    }
+}
+"#,
+            )
+            .unwrap();
+
+        assert!(migration_difficulty(&contract) >= 8);
+    }
+}
@@ -0,0 +1,301 @@
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+
+use super::solidity_parser::{block_body, parse_imports, split_bases, SolidityContract, SolidityFunction, SolidityParser};
+
+/// A contract, interface or library declared in one of the project files.
+#[derive(Debug, Clone)]
+struct Declaration {
+    contract: SolidityContract,
+    bases: Vec<String>,
+}
+
+/// Result of resolving a root file against the rest of the project.
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct ResolvedProject {
+    /// The root contract with inherited members merged in
+    pub contract: SolidityContract,
+    /// Every file reachable from the root, in the order it was loaded
+    pub files: Vec<String>,
+}
+
+/// Follows `import` statements across an in-memory file map and merges
+/// inherited contracts and interfaces into the root contract.
+#[allow(dead_code)]
+pub struct ImportResolver<'a> {
+    files: &'a HashMap<String, String>,
+    parser: SolidityParser,
+}
+
+#[allow(dead_code)]
+impl<'a> ImportResolver<'a> {
+    pub fn new(files: &'a HashMap<String, String>) -> Self {
+        Self {
+            files,
+            parser: SolidityParser::new(),
+        }
+    }
+
+    pub fn resolve(&self, root: &str) -> Result<ResolvedProject, String> {
+        let mut order = Vec::new();
+        self.visit(root, &mut Vec::new(), &mut HashSet::new(), &mut order)?;
+
+        let mut declarations = HashMap::new();
+        let mut root_name = None;
+        for path in &order {
+            for (name, declaration) in self.parse_declarations(&self.files[path])? {
+                // The last declaration in the root file is the one being converted
+                if path == root {
+                    root_name = Some(name.clone());
+                }
+                declarations.insert(name, declaration);
+            }
+        }
+
+        let root_name = root_name.ok_or_else(|| format!("No contract declared in {}", root))?;
+        let mut contract = merge_inherited(&root_name, &declarations, &mut HashSet::new())?;
+        contract.imports = parse_imports(&self.files[root])?;
+
+        Ok(ResolvedProject { contract, files: order })
+    }
+
+    /// Depth-first walk over imports, recording files in load order and
+    /// rejecting cycles.
+    fn visit(
+        &self,
+        path: &str,
+        stack: &mut Vec<String>,
+        done: &mut HashSet<String>,
+        order: &mut Vec<String>,
+    ) -> Result<(), String> {
+        if let Some(pos) = stack.iter().position(|p| p == path) {
+            let mut cycle = stack[pos..].to_vec();
+            cycle.push(path.to_string());
+            return Err(format!("Circular import: {}", cycle.join(" -> ")));
+        }
+        if done.contains(path) {
+            return Ok(());
+        }
+
+        let content = self
+            .files
+            .get(path)
+            .ok_or_else(|| format!("Import not found: {}", path))?;
+
+        stack.push(path.to_string());
+        for import in parse_imports(content)? {
+            self.visit(&resolve_path(path, &import), stack, done, order)?;
+        }
+        stack.pop();
+
+        done.insert(path.to_string());
+        order.push(path.to_string());
+        Ok(())
+    }
+
+    fn parse_declarations(&self, content: &str) -> Result<Vec<(String, Declaration)>, String> {
+        let header_re = Regex::new(r"(?:abstract\s+)?(?:contract|interface|library)\s+(\w+)(?:\s+is\s+([^{]+))?\s*\{")
+            .map_err(|e| format!("Regex error: {}", e))?;
+
+        let mut declarations = Vec::new();
+        for captures in header_re.captures_iter(content) {
+            let name = captures.get(1).unwrap().as_str().to_string();
+            let bases: Vec<String> = captures.get(2).map(|b| split_bases(b.as_str())).unwrap_or_default();
+
+            let body_start = captures.get(0).unwrap().end();
+            let body = block_body(&content[body_start..]);
+
+            // Re-wrap as a plain contract so the regular parser picks the name up
+            let mut contract = self.parser.parse_contract(&format!("contract {} {{{}}}", name, body)).map_err(|e| e.to_string())?;
+            contract.inherits = bases.clone();
+            for signature in self.parse_signatures(body)? {
+                if !contract.functions.iter().any(|f| f.name == signature.name) {
+                    contract.functions.push(signature);
+                }
+            }
+
+            declarations.push((name, Declaration { contract, bases }));
+        }
+
+        Ok(declarations)
+    }
+
+    /// Function declarations without a body, as found in interfaces and
+    /// abstract contracts.
+    fn parse_signatures(&self, body: &str) -> Result<Vec<SolidityFunction>, String> {
+        let signature_re = Regex::new(r"function\s+(\w+)\s*\(([^)]*)\)\s*(public|external)(?:\s+(view|pure|payable))?[^;{]*?(?:returns\s*\(([^)]*)\))?\s*;")
+            .map_err(|e| format!("Regex error: {}", e))?;
+
+        let mut functions = Vec::new();
+        for captures in signature_re.captures_iter(body) {
+            let words: Vec<&str> = captures[0].split(|c: char| !c.is_alphanumeric() && c != '_').collect();
+            functions.push(SolidityFunction {
+                name: captures.get(1).unwrap().as_str().to_string(),
+                parameters: self.parser.parse_parameters(captures.get(2).unwrap().as_str())?,
+                return_type: captures
+                    .get(5)
+                    .and_then(|r| r.as_str().split_whitespace().next())
+                    .map(|r| r.to_string()),
+                visibility: captures.get(3).unwrap().as_str().to_string(),
+                mutability: captures.get(4).map(|m| m.as_str().to_string()),
+                body: String::new(),
+                is_virtual: words.contains(&"virtual"),
+                is_override: words.contains(&"override"),
+                modifiers: Vec::new(),
+            });
+        }
+
+        Ok(functions)
+    }
+}
+
+/// Resolve an import path relative to the importing file. Non-relative
+/// imports are taken as project-root paths.
+fn resolve_path(from: &str, import: &str) -> String {
+    if !import.starts_with('.') {
+        return import.to_string();
+    }
+
+    let mut parts: Vec<&str> = from.split('/').collect();
+    parts.pop();
+    for segment in import.split('/') {
+        match segment {
+            "." | "" => {}
+            ".." => {
+                parts.pop();
+            }
+            other => parts.push(other),
+        }
+    }
+    parts.join("/")
+}
+
+/// Merge members of every base into `name`, letting the derived contract's
+/// own definitions win.
+fn merge_inherited(
+    name: &str,
+    declarations: &HashMap<String, Declaration>,
+    seen: &mut HashSet<String>,
+) -> Result<SolidityContract, String> {
+    if !seen.insert(name.to_string()) {
+        return Err(format!("Circular inheritance involving {}", name));
+    }
+
+    let declaration = declarations
+        .get(name)
+        .ok_or_else(|| format!("Unresolved base contract: {}", name))?;
+    let mut contract = declaration.contract.clone();
+
+    for base in &declaration.bases {
+        let inherited = merge_inherited(base, declarations, seen)?;
+
+        for function in inherited.functions {
+            if function.name != "constructor" && !contract.functions.iter().any(|f| f.name == function.name) {
+                contract.functions.push(function);
+            }
+        }
+        for variable in inherited.state_variables {
+            if !contract.state_variables.iter().any(|v| v.name == variable.name) {
+                contract.state_variables.push(variable);
+            }
+        }
+        for event in inherited.events {
+            if !contract.events.iter().any(|e| e.name == event.name) {
+                contract.events.push(event);
+            }
+        }
+        for error in inherited.custom_errors {
+            if !contract.custom_errors.contains(&error) {
+                contract.custom_errors.push(error);
+            }
+        }
+        for modifier in inherited.modifiers {
+            if !contract.modifiers.iter().any(|m| m.name == modifier.name) {
+                contract.modifiers.push(modifier);
+            }
+        }
+    }
+
+    seen.remove(name);
+    Ok(contract)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_merge_imported_interface_into_contract() {
+        let mut files = HashMap::new();
+        files.insert(
+            "contracts/interfaces/IERC20.sol".to_string(),
+            r#"
+pragma solidity ^0.8.0;
+
+interface IERC20 {
+    event Transfer(address indexed from, address indexed to, uint256 value);
+
+    function totalSupply() external view returns (uint256);
+    function balanceOf(address account) external view returns (uint256);
+    function transfer(address to, uint256 amount) external returns (bool);
+}
+"#
+            .to_string(),
+        );
+        files.insert(
+            "contracts/Token.sol".to_string(),
+            r#"
+pragma solidity ^0.8.0;
+
+import "./interfaces/IERC20.sol";
+
+contract Token is IERC20 {
+    uint256 public supply;
+
+    function transfer(address to, uint256 amount) public returns (bool) {
+        return true;
+    }
+}
+"#
+            .to_string(),
+        );
+
+        let resolved = ImportResolver::new(&files).resolve("contracts/Token.sol").unwrap();
+
+        assert_eq!(resolved.contract.name, "Token");
+        assert_eq!(resolved.files, vec!["contracts/interfaces/IERC20.sol", "contracts/Token.sol"]);
+        assert_eq!(resolved.contract.imports, vec!["./interfaces/IERC20.sol"]);
+
+        let balance_of = resolved.contract.functions.iter().find(|f| f.name == "balanceOf").unwrap();
+        assert_eq!(balance_of.parameters[0].type_name, "address");
+        assert_eq!(balance_of.mutability, Some("view".to_string()));
+        assert_eq!(balance_of.return_type, Some("uint256".to_string()));
+
+        // The contract's own transfer wins over the interface declaration
+        let transfers: Vec<_> = resolved.contract.functions.iter().filter(|f| f.name == "transfer").collect();
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].visibility, "public");
+
+        assert!(resolved.contract.events.iter().any(|e| e.name == "Transfer"));
+    }
+
+    #[test]
+    fn should_report_circular_imports() {
+        let mut files = HashMap::new();
+        files.insert("A.sol".to_string(), "import \"./B.sol\";\ncontract A {}".to_string());
+        files.insert("B.sol".to_string(), "import \"./A.sol\";\ncontract B {}".to_string());
+
+        let err = ImportResolver::new(&files).resolve("A.sol").unwrap_err();
+        assert_eq!(err, "Circular import: A.sol -> B.sol -> A.sol");
+    }
+
+    #[test]
+    fn should_report_missing_imports() {
+        let mut files = HashMap::new();
+        files.insert("A.sol".to_string(), "import \"./Missing.sol\";\ncontract A {}".to_string());
+
+        let err = ImportResolver::new(&files).resolve("A.sol").unwrap_err();
+        assert_eq!(err, "Import not found: Missing.sol");
+    }
+}
@@ -0,0 +1,78 @@
+use super::solidity_parser::{SolidityContract, SolidityFunction};
+
+const FLATTEN_NOTE: &str = "## Migration Notes: Inheritance and overrides in ink!
+
+Solidity:
+- `virtual` functions may be replaced by derived contracts; `override` marks the replacement
+- `super.fn()` calls the next implementation up the inheritance chain
+
+ink! Equivalent:
+- ink! has no contract inheritance: everything lives in a single `#[ink::contract]` module
+- Flatten each overridden function into one message holding the most-derived implementation
+- Inline any `super.fn()` logic the override relied on, or move it into a private helper on the same `impl`
+- Shared behaviour across contracts belongs in a trait with `#[ink::trait_definition]`, not a base contract";
+
+/// Note on flattening inheritance, listing the contract's `virtual` and
+/// `override` functions, when it declares any.
+pub fn inheritance_notes(contract: &SolidityContract) -> Vec<String> {
+    let names = |pick: fn(&SolidityFunction) -> bool| {
+        contract
+            .functions
+            .iter()
+            .filter(|f| pick(f))
+            .map(|f| format!("`{}`", f.name))
+            .collect::<Vec<_>>()
+    };
+    let overrides = names(|f| f.is_override);
+    let virtuals = names(|f| f.is_virtual && !f.is_override);
+    if overrides.is_empty() && virtuals.is_empty() {
+        return Vec::new();
+    }
+
+    let mut note = FLATTEN_NOTE.to_string();
+    if !overrides.is_empty() {
+        note.push_str(&format!("\n\nOverrides to flatten: {}", overrides.join(", ")));
+    }
+    if !virtuals.is_empty() {
+        note.push_str(&format!("\n\nVirtual functions (no longer overridable): {}", virtuals.join(", ")));
+    }
+    vec![note]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::solidity_parser::SolidityParser;
+
+    #[test]
+    fn should_list_overrides_to_flatten() {
+        let contract = SolidityParser::new()
+            .parse_contract(
+                r#"
+contract Token is ERC20 {
+    function decimals() public view virtual override returns (uint8) {
+        return 6;
+    }
+
+    function hook() internal virtual {
+    }
+}
+"#,
+            )
+            .unwrap();
+
+        let notes = inheritance_notes(&contract);
+        assert_eq!(notes.len(), 1);
+        assert!(notes[0].contains("no contract inheritance"));
+        assert!(notes[0].contains("Overrides to flatten: `decimals`"));
+        assert!(notes[0].contains("no longer overridable): `hook`"));
+    }
+
+    #[test]
+    fn should_emit_no_inheritance_notes_without_specifiers() {
+        let contract = SolidityParser::new()
+            .parse_contract("contract Flipper {\n    bool value;\n    function flip() public { value = !value; }\n}")
+            .unwrap();
+        assert!(inheritance_notes(&contract).is_empty());
+    }
+}
@@ -0,0 +1,269 @@
+use regex::Regex;
+
+use super::custody::{TransferKind, ValueTransfer};
+use super::library::to_snake_case;
+use super::solidity_parser::{SolidityContract, SolidityFunction, SolidityStateVariable};
+use super::type_mapping::map_solidity_type;
+
+/// Storage accesses in one function's Solidity body, counted per mention of
+/// a state variable. Compound assignments like `+=` count as both.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StorageAccess {
+    pub reads: usize,
+    pub writes: usize,
+}
+
+/// ink! contract module with `contract`'s storage, constructor, and a stub
+/// message per public or external function.
+///
+/// `constant`s become module-level `const`s rather than storage. `immutable`
+/// fields, and any other field the Solidity constructor assigns, become
+/// parameters of `new` stored as passed; the remaining fields start at
+/// their default. Each message stub carries its estimated storage reads and
+/// writes as a doc comment, a rough guide to its gas cost.
+#[allow(dead_code)]
+pub fn generate_ink_skeleton(contract: &SolidityContract) -> Result<String, String> {
+    let constructor_body = contract
+        .functions
+        .iter()
+        .find(|f| f.name == "constructor")
+        .map(|f| f.body.as_str())
+        .unwrap_or("");
+
+    let mut consts = Vec::new();
+    let mut fields = Vec::new();
+    let mut params = Vec::new();
+    let mut initializers = Vec::new();
+    for variable in &contract.state_variables {
+        let ink_type = map_solidity_type(&variable.type_name)?;
+        if variable.is_constant {
+            let value = variable.initial_value.as_deref().unwrap_or("Default::default()");
+            consts.push(format!("    pub const {}: {} = {};\n", variable.name, ink_type, value));
+            continue;
+        }
+
+        let field = to_snake_case(variable.name.trim_start_matches('_'));
+        fields.push(format!("        {}: {},\n", field, ink_type));
+        if variable.is_immutable || assigned_in(constructor_body, variable)? {
+            params.push(format!("{}: {}", field, ink_type));
+            initializers.push(format!("                {},\n", field));
+        } else if variable.is_mapping {
+            initializers.push(format!("                {}: Mapping::default(),\n", field));
+        } else {
+            initializers.push(format!("                {}: Default::default(),\n", field));
+        }
+    }
+
+    let mut module = format!("#[ink::contract]\nmod {} {{\n", to_snake_case(&contract.name));
+    if contract.state_variables.iter().any(|v| v.is_mapping) {
+        module.push_str("    use ink::storage::Mapping;\n\n");
+    }
+    if !consts.is_empty() {
+        module.push_str(&consts.concat());
+        module.push('\n');
+    }
+    let mut messages = Vec::new();
+    for function in &contract.functions {
+        if function.name != "constructor" && matches!(function.visibility.as_str(), "public" | "external") {
+            messages.push(message_stub(contract, function)?);
+        }
+    }
+
+    module.push_str(&format!(
+        "    #[ink(storage)]\n    pub struct {name} {{\n{fields}    }}\n\n    impl {name} {{\n        #[ink(constructor)]\n        pub fn new({params}) -> Self {{\n            Self {{\n{initializers}            }}\n        }}\n{messages}    }}\n}}\n",
+        name = contract.name,
+        fields = fields.concat(),
+        params = params.join(", "),
+        initializers = initializers.concat(),
+        messages = messages.concat(),
+    ));
+
+    Ok(module)
+}
+
+/// `#[ink(message)]` stub for `function`, annotated with its storage access.
+fn message_stub(contract: &SolidityContract, function: &SolidityFunction) -> Result<String, String> {
+    let access = storage_access(contract, function)?;
+    let receiver = match function.mutability.as_deref() {
+        Some("view") | Some("pure") => "&self",
+        _ => "&mut self",
+    };
+    let mut params = vec![receiver.to_string()];
+    for param in &function.parameters {
+        params.push(format!("{}: {}", to_snake_case(param.name.trim_start_matches('_')), map_solidity_type(&param.type_name)?));
+    }
+    let returns = match function.return_type.as_deref().map(|t| t.trim_end_matches(',')) {
+        Some(return_type) if !return_type.is_empty() => format!(" -> {}", map_solidity_type(return_type)?),
+        _ => String::new(),
+    };
+    let attribute = if function.mutability.as_deref() == Some("payable") {
+        "#[ink(message, payable)]"
+    } else {
+        "#[ink(message)]"
+    };
+
+    Ok(format!(
+        "\n        /// Storage: {}, {}\n        {}\n        pub fn {}({}){} {{\n            todo!()\n        }}\n",
+        plural(access.reads, "read"),
+        plural(access.writes, "write"),
+        attribute,
+        to_snake_case(function.name.trim_start_matches('_')),
+        params.join(", "),
+        returns,
+    ))
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    format!("{} {}{}", count, noun, if count == 1 { "" } else { "s" })
+}
+
+/// Storage reads and writes in `function`'s body, from each mention of a
+/// non-constant state variable: assigned with `=` or `delete`d is a write,
+/// updated in place (`+=`, `++`, ...) is a read and a write, and any other
+/// mention is a read. Indexing counts against the mapping or array itself.
+#[allow(dead_code)]
+pub fn storage_access(contract: &SolidityContract, function: &SolidityFunction) -> Result<StorageAccess, String> {
+    let regex = |pattern: String| Regex::new(&pattern).map_err(|e| format!("Regex error: {}", e));
+    let mut access = StorageAccess::default();
+    for variable in contract.state_variables.iter().filter(|v| !v.is_constant) {
+        let name = regex::escape(&variable.name);
+        let mention = regex(format!(r"(^|[^\w.])(this\.)?{}\b", name))?;
+        let assigned = regex(format!(r"(^|[^\w.])(this\.)?{}(\s*\[[^\]]*\])*\s*=([^=]|$)", name))?;
+        let deleted = regex(format!(r"\bdelete\s+(this\.)?{}\b", name))?;
+        let updated = regex(format!(
+            r"(^|[^\w.])(this\.)?{name}(\s*\[[^\]]*\])*\s*(\+\+|--|<<=|>>=|[-+*/%|&^]=)|(\+\+|--)\s*(this\.)?{name}\b",
+            name = name
+        ))?;
+
+        let overwrites = assigned.find_iter(&function.body).count() + deleted.find_iter(&function.body).count();
+        let updates = updated.find_iter(&function.body).count();
+        access.reads += mention.find_iter(&function.body).count().saturating_sub(overwrites);
+        access.writes += overwrites + updates;
+    }
+    Ok(access)
+}
+
+/// ink! statements replacing a Solidity native transfer. A failed
+/// `transfer` becomes an `Error::TransferFailed` return; `send` and `call`
+/// report failure as a `bool`, so their stubs keep one.
+pub fn generate_transfer_stub(transfer: &ValueTransfer) -> String {
+    let call = format!(
+        "self.env()\n    .transfer({}, {})",
+        ink_expression(&transfer.recipient),
+        ink_expression(&transfer.amount)
+    );
+    match transfer.kind {
+        TransferKind::Transfer => format!("{}\n    .map_err(|_| Error::TransferFailed)?;\n", call),
+        TransferKind::Send => format!("let sent = {}\n    .is_ok();\n", call),
+        TransferKind::Call => format!("// Only the value is sent; port any calldata with `build_call`\nlet ok = {}\n    .is_ok();\n", call),
+    }
+}
+
+/// `expression` with Solidity environment reads swapped for their ink!
+/// equivalents and plain identifiers snake-cased.
+fn ink_expression(expression: &str) -> String {
+    let expression = expression
+        .replace("address(this).balance", "self.env().balance()")
+        .replace("msg.sender", "self.env().caller()")
+        .replace("msg.value", "self.env().transferred_value()");
+    if expression.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        to_snake_case(expression.trim_start_matches('_'))
+    } else {
+        expression
+    }
+}
+
+/// Whether `body` assigns `variable` with a plain `=` (not `==` or `+=`).
+fn assigned_in(body: &str, variable: &SolidityStateVariable) -> Result<bool, String> {
+    let assignment = Regex::new(&format!(r"(^|[^\w.])(this\.)?{}\s*=([^=]|$)", regex::escape(&variable.name)))
+        .map_err(|e| format!("Regex error: {}", e))?;
+    Ok(assignment.is_match(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::solidity_parser::SolidityParser;
+
+    #[test]
+    fn should_route_constants_and_immutables() {
+        let contract = SolidityParser::new()
+            .parse_contract(
+                r#"
+contract Vault {
+    uint256 public constant MAX = 100;
+    address public immutable owner;
+    uint256 public fee;
+    uint256 private total;
+    mapping(address => uint256) public balances;
+
+    constructor(address _owner, uint256 _fee) {
+        owner = _owner;
+        fee = _fee;
+    }
+
+    function deposit() public payable {
+        total += msg.value;
+    }
+}
+"#,
+            )
+            .unwrap();
+
+        let max = contract.state_variables.iter().find(|v| v.name == "MAX").unwrap();
+        assert!(max.is_constant && !max.is_immutable);
+        assert_eq!(max.initial_value.as_deref(), Some("100"));
+        assert!(contract.state_variables.iter().find(|v| v.name == "owner").unwrap().is_immutable);
+
+        let skeleton = generate_ink_skeleton(&contract).unwrap();
+        assert!(skeleton.starts_with("#[ink::contract]\nmod vault {\n    use ink::storage::Mapping;\n"));
+        assert!(skeleton.contains("    pub const MAX: u128 = 100;\n"));
+        assert!(!skeleton.contains("        max: u128,"));
+        assert!(skeleton.contains("        owner: AccountId,\n"));
+        assert!(skeleton.contains("pub fn new(fee: u128, owner: AccountId) -> Self {"));
+        assert!(skeleton.contains("                owner,\n"));
+        assert!(skeleton.contains("                total: Default::default(),\n"));
+        assert!(skeleton.contains("                balances: Mapping::default(),\n"));
+        assert!(skeleton.contains(
+            "        /// Storage: 1 read, 1 write\n        #[ink(message, payable)]\n        pub fn deposit(&mut self) {\n            todo!()\n        }\n"
+        ));
+    }
+
+    #[test]
+    fn should_annotate_messages_with_storage_access() {
+        let contract = SolidityParser::new()
+            .parse_contract(
+                r#"
+contract Registry {
+    uint256 public count;
+    address public owner;
+
+    function set(uint256 _count, address _owner) public {
+        count = _count;
+        owner = _owner;
+    }
+
+    function getCount() public view returns (uint256) {
+        return count;
+    }
+}
+"#,
+            )
+            .unwrap();
+
+        let setter = storage_access(&contract, &contract.functions[0]).unwrap();
+        let getter = storage_access(&contract, &contract.functions[1]).unwrap();
+        assert_eq!(setter, StorageAccess { reads: 0, writes: 2 });
+        assert_eq!(getter, StorageAccess { reads: 1, writes: 0 });
+        assert!(setter.writes > getter.writes);
+
+        let skeleton = generate_ink_skeleton(&contract).unwrap();
+        assert!(skeleton.contains(
+            "        /// Storage: 0 reads, 2 writes\n        #[ink(message)]\n        pub fn set(&mut self, count: u128, owner: AccountId) {"
+        ));
+        assert!(skeleton.contains(
+            "        /// Storage: 1 read, 0 writes\n        #[ink(message)]\n        pub fn get_count(&self) -> u128 {"
+        ));
+    }
+}
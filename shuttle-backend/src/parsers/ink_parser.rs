@@ -1,8 +1,368 @@
-// Placeholder for ink parser - will be implemented in future tests
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::solidity_parser::{block_body, strip_comments, ParseError};
+
+/// A `#[ink(storage)]` field, the counterpart of `SolidityStateVariable`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InkStorageField {
+    pub name: String,
+    pub type_name: String,
+    pub is_mapping: bool,
+    pub key_type: Option<String>,
+    pub value_type: Option<String>,
+}
+
+/// A constructor or message argument, or an event field.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InkParameter {
+    pub name: String,
+    pub type_name: String,
+    /// Marked `#[ink(topic)]`; only set on event fields
+    pub is_topic: bool,
+}
+
+/// An `#[ink(constructor)]` or `#[ink(message)]` function.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InkFunction {
+    pub name: String,
+    /// Arguments after the `self` receiver
+    pub parameters: Vec<InkParameter>,
+    pub return_type: Option<String>,
+    /// Takes `&mut self`; false for `&self` messages and for constructors
+    pub is_mutable: bool,
+    /// Declared `#[ink(..., payable)]`
+    pub is_payable: bool,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InkEvent {
+    pub name: String,
+    pub fields: Vec<InkParameter>,
+}
+
+/// The structure of an `#[ink::contract]` module, shaped like
+/// `SolidityContract` so the two sides of a pair can be compared.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InkContract {
+    /// Name of the contract module, e.g. `erc20`
+    pub name: String,
+    /// Name of the `#[ink(storage)]` struct, e.g. `Erc20`
+    pub storage_name: String,
+    pub storage: Vec<InkStorageField>,
+    pub constructors: Vec<InkFunction>,
+    pub messages: Vec<InkFunction>,
+    pub events: Vec<InkEvent>,
+}
+
+#[derive(Default)]
 pub struct InkParser;
 
 impl InkParser {
     pub fn new() -> Self {
         Self
     }
-}
\ No newline at end of file
+
+    pub fn parse_contract(&self, content: &str) -> Result<InkContract, ParseError> {
+        let code = strip_comments(content);
+
+        let name_re = Regex::new(r"#\[ink::contract[^\]]*\]\s*(?:pub\s+)?mod\s+(\w+)").map_err(|e| format!("Regex error: {}", e))?;
+        let name = name_re
+            .captures(&code)
+            .map(|c| c[1].to_string())
+            .ok_or(ParseError::NoContract)?;
+
+        let (storage_name, storage) = self.parse_storage(&code)?;
+        let (constructors, messages) = self.parse_functions(&code)?;
+        let events = self.parse_events(&code)?;
+
+        Ok(InkContract {
+            name,
+            storage_name,
+            storage,
+            constructors,
+            messages,
+            events,
+        })
+    }
+
+    fn parse_storage(&self, code: &str) -> Result<(String, Vec<InkStorageField>), String> {
+        let storage_re = Regex::new(r"#\[ink\(storage\)\](?:\s*#\[[^\]]*\])*\s*pub\s+struct\s+(\w+)\s*\{")
+            .map_err(|e| format!("Regex error: {}", e))?;
+        let Some(captures) = storage_re.captures(code) else {
+            return Ok((String::new(), Vec::new()));
+        };
+
+        let body = block_body(&code[captures.get(0).unwrap().end()..]);
+        let fields = split_top_level(body)
+            .into_iter()
+            .filter_map(|field| parse_field(&field))
+            .map(|(name, type_name)| {
+                let mapping_args = mapping_args(&type_name);
+                InkStorageField {
+                    name,
+                    is_mapping: mapping_args.is_some(),
+                    key_type: mapping_args.as_ref().map(|(key, _)| key.clone()),
+                    value_type: mapping_args.map(|(_, value)| value),
+                    type_name,
+                }
+            })
+            .collect();
+
+        Ok((captures[1].to_string(), fields))
+    }
+
+    /// Constructors and messages, each in declaration order.
+    fn parse_functions(&self, code: &str) -> Result<(Vec<InkFunction>, Vec<InkFunction>), String> {
+        let function_re = Regex::new(r"#\[ink\((constructor|message)([^\]]*)\)\](?:\s*#\[[^\]]*\])*\s*pub\s+fn\s+(\w+)\s*\(")
+            .map_err(|e| format!("Regex error: {}", e))?;
+
+        let mut constructors = Vec::new();
+        let mut messages = Vec::new();
+        for captures in function_re.captures_iter(code) {
+            let rest = &code[captures.get(0).unwrap().end()..];
+            let params = paren_body(rest);
+            let after_params = &rest[(params.len() + 1).min(rest.len())..];
+            let Some(open) = after_params.find('{') else {
+                continue;
+            };
+            let return_type = after_params[..open]
+                .trim()
+                .strip_prefix("->")
+                .map(|r| r.trim().to_string())
+                .filter(|r| !r.is_empty());
+
+            let mut args = split_top_level(params);
+            let receiver = args
+                .first()
+                .map(|a| a.replace(' ', ""))
+                .filter(|a| matches!(a.as_str(), "&self" | "&mutself" | "self" | "mutself"));
+            if receiver.is_some() {
+                args.remove(0);
+            }
+
+            let function = InkFunction {
+                name: captures[3].to_string(),
+                parameters: args
+                    .iter()
+                    .filter_map(|arg| parse_field(arg))
+                    .map(|(name, type_name)| InkParameter { name, type_name, is_topic: false })
+                    .collect(),
+                return_type,
+                is_mutable: receiver.is_some_and(|r| r.contains("mut")),
+                is_payable: captures[2].split(',').any(|arg| arg.trim() == "payable"),
+                body: block_body(&after_params[open + 1..]).to_string(),
+            };
+
+            if &captures[1] == "constructor" {
+                constructors.push(function);
+            } else {
+                messages.push(function);
+            }
+        }
+
+        Ok((constructors, messages))
+    }
+
+    fn parse_events(&self, code: &str) -> Result<Vec<InkEvent>, String> {
+        let event_re = Regex::new(r"#\[ink\(event\)\](?:\s*#\[[^\]]*\])*\s*pub\s+struct\s+(\w+)\s*\{")
+            .map_err(|e| format!("Regex error: {}", e))?;
+
+        let mut events = Vec::new();
+        for captures in event_re.captures_iter(code) {
+            let body = block_body(&code[captures.get(0).unwrap().end()..]);
+            let fields = split_top_level(body)
+                .into_iter()
+                .filter_map(|field| {
+                    let is_topic = field.contains("#[ink(topic)]");
+                    parse_field(&field).map(|(name, type_name)| InkParameter { name, type_name, is_topic })
+                })
+                .collect();
+
+            events.push(InkEvent {
+                name: captures[1].to_string(),
+                fields,
+            });
+        }
+
+        Ok(events)
+    }
+}
+
+/// `name` and type from `[#[attr]] [pub] name: Type`.
+fn parse_field(field: &str) -> Option<(String, String)> {
+    let mut field = field.trim();
+    // Attributes such as `#[ink(topic)]` come before the name
+    while field.starts_with("#[") {
+        field = field[field.find(']')? + 1..].trim_start();
+    }
+    let field = field.strip_prefix("pub ").unwrap_or(field);
+    let (name, type_name) = field.split_once(':')?;
+    let name = name.trim();
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
+    Some((name.to_string(), type_name.split_whitespace().collect::<Vec<_>>().join(" ")))
+}
+
+/// Key and value types of a `Mapping<K, V>`, with or without its path.
+fn mapping_args(type_name: &str) -> Option<(String, String)> {
+    let start = type_name.find("Mapping<")?;
+    let args = type_name[start + "Mapping<".len()..].strip_suffix('>')?;
+    match split_top_level(args).as_slice() {
+        [key, value, ..] => Some((key.clone(), value.clone())),
+        _ => None,
+    }
+}
+
+/// Text between an opening parenthesis (already consumed) and its match.
+fn paren_body(rest: &str) -> &str {
+    let mut depth = 1;
+    for (i, c) in rest.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return &rest[..i];
+                }
+            }
+            _ => {}
+        }
+    }
+    rest
+}
+
+/// Split on commas outside `()`, `[]` and `<>`, dropping empty items.
+fn split_top_level(list: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let (mut depth, mut start) = (0i32, 0);
+    for (i, c) in list.char_indices() {
+        match c {
+            '(' | '[' | '<' => depth += 1,
+            ')' | ']' | '>' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(list[start..i].trim().to_string());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(list[start..].trim().to_string());
+    items.retain(|item| !item.is_empty());
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sample_data::{ERC20_EXAMPLE, FLIPPER_EXAMPLE};
+
+    #[test]
+    fn should_parse_flipper_sample() {
+        let contract = InkParser::new().parse_contract(FLIPPER_EXAMPLE).unwrap();
+
+        assert_eq!(contract.name, "flipper");
+        assert_eq!(contract.storage_name, "Flipper");
+        assert_eq!(contract.storage.len(), 1);
+        assert_eq!(contract.storage[0].name, "value");
+        assert_eq!(contract.storage[0].type_name, "bool");
+        assert!(!contract.storage[0].is_mapping);
+
+        let constructors: Vec<&str> = contract.constructors.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(constructors, vec!["new", "default"]);
+        assert_eq!(contract.constructors[0].parameters[0].name, "init_value");
+        assert_eq!(contract.constructors[0].return_type.as_deref(), Some("Self"));
+
+        let flip = &contract.messages[0];
+        assert_eq!(flip.name, "flip");
+        assert!(flip.is_mutable);
+        assert!(flip.parameters.is_empty());
+        assert_eq!(flip.return_type, None);
+        assert_eq!(flip.body.trim(), "self.value = !self.value;");
+
+        let get = &contract.messages[1];
+        assert_eq!(get.name, "get");
+        assert!(!get.is_mutable);
+        assert_eq!(get.return_type.as_deref(), Some("bool"));
+        assert!(contract.events.is_empty());
+    }
+
+    #[test]
+    fn should_parse_erc20_sample() {
+        let contract = InkParser::new().parse_contract(ERC20_EXAMPLE).unwrap();
+
+        assert_eq!(contract.name, "erc20");
+        assert_eq!(contract.storage_name, "Erc20");
+        let storage: Vec<(&str, bool)> = contract.storage.iter().map(|f| (f.name.as_str(), f.is_mapping)).collect();
+        assert_eq!(storage, vec![("total_supply", false), ("balances", true), ("allowances", true)]);
+        assert_eq!(contract.storage[2].key_type.as_deref(), Some("(AccountId, AccountId)"));
+        assert_eq!(contract.storage[2].value_type.as_deref(), Some("Balance"));
+
+        assert_eq!(contract.constructors.len(), 1);
+        let messages: Vec<(&str, bool)> = contract.messages.iter().map(|m| (m.name.as_str(), m.is_mutable)).collect();
+        assert_eq!(
+            messages,
+            vec![("total_supply", false), ("balance_of", false), ("transfer", true), ("approve", true)]
+        );
+
+        let transfer = &contract.messages[2];
+        let params: Vec<(&str, &str)> = transfer.parameters.iter().map(|p| (p.name.as_str(), p.type_name.as_str())).collect();
+        assert_eq!(params, vec![("to", "AccountId"), ("value", "Balance")]);
+        assert_eq!(transfer.return_type.as_deref(), Some("Result<()>"));
+    }
+
+    #[test]
+    fn should_parse_events_with_topics() {
+        let contract = InkParser::new()
+            .parse_contract(
+                r#"
+#[ink::contract]
+mod vault {
+    #[ink(storage)]
+    pub struct Vault {
+        owner: AccountId,
+    }
+
+    /// Emitted on every deposit.
+    #[ink(event)]
+    pub struct Deposited {
+        #[ink(topic)]
+        from: Option<AccountId>,
+        amount: Balance,
+    }
+
+    impl Vault {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self { owner: Self::env().caller() }
+        }
+
+        #[ink(message, payable)]
+        pub fn deposit(&mut self) {}
+    }
+}
+"#,
+            )
+            .unwrap();
+
+        assert_eq!(contract.events.len(), 1);
+        let event = &contract.events[0];
+        assert_eq!(event.name, "Deposited");
+        assert_eq!(
+            event.fields,
+            vec![
+                InkParameter { name: "from".to_string(), type_name: "Option<AccountId>".to_string(), is_topic: true },
+                InkParameter { name: "amount".to_string(), type_name: "Balance".to_string(), is_topic: false },
+            ]
+        );
+        assert!(contract.messages[0].is_payable);
+        assert!(!contract.constructors[0].is_payable);
+    }
+
+    #[test]
+    fn should_reject_code_without_contract_module() {
+        assert_eq!(InkParser::new().parse_contract("pub struct NotAContract {}"), Err(ParseError::NoContract));
+    }
+}
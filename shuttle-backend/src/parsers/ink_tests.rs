@@ -0,0 +1,148 @@
+use super::library::to_snake_case;
+use super::solidity_parser::{SolidityContract, SolidityFunction};
+use super::type_mapping::map_solidity_type;
+
+/// `#[cfg(test)]` scaffold for the ink! port of `contract`. It builds the
+/// contract through its `new` constructor and calls every read-only message,
+/// asserting that each returns its type's default. Constructor arguments are
+/// typed placeholders (`0`, `false`, a zero `AccountId`) for the Solidity
+/// constructor's parameters; message arguments are `Default::default()`.
+/// Either way the scaffold compiles before the values are filled in by hand.
+pub fn ink_test_module(contract: &SolidityContract) -> String {
+    let constructor_args = contract
+        .functions
+        .iter()
+        .find(|f| f.name == "constructor")
+        .map(|constructor| {
+            constructor
+                .parameters
+                .iter()
+                .map(|p| placeholder_value(&p.type_name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+        .unwrap_or_default();
+    let instantiate = format!("        let contract = {}::new({});\n", contract.name, constructor_args);
+
+    let read_only: Vec<&SolidityFunction> = contract
+        .functions
+        .iter()
+        .filter(|f| f.name != "constructor")
+        .filter(|f| matches!(f.visibility.as_str(), "public" | "external"))
+        .filter(|f| matches!(f.mutability.as_deref(), Some("view") | Some("pure")))
+        .filter(|f| f.return_type.is_some())
+        .collect();
+
+    let mut tests = vec![format!("    #[ink::test]\n    fn new_works() {{\n{}        let _ = contract;\n    }}\n", instantiate)];
+    for function in read_only {
+        let message = to_snake_case(&function.name);
+        tests.push(format!(
+            "    #[ink::test]\n    fn {message}_returns_default() {{\n{instantiate}        assert_eq!(contract.{message}({args}), Default::default());\n    }}\n",
+            message = message,
+            instantiate = instantiate,
+            args = default_args(function)
+        ));
+    }
+
+    format!("#[cfg(test)]\nmod tests {{\n    use super::*;\n\n{}}}\n", tests.join("\n"))
+}
+
+fn default_args(function: &SolidityFunction) -> String {
+    vec!["Default::default()"; function.parameters.len()].join(", ")
+}
+
+/// A literal of the ink! type `solidity_type` maps to, falling back to
+/// `Default::default()` for types without an obvious one.
+fn placeholder_value(solidity_type: &str) -> String {
+    let value = match map_solidity_type(solidity_type).as_deref() {
+        Ok("bool") => "false",
+        Ok("AccountId") => "AccountId::from([0x0; 32])",
+        Ok("String") => "String::new()",
+        Ok(ty) if ty.len() > 1 && ty[1..].chars().all(|c| c.is_ascii_digit()) && matches!(&ty[..1], "u" | "i") => "0",
+        _ => "Default::default()",
+    };
+    value.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::solidity_parser::SolidityParser;
+
+    #[test]
+    fn should_call_constructor_and_read_only_messages() {
+        let contract = SolidityParser::new()
+            .parse_contract(
+                r#"
+contract Counter {
+    uint256 private count;
+    address private owner;
+
+    constructor(uint256 start) {
+        count = start;
+        owner = msg.sender;
+    }
+
+    function increment() public {
+        count += 1;
+    }
+
+    function getCount() public view returns (uint256) {
+        return count;
+    }
+
+    function balanceOf(address account) external view returns (uint256) {
+        return 0;
+    }
+}
+"#,
+            )
+            .unwrap();
+
+        let module = ink_test_module(&contract);
+        assert!(module.starts_with("#[cfg(test)]\nmod tests {\n    use super::*;"));
+        assert!(module.contains("let contract = Counter::new(0);"));
+        assert!(module.contains("    #[ink::test]\n    fn get_count_returns_default() {"));
+        assert!(module.contains("assert_eq!(contract.get_count(), Default::default());"));
+        assert!(module.contains("assert_eq!(contract.balance_of(Default::default()), Default::default());"));
+        assert!(!module.contains("increment"));
+    }
+
+    #[test]
+    fn should_pass_erc20_constructor_arguments() {
+        let contract = SolidityParser::new()
+            .parse_contract(
+                r#"
+contract SimpleERC20 {
+    uint256 public totalSupply;
+    mapping(address => uint256) public balances;
+
+    constructor(uint256 _totalSupply) {
+        totalSupply = _totalSupply;
+        balances[msg.sender] = _totalSupply;
+    }
+
+    function balanceOf(address owner) public view returns (uint256) {
+        return balances[owner];
+    }
+}
+"#,
+            )
+            .unwrap();
+
+        let skeleton = crate::parsers::ink_generator::generate_ink_skeleton(&contract).unwrap();
+        assert!(skeleton.contains("pub fn new(total_supply: u128) -> Self {"));
+        let module = ink_test_module(&contract);
+        assert!(module.contains("let contract = SimpleERC20::new(0);"));
+        assert!(!module.contains("SimpleERC20::new()"));
+
+        let placeholders: Vec<String> = ["bool", "address payable", "string memory", "int64", "bytes32"]
+            .iter()
+            .map(|ty| placeholder_value(ty))
+            .collect();
+        assert_eq!(
+            placeholders,
+            vec!["false", "AccountId::from([0x0; 32])", "String::new()", "0", "Default::default()"]
+        );
+    }
+}
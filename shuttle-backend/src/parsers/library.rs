@@ -0,0 +1,123 @@
+use super::solidity_parser::{SolidityFunction, SolidityLibrary};
+use super::type_mapping::map_solidity_type;
+
+/// Render a library as a module of ink! free functions: no `self` and no
+/// `#[ink(message)]`, callable directly from contract messages.
+#[allow(dead_code)]
+pub fn library_to_ink(library: &SolidityLibrary) -> Result<String, String> {
+    let functions = library
+        .functions
+        .iter()
+        .map(free_function)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(format!(
+        "/// Helpers ported from Solidity `library {}`\npub mod {} {{\n{}}}\n",
+        library.name,
+        to_snake_case(&library.name),
+        functions.join("\n")
+    ))
+}
+
+fn free_function(function: &SolidityFunction) -> Result<String, String> {
+    let params = function
+        .parameters
+        .iter()
+        .map(|p| Ok(format!("{}: {}", to_snake_case(p.name.trim_start_matches('_')), map_solidity_type(&p.type_name)?)))
+        .collect::<Result<Vec<_>, String>>()?;
+    let return_type = match &function.return_type {
+        Some(ty) => format!(" -> {}", map_solidity_type(ty)?),
+        None => String::new(),
+    };
+
+    // A lone `return expr;` carries over as a tail expression; anything else
+    // is left as a comment to port by hand
+    let body = function.body.trim();
+    let body = match body.strip_prefix("return ").and_then(|b| b.strip_suffix(';')) {
+        Some(expr) if !expr.contains(';') => format!("        {}\n", expr.trim()),
+        _ => {
+            let original: String = body.lines().map(|line| format!("        // {}\n", line.trim())).collect();
+            format!("{}        todo!()\n", original)
+        }
+    };
+
+    Ok(format!(
+        "    pub fn {}({}){} {{\n{}    }}\n",
+        to_snake_case(&function.name),
+        params.join(", "),
+        return_type,
+        body
+    ))
+}
+
+/// Migration note for `using L for T;` directives, which ink! has no equivalent for.
+#[allow(dead_code)]
+pub fn using_migration_note(library: &SolidityLibrary) -> String {
+    format!(
+        "## Migration Notes: library {name}\n\n\
+- Library functions become free functions in `mod {module}`; they take no `self` and need no `#[ink(message)]`\n\
+- Replace `using {name} for T;` with direct calls: `x.add(y)` becomes `{module}::add(x, y)`\n\
+- Internal library calls are inlined by Rust, so there is no separate deployment or linking step\n",
+        name = library.name,
+        module = to_snake_case(&library.name)
+    )
+}
+
+pub(super) fn to_snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    let chars: Vec<char> = name.chars().collect();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() {
+            let prev_lower = i > 0 && (chars[i - 1].is_ascii_lowercase() || chars[i - 1].is_ascii_digit());
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_ascii_lowercase());
+            if i > 0 && (prev_lower || (next_lower && chars[i - 1].is_ascii_uppercase())) {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::solidity_parser::SolidityParser;
+
+    #[test]
+    fn should_generate_free_functions_for_library() {
+        let source = r#"
+library Math {
+    function add(uint256 a, uint256 b) internal pure returns (uint256) {
+        return a + b;
+    }
+
+    function mulDiv(uint256 x, uint256 y, uint256 d) internal pure returns (uint256) {
+        require(d != 0);
+        return x * y / d;
+    }
+}
+
+contract Calculator {
+    using Math for uint256;
+}
+"#;
+
+        let libraries = SolidityParser::new().parse_libraries(source).unwrap();
+        assert_eq!(libraries.len(), 1);
+        assert_eq!(libraries[0].name, "Math");
+
+        let ink = library_to_ink(&libraries[0]).unwrap();
+        assert!(ink.contains("pub mod math {"));
+        assert!(ink.contains("    pub fn add(a: u128, b: u128) -> u128 {\n        a + b\n    }"));
+        assert!(ink.contains("pub fn mul_div(x: u128, y: u128, d: u128) -> u128 {"));
+        assert!(ink.contains("todo!()"));
+        assert!(!ink.contains("self"));
+        assert!(!ink.contains("#[ink(message)]"));
+
+        let note = using_migration_note(&libraries[0]);
+        assert!(note.contains("`math::add(x, y)`"));
+    }
+}
@@ -0,0 +1,140 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::solidity_parser::{block_body, strip_comments, SolidityContract};
+
+/// Keywords that each add a decision point to a function
+const BRANCH_KEYWORDS: &[&str] = &["if", "for", "while", "require"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct FunctionComplexity {
+    pub name: String,
+    /// 1 plus the number of `if`/`for`/`while`/`require` in the body
+    pub complexity: usize,
+}
+
+/// Size and complexity figures for a parsed contract.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct ContractMetrics {
+    pub contract_name: String,
+    pub functions: usize,
+    pub state_variables: usize,
+    pub events: usize,
+    pub custom_errors: usize,
+    /// Non-blank lines once comments are removed
+    pub lines_of_code: usize,
+    /// One entry per parsed function, in declaration order
+    pub function_complexity: Vec<FunctionComplexity>,
+}
+
+/// Metrics for `contract`, parsed from `source`. Complexity is counted over
+/// each function's full body in `source`, since the parsed body stops at
+/// the first closing brace.
+pub fn contract_metrics(contract: &SolidityContract, source: &str) -> Result<ContractMetrics, String> {
+    let code = strip_comments(source);
+    let branch_re = Regex::new(&format!(r"\b({})\b", BRANCH_KEYWORDS.join("|"))).map_err(|e| format!("Regex error: {}", e))?;
+
+    let mut function_complexity = Vec::with_capacity(contract.functions.len());
+    for function in &contract.functions {
+        let header = if function.name == "constructor" {
+            r"\bconstructor\s*\(".to_string()
+        } else {
+            format!(r"\bfunction\s+{}\s*\(", regex::escape(&function.name))
+        };
+        let header_re = Regex::new(&header).map_err(|e| format!("Regex error: {}", e))?;
+        let body = header_re
+            .find(&code)
+            .and_then(|m| code[m.end()..].find('{').map(|open| m.end() + open + 1))
+            .map(|start| block_body(&code[start..]))
+            .unwrap_or(&function.body);
+
+        function_complexity.push(FunctionComplexity {
+            name: function.name.clone(),
+            complexity: 1 + branch_re.find_iter(body).count(),
+        });
+    }
+
+    Ok(ContractMetrics {
+        contract_name: contract.name.clone(),
+        functions: contract.functions.len(),
+        state_variables: contract.state_variables.len(),
+        events: contract.events.len(),
+        custom_errors: contract.custom_errors.len(),
+        lines_of_code: code.lines().filter(|line| !line.trim().is_empty()).count(),
+        function_complexity,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::solidity_parser::SolidityParser;
+
+    const ERC20: &str = r#"
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+contract SimpleERC20 {
+    uint256 public totalSupply;
+    mapping(address => uint256) public balanceOf;
+    mapping(address => mapping(address => uint256)) public allowance;
+
+    event Transfer(address indexed from, address indexed to, uint256 value);
+    event Approval(address indexed owner, address indexed spender, uint256 value);
+    error InsufficientBalance(uint256 available, uint256 required);
+
+    constructor(uint256 initialSupply) {
+        totalSupply = initialSupply;
+        balanceOf[msg.sender] = initialSupply;
+    }
+
+    function transfer(address to, uint256 value) public returns (bool) {
+        require(balanceOf[msg.sender] >= value, "balance");
+        balanceOf[msg.sender] -= value;
+        balanceOf[to] += value;
+        emit Transfer(msg.sender, to, value);
+        return true;
+    }
+
+    function approve(address spender, uint256 value) public returns (bool) {
+        allowance[msg.sender][spender] = value;
+        emit Approval(msg.sender, spender, value);
+        return true;
+    }
+
+    function transferFrom(address from, address to, uint256 value) public returns (bool) {
+        if (from != msg.sender) {
+            require(allowance[from][msg.sender] >= value, "allowance");
+            allowance[from][msg.sender] -= value;
+        }
+        require(balanceOf[from] >= value, "balance");
+        balanceOf[from] -= value;
+        balanceOf[to] += value;
+        emit Transfer(from, to, value);
+        return true;
+    }
+}
+"#;
+
+    #[test]
+    fn should_count_members_of_erc20() {
+        let contract = SolidityParser::new().parse_contract(ERC20).unwrap();
+        let metrics = contract_metrics(&contract, ERC20).unwrap();
+
+        assert_eq!(metrics.contract_name, "SimpleERC20");
+        assert_eq!(metrics.functions, 4);
+        assert_eq!(metrics.state_variables, 3);
+        assert_eq!(metrics.events, 2);
+        assert_eq!(metrics.custom_errors, 1);
+        // The licence comment line is not code
+        assert_eq!(metrics.lines_of_code, ERC20.lines().filter(|l| !l.trim().is_empty()).count() - 1);
+
+        let complexity: Vec<(&str, usize)> =
+            metrics.function_complexity.iter().map(|f| (f.name.as_str(), f.complexity)).collect();
+        assert_eq!(
+            complexity,
+            vec![("constructor", 1), ("transfer", 2), ("approve", 1), ("transferFrom", 4)]
+        );
+    }
+}
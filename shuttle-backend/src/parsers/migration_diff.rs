@@ -0,0 +1,230 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::ink_parser::{InkContract, InkFunction};
+use super::library::to_snake_case;
+use super::solidity_parser::{SolidityContract, SolidityFunction};
+
+/// Lowest name similarity at which two functions are paired
+const MATCH_THRESHOLD: f64 = 0.5;
+
+/// A construct in the Solidity body, its replacement in the ink! body, and
+/// the label reported when both appear.
+const BODY_CHANGES: &[(&str, &str, &str)] = &[
+    ("require(", "ensure!", "require→ensure"),
+    ("require(", "Err(", "require→Err"),
+    ("msg.sender", "self.env().caller()", "msg.sender→self.env().caller()"),
+    ("msg.value", "transferred_value()", "msg.value→self.env().transferred_value()"),
+    ("block.timestamp", "block_timestamp()", "block.timestamp→self.env().block_timestamp()"),
+    ("emit ", "emit_event(", "emit→self.env().emit_event()"),
+];
+
+/// A Solidity function and the ink! constructor or message it became.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct FunctionMapping {
+    pub solidity_function: String,
+    pub ink_function: String,
+    /// Name similarity, 1.0 when the names agree once snake-cased
+    pub similarity: f64,
+    /// What changed between the two, e.g. `require→ensure` or `mapping→Mapping`
+    pub differences: Vec<String>,
+}
+
+/// Side-by-side pairing of a Solidity contract's functions with its ink! port.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct MigrationDiff {
+    pub functions: Vec<FunctionMapping>,
+    /// Solidity functions with no ink! counterpart, including every
+    /// `internal` or `private` helper
+    pub unmatched_solidity: Vec<String>,
+    /// ink! constructors and messages with no Solidity counterpart
+    pub unmatched_ink: Vec<String>,
+}
+
+/// Pair each public Solidity function with the most similarly named ink!
+/// function, the constructor with an ink! constructor, and list what's left.
+pub fn migration_diff(solidity: &SolidityContract, ink: &InkContract) -> MigrationDiff {
+    let candidates: Vec<(&InkFunction, bool)> = ink
+        .constructors
+        .iter()
+        .map(|f| (f, true))
+        .chain(ink.messages.iter().map(|f| (f, false)))
+        .collect();
+    let mut used = vec![false; candidates.len()];
+
+    let mut functions = Vec::new();
+    let mut unmatched_solidity = Vec::new();
+    for function in &solidity.functions {
+        let is_constructor = function.name == "constructor";
+        if !is_constructor && !matches!(function.visibility.as_str(), "public" | "external") {
+            unmatched_solidity.push(function.name.clone());
+            continue;
+        }
+
+        let best = candidates
+            .iter()
+            .enumerate()
+            .filter(|(i, (_, ink_constructor))| !used[*i] && *ink_constructor == is_constructor)
+            .map(|(i, (ink_function, _))| (i, *ink_function, similarity(function, ink_function, is_constructor)))
+            .filter(|(_, _, score)| *score >= MATCH_THRESHOLD)
+            .max_by(|a, b| a.2.total_cmp(&b.2));
+        match best {
+            Some((i, ink_function, score)) => {
+                used[i] = true;
+                functions.push(FunctionMapping {
+                    solidity_function: function.name.clone(),
+                    ink_function: ink_function.name.clone(),
+                    similarity: (score * 100.0).round() / 100.0,
+                    differences: differences(solidity, ink, function, ink_function),
+                });
+            }
+            None => unmatched_solidity.push(function.name.clone()),
+        }
+    }
+
+    let unmatched_ink = candidates
+        .iter()
+        .zip(&used)
+        .filter(|(_, used)| !**used)
+        .map(|((f, _), _)| f.name.clone())
+        .collect();
+
+    MigrationDiff {
+        functions,
+        unmatched_solidity,
+        unmatched_ink,
+    }
+}
+
+/// Share of name words in common, plus a little when the argument counts
+/// agree. Constructors only compete with each other, so any pair counts.
+fn similarity(solidity: &SolidityFunction, ink: &InkFunction, is_constructor: bool) -> f64 {
+    let same_arity = solidity.parameters.len() == ink.parameters.len();
+    if is_constructor {
+        return if same_arity { 1.0 } else { MATCH_THRESHOLD };
+    }
+
+    let solidity_name = to_snake_case(solidity.name.trim_start_matches('_'));
+    if solidity_name == ink.name {
+        return 1.0;
+    }
+    let words = |name: &str| name.split('_').filter(|w| !w.is_empty()).map(str::to_string).collect::<Vec<_>>();
+    let (a, b) = (words(&solidity_name), words(&ink.name));
+    let common = a.iter().filter(|w| b.contains(w)).count();
+    let total = a.len() + b.len() - common;
+    if total == 0 {
+        return 0.0;
+    }
+    let score = common as f64 / total as f64 + if same_arity { 0.25 } else { 0.0 };
+    score.min(0.99)
+}
+
+fn differences(solidity_contract: &SolidityContract, ink_contract: &InkContract, solidity: &SolidityFunction, ink: &InkFunction) -> Vec<String> {
+    let mut differences: Vec<String> = BODY_CHANGES
+        .iter()
+        .filter(|(from, to, _)| solidity.body.contains(from) && ink.body.contains(to))
+        .map(|(_, _, label)| label.to_string())
+        .collect();
+
+    // Indexing a mapping whose ink! storage counterpart is a `Mapping`,
+    // read with `get` and written with `insert` instead
+    let touches_mapping = solidity_contract
+        .state_variables
+        .iter()
+        .filter(|v| v.is_mapping && solidity.body.contains(&format!("{}[", v.name)))
+        .any(|v| {
+            let field = to_snake_case(v.name.trim_start_matches('_'));
+            ink_contract.storage.iter().any(|f| f.is_mapping && f.name == field)
+        });
+    if touches_mapping {
+        differences.push("mapping→Mapping".to_string());
+    }
+
+    let returns_result = ink.return_type.as_deref().is_some_and(|t| t.starts_with("Result"));
+    if returns_result && !solidity.return_type.as_deref().is_some_and(|t| t.contains("Result")) {
+        differences.push("revert→Result".to_string());
+    }
+    if solidity.mutability.as_deref() == Some("payable") && !ink.is_payable {
+        differences.push("payable not marked #[ink(payable)]".to_string());
+    }
+
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::ink_parser::InkParser;
+    use crate::parsers::solidity_parser::SolidityParser;
+    use crate::sample_data::ERC20_EXAMPLE;
+
+    const SOLIDITY_ERC20: &str = r#"
+contract SimpleERC20 {
+    uint256 public totalSupply;
+    mapping(address => uint256) public balances;
+    mapping(address => mapping(address => uint256)) public allowances;
+
+    event Transfer(address indexed from, address indexed to, uint256 value);
+    event Approval(address indexed owner, address indexed spender, uint256 value);
+
+    constructor(uint256 _totalSupply) {
+        totalSupply = _totalSupply;
+        balances[msg.sender] = _totalSupply;
+    }
+
+    function balanceOf(address owner) public view returns (uint256) {
+        return balances[owner];
+    }
+
+    function transfer(address to, uint256 value) public returns (bool) {
+        require(balances[msg.sender] >= value, "Insufficient balance");
+        _move(msg.sender, to, value);
+        return true;
+    }
+
+    function approve(address spender, uint256 value) public returns (bool) {
+        allowances[msg.sender][spender] = value;
+        emit Approval(msg.sender, spender, value);
+        return true;
+    }
+
+    function allowance(address owner, address spender) public view returns (uint256) {
+        return allowances[owner][spender];
+    }
+
+    function _move(address from, address to, uint256 value) internal {
+        balances[from] -= value;
+        balances[to] += value;
+        emit Transfer(from, to, value);
+    }
+}
+"#;
+
+    #[test]
+    fn should_pair_erc20_functions_and_flag_differences() {
+        let solidity = SolidityParser::new().parse_contract(SOLIDITY_ERC20).unwrap();
+        let ink = InkParser::new().parse_contract(ERC20_EXAMPLE).unwrap();
+        let diff = migration_diff(&solidity, &ink);
+
+        let pairs: Vec<(&str, &str)> = diff
+            .functions
+            .iter()
+            .map(|m| (m.solidity_function.as_str(), m.ink_function.as_str()))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![("constructor", "new"), ("balanceOf", "balance_of"), ("transfer", "transfer"), ("approve", "approve")]
+        );
+        assert_eq!(diff.unmatched_solidity, vec!["allowance", "_move"]);
+        assert_eq!(diff.unmatched_ink, vec!["total_supply"]);
+
+        let approve = diff.functions.iter().find(|m| m.ink_function == "approve").unwrap();
+        assert_eq!(
+            approve.differences,
+            vec!["msg.sender→self.env().caller()", "emit→self.env().emit_event()", "mapping→Mapping", "revert→Result"]
+        );
+        let transfer = diff.functions.iter().find(|m| m.ink_function == "transfer").unwrap();
+        assert!(transfer.differences.contains(&"msg.sender→self.env().caller()".to_string()));
+        assert!(transfer.differences.contains(&"mapping→Mapping".to_string()));
+    }
+}
@@ -1,2 +1,15 @@
 pub mod solidity_parser;
-pub mod ink_parser;
\ No newline at end of file
+pub mod ink_parser;
+pub mod import_resolver;
+pub mod access_control;
+pub mod type_mapping;
+pub mod library;
+pub mod difficulty;
+pub mod parse_cache;
+pub mod custody;
+pub mod ink_tests;
+pub mod inheritance;
+pub mod reentrancy;
+pub mod metrics;
+pub mod ink_generator;
+pub mod migration_diff;
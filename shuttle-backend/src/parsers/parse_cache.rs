@@ -0,0 +1,171 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use super::solidity_parser::{ParseError, SolidityContract, SolidityParser};
+
+const DEFAULT_CAPACITY: usize = 128;
+
+struct CacheEntry {
+    /// Kept to rule out hash collisions
+    source: String,
+    contract: SolidityContract,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<u64, CacheEntry>,
+    /// Least recently used first
+    order: VecDeque<u64>,
+}
+
+/// Bounded LRU of parsed contracts keyed by source hash, so repeated
+/// requests with the same file skip the regex passes.
+pub struct ParseCache {
+    capacity: usize,
+    state: Mutex<CacheState>,
+}
+
+impl ParseCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    /// Process-wide cache sized by `PARSE_CACHE_SIZE` (default 128).
+    pub fn shared() -> Arc<ParseCache> {
+        static CACHE: OnceLock<Arc<ParseCache>> = OnceLock::new();
+        CACHE
+            .get_or_init(|| {
+                let capacity = std::env::var("PARSE_CACHE_SIZE")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(DEFAULT_CAPACITY);
+                Arc::new(ParseCache::new(capacity))
+            })
+            .clone()
+    }
+
+    pub fn parse_contract(&self, source: &str) -> Result<SolidityContract, ParseError> {
+        self.get_or_parse(source, |source| SolidityParser::new().parse_contract(source))
+    }
+
+    /// Return the cached contract for `source`, or run `parse` and cache a
+    /// successful result. Parse errors are not cached.
+    pub fn get_or_parse<F>(&self, source: &str, parse: F) -> Result<SolidityContract, ParseError>
+    where
+        F: FnOnce(&str) -> Result<SolidityContract, ParseError>,
+    {
+        let key = source_hash(source);
+        if let Some(contract) = self.lookup(key, source) {
+            return Ok(contract);
+        }
+
+        // Parse outside the lock; a concurrent miss just parses twice
+        let contract = parse(source)?;
+        self.insert(key, source, contract.clone());
+        Ok(contract)
+    }
+
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lookup(&self, key: u64, source: &str) -> Option<SolidityContract> {
+        let mut state = self.state.lock().unwrap();
+        let contract = state
+            .entries
+            .get(&key)
+            .filter(|entry| entry.source == source)
+            .map(|entry| entry.contract.clone())?;
+        state.order.retain(|k| *k != key);
+        state.order.push_back(key);
+        Some(contract)
+    }
+
+    fn insert(&self, key: u64, source: &str, contract: SolidityContract) {
+        let mut state = self.state.lock().unwrap();
+        state.order.retain(|k| *k != key);
+        while state.order.len() >= self.capacity {
+            if let Some(oldest) = state.order.pop_front() {
+                state.entries.remove(&oldest);
+            }
+        }
+        state.order.push_back(key);
+        state.entries.insert(
+            key,
+            CacheEntry {
+                source: source.to_string(),
+                contract,
+            },
+        );
+    }
+}
+
+fn source_hash(source: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn counted_parse<'a>(parses: &'a Cell<usize>) -> impl FnOnce(&str) -> Result<SolidityContract, ParseError> + 'a {
+        move |source| {
+            parses.set(parses.get() + 1);
+            SolidityParser::new().parse_contract(source)
+        }
+    }
+
+    #[test]
+    fn should_hit_cache_for_identical_content() {
+        let cache = ParseCache::new(8);
+        let parses = Cell::new(0);
+        let source = "contract Flipper { bool value; function flip() public { value = !value; } }";
+
+        let first = cache.get_or_parse(source, counted_parse(&parses)).unwrap();
+        let second = cache.get_or_parse(source, counted_parse(&parses)).unwrap();
+
+        assert_eq!(parses.get(), 1);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn should_evict_least_recently_used() {
+        let cache = ParseCache::new(2);
+        let parses = Cell::new(0);
+        let (a, b, c) = ("contract A {}", "contract B {}", "contract C {}");
+
+        cache.get_or_parse(a, counted_parse(&parses)).unwrap();
+        cache.get_or_parse(b, counted_parse(&parses)).unwrap();
+        // Touch A so B becomes the oldest
+        cache.get_or_parse(a, counted_parse(&parses)).unwrap();
+        cache.get_or_parse(c, counted_parse(&parses)).unwrap();
+        assert_eq!(cache.len(), 2);
+        assert_eq!(parses.get(), 3);
+
+        cache.get_or_parse(a, counted_parse(&parses)).unwrap();
+        assert_eq!(parses.get(), 3);
+        cache.get_or_parse(b, counted_parse(&parses)).unwrap();
+        assert_eq!(parses.get(), 4);
+    }
+
+    #[test]
+    fn should_not_cache_parse_errors() {
+        let cache = ParseCache::new(2);
+        assert!(cache.parse_contract("// only a comment").is_err());
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,200 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::solidity_parser::{block_body, SolidityContract, SolidityStateVariable};
+
+/// Storage names conventionally used for a hand-rolled reentrancy lock,
+/// compared with leading underscores removed and lowercased
+const LOCK_NAMES: &[&str] = &["locked", "lock", "status", "entered", "notentered", "reentrancylock", "reentrancy_lock"];
+
+/// Calls that hand control to another contract
+const EXTERNAL_CALLS: &[&str] = &[".call{", ".call(", ".delegatecall(", ".transfer(", ".send("];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReentrancyPattern {
+    /// A function guarded by OpenZeppelin's `nonReentrant` modifier
+    NonReentrantModifier,
+    /// A storage flag such as `bool locked` or `uint256 _status` used as a lock
+    ManualLock,
+    /// A function that writes storage after an external call, breaking
+    /// checks-effects-interactions
+    StateWriteAfterCall,
+}
+
+/// One reentrancy-relevant pattern found in a contract.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct ReentrancyFinding {
+    pub pattern: ReentrancyPattern,
+    /// The function or, for a manual lock, the state variable involved
+    pub subject: String,
+}
+
+/// Find reentrancy guards and unguarded orderings in comment-free `code`.
+pub(crate) fn detect_reentrancy(code: &str, state_variables: &[SolidityStateVariable]) -> Result<Vec<ReentrancyFinding>, String> {
+    let finding = |pattern, subject: &str| ReentrancyFinding { pattern, subject: subject.to_string() };
+    let mut findings = Vec::new();
+
+    let guarded_re = Regex::new(r"function\s+(\w+)\s*\([^)]*\)[^{;]*\bnonReentrant\b").map_err(|e| format!("Regex error: {}", e))?;
+    for captures in guarded_re.captures_iter(code) {
+        findings.push(finding(ReentrancyPattern::NonReentrantModifier, &captures[1]));
+    }
+
+    // A lock is set and cleared, so expect at least two assignments
+    let mut locks = Vec::new();
+    for variable in state_variables {
+        let is_flag = variable.type_name == "bool" || variable.type_name.starts_with("uint");
+        let normalized = variable.name.trim_start_matches('_').to_lowercase();
+        if !is_flag || !LOCK_NAMES.contains(&normalized.as_str()) {
+            continue;
+        }
+        let assign_re = Regex::new(&format!(r"\b{}\s*=[^=]", regex::escape(&variable.name))).map_err(|e| format!("Regex error: {}", e))?;
+        if assign_re.find_iter(code).count() >= 2 {
+            findings.push(finding(ReentrancyPattern::ManualLock, &variable.name));
+            locks.push(variable.name.as_str());
+        }
+    }
+
+    // Storage writes after the first external call; clearing a lock is the
+    // guard's own bookkeeping and doesn't count
+    let written: Vec<Regex> = state_variables
+        .iter()
+        .filter(|v| !locks.contains(&v.name.as_str()))
+        .map(|v| Regex::new(&format!(r"\b{}\b(\s*\[[^\]]*\])*\s*(=[^=]|\+=|-=|\+\+|--)", regex::escape(&v.name))))
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Regex error: {}", e))?;
+    let function_re = Regex::new(r"function\s+(\w+)\s*\([^)]*\)[^{;]*\{").map_err(|e| format!("Regex error: {}", e))?;
+    for captures in function_re.captures_iter(code) {
+        let body = block_body(&code[captures.get(0).unwrap().end()..]);
+        let Some(first_call) = EXTERNAL_CALLS.iter().filter_map(|call| body.find(call)).min() else {
+            continue;
+        };
+        if written.iter().any(|re| re.is_match(&body[first_call..])) {
+            findings.push(finding(ReentrancyPattern::StateWriteAfterCall, &captures[1]));
+        }
+    }
+
+    Ok(findings)
+}
+
+const REENTRANCY_NOTE: &str = "## Migration Notes: Reentrancy in ink!
+
+Solidity:
+- `nonReentrant` (OpenZeppelin `ReentrancyGuard`) or a hand-rolled `locked`/`_status` flag blocks re-entering calls
+- Checks-effects-interactions keeps state consistent when an external call calls back in
+
+ink! Equivalent:
+- Contracts are not reentrant by default: a cross-contract call back into the contract fails unless the call is built with `.set_allow_reentry(true)`
+- A plain `self.env().transfer` runs no recipient code, so it cannot re-enter
+- Cross-contract calls (`build_call`, contract refs) still hand over control, so keep updating storage *before* making them
+- If you do allow reentry, port the guard as a `locked: bool` storage field checked and set at the top of the message, cleared before returning";
+
+/// Reentrancy notes for contracts with guards or calls that need ordering
+/// care, naming the functions involved.
+pub fn reentrancy_notes(contract: &SolidityContract) -> Vec<String> {
+    if contract.reentrancy.is_empty() {
+        return Vec::new();
+    }
+
+    let subjects = |pattern: ReentrancyPattern| {
+        contract
+            .reentrancy
+            .iter()
+            .filter(|f| f.pattern == pattern)
+            .map(|f| format!("`{}`", f.subject))
+            .collect::<Vec<_>>()
+    };
+    let mut note = REENTRANCY_NOTE.to_string();
+    for (pattern, label) in [
+        (ReentrancyPattern::NonReentrantModifier, "Guarded with nonReentrant"),
+        (ReentrancyPattern::ManualLock, "Manual lock variables"),
+        (ReentrancyPattern::StateWriteAfterCall, "Write storage after an external call; reorder before migrating"),
+    ] {
+        let names = subjects(pattern);
+        if !names.is_empty() {
+            note.push_str(&format!("\n\n{}: {}", label, names.join(", ")));
+        }
+    }
+    vec![note]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::solidity_parser::SolidityParser;
+
+    #[test]
+    fn should_detect_non_reentrant_modifier_and_manual_lock() {
+        let contract = SolidityParser::new()
+            .parse_contract(
+                r#"
+contract Vault is ReentrancyGuard {
+    mapping(address => uint256) public balances;
+    bool private locked;
+
+    function withdraw(uint256 amount) external nonReentrant {
+        balances[msg.sender] -= amount;
+        payable(msg.sender).transfer(amount);
+    }
+
+    function withdrawAll() public {
+        require(!locked, "reentrant");
+        locked = true;
+        uint256 amount = balances[msg.sender];
+        balances[msg.sender] = 0;
+        (bool ok, ) = msg.sender.call{value: amount}("");
+        require(ok);
+        locked = false;
+    }
+}
+"#,
+            )
+            .unwrap();
+
+        assert_eq!(
+            contract.reentrancy,
+            vec![
+                ReentrancyFinding { pattern: ReentrancyPattern::NonReentrantModifier, subject: "withdraw".to_string() },
+                ReentrancyFinding { pattern: ReentrancyPattern::ManualLock, subject: "locked".to_string() },
+            ]
+        );
+
+        let notes = reentrancy_notes(&contract);
+        assert_eq!(notes.len(), 1);
+        assert!(notes[0].contains("set_allow_reentry"));
+        assert!(notes[0].contains("Guarded with nonReentrant: `withdraw`"));
+        assert!(notes[0].contains("Manual lock variables: `locked`"));
+    }
+
+    #[test]
+    fn should_flag_state_write_after_external_call() {
+        let contract = SolidityParser::new()
+            .parse_contract(
+                r#"
+contract Bank {
+    mapping(address => uint256) public balances;
+
+    function withdraw() public {
+        uint256 amount = balances[msg.sender];
+        (bool ok, ) = msg.sender.call{value: amount}("");
+        require(ok);
+        balances[msg.sender] = 0;
+    }
+}
+"#,
+            )
+            .unwrap();
+
+        assert_eq!(
+            contract.reentrancy,
+            vec![ReentrancyFinding { pattern: ReentrancyPattern::StateWriteAfterCall, subject: "withdraw".to_string() }]
+        );
+        assert!(reentrancy_notes(&contract)[0].contains("external call; reorder before migrating: `withdraw`"));
+
+        let flipper = SolidityParser::new()
+            .parse_contract("contract Flipper {\n    bool value;\n    function flip() public { value = !value; }\n}")
+            .unwrap();
+        assert!(reentrancy_notes(&flipper).is_empty());
+    }
+}
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use regex::Regex;
 
+use super::reentrancy::{detect_reentrancy, ReentrancyFinding};
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct SolidityFunction {
     pub name: String,
@@ -9,6 +11,19 @@ pub struct SolidityFunction {
     pub visibility: String,
     pub mutability: Option<String>,
     pub body: String,
+    /// Declared `virtual`, so derived contracts may override it
+    pub is_virtual: bool,
+    /// Declared `override`, replacing a base contract's function
+    pub is_override: bool,
+    /// Modifiers applied in the header, e.g. `onlyRole(ADMIN)`, in order
+    pub modifiers: Vec<ModifierInvocation>,
+}
+
+/// A modifier applied to a function, with its argument expressions as written.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModifierInvocation {
+    pub name: String,
+    pub args: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -26,12 +41,42 @@ pub struct SolidityStateVariable {
     pub is_mapping: bool,
     pub key_type: Option<String>,
     pub value_type: Option<String>,
+    /// Declared `constant`: fixed at compile time, not held in storage
+    #[serde(default)]
+    pub is_constant: bool,
+    /// Declared `immutable`: set once, by the constructor or its initializer
+    #[serde(default)]
+    pub is_immutable: bool,
+    /// The `= ...` initializer expression as written
+    #[serde(default)]
+    pub initial_value: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct SolidityEvent {
     pub name: String,
     pub parameters: Vec<SolidityParameter>,
+    /// Declared `anonymous`: no signature topic, so up to four indexed params
+    pub is_anonymous: bool,
+}
+
+/// A base constructor invoked from the constructor header, e.g. `ERC20("N", "S")`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BaseConstructorCall {
+    pub name: String,
+    /// Argument expressions as written
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SolidityModifier {
+    pub name: String,
+    pub params: Vec<SolidityParameter>,
+    pub body: String,
+    /// Statements that run before the `_;` placeholder
+    pub before_placeholder: String,
+    /// Statements that run after the `_;` placeholder
+    pub after_placeholder: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -41,8 +86,55 @@ pub struct SolidityContract {
     pub state_variables: Vec<SolidityStateVariable>,
     pub events: Vec<SolidityEvent>,
     pub custom_errors: Vec<String>,
+    pub modifiers: Vec<SolidityModifier>,
+    /// Base contracts listed after `is`, in declaration order
+    pub inherits: Vec<String>,
+    /// Paths named by top-level `import` statements, as written
+    pub imports: Vec<String>,
+    /// Declares `receive()` or a payable `fallback()`, so it accepts plain transfers
+    pub accepts_plain_transfers: bool,
+    /// Base constructors called between the constructor's `)` and `{`, which
+    /// the ink! constructor has to initialize inline
+    pub constructor_base_calls: Vec<BaseConstructorCall>,
+    /// Modifiers applied to the constructor, e.g. `initializer`
+    pub constructor_modifiers: Vec<String>,
+    /// Reentrancy guards and external-call orderings worth a migration note
+    pub reentrancy: Vec<ReentrancyFinding>,
+}
+
+/// A `library` block and its (typically internal pure) helper functions.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SolidityLibrary {
+    pub name: String,
+    pub functions: Vec<SolidityFunction>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    /// Input is empty, whitespace or comments only, or declares no contract
+    NoContract,
+    Invalid(String),
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::NoContract => write!(f, "No contract name found"),
+            ParseError::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<String> for ParseError {
+    fn from(message: String) -> Self {
+        ParseError::Invalid(message)
+    }
 }
 
+#[derive(Default)]
 pub struct SolidityParser;
 
 impl SolidityParser {
@@ -50,10 +142,16 @@ impl SolidityParser {
         Self
     }
 
-    pub fn parse_contract(&self, content: &str) -> Result<SolidityContract, String> {
+    pub fn parse_contract(&self, content: &str) -> Result<SolidityContract, ParseError> {
         // Parse contract name
         let contract_name = self.parse_contract_name(content)?;
         
+        // Parse base contracts
+        let inherits = self.parse_inherits(content)?;
+
+        // Parse imported files
+        let imports = parse_imports(content)?;
+        
         // Parse state variables
         let state_variables = self.parse_state_variables(content)?;
         
@@ -66,21 +164,111 @@ impl SolidityParser {
         // Parse custom errors
         let custom_errors = self.parse_custom_errors(content)?;
         
+        // Parse modifier definitions
+        let modifiers = self.parse_modifiers(content)?;
+
+        // receive()/fallback() are declared without the `function` keyword
+        let receive_re = Regex::new(r"\b(receive\s*\(\s*\)\s*external|fallback\s*\([^)]*\)\s*external[^{;]*\bpayable)\b")
+            .map_err(|e| format!("Regex error: {}", e))?;
+        let accepts_plain_transfers = receive_re.is_match(&strip_comments(content));
+
+        // Base constructor calls and modifiers on the constructor
+        let (constructor_base_calls, constructor_modifiers) = self.parse_constructor_header(content, &inherits)?;
+
+        let reentrancy = detect_reentrancy(&strip_comments(content), &state_variables)?;
+        
         Ok(SolidityContract {
             name: contract_name,
             functions,
             state_variables,
             events,
             custom_errors,
+            modifiers,
+            inherits,
+            imports,
+            accepts_plain_transfers,
+            constructor_base_calls,
+            constructor_modifiers,
+            reentrancy,
         })
     }
+
+    /// Every `library` declared in `content`.
+    #[allow(dead_code)]
+    pub fn parse_libraries(&self, content: &str) -> Result<Vec<SolidityLibrary>, String> {
+        let code = strip_comments(content);
+        let library_re = Regex::new(r"\blibrary\s+(\w+)\s*\{").map_err(|e| format!("Regex error: {}", e))?;
+
+        let mut libraries = Vec::new();
+        for captures in library_re.captures_iter(&code) {
+            let body = block_body(&code[captures.get(0).unwrap().end()..]);
+            libraries.push(SolidityLibrary {
+                name: captures.get(1).unwrap().as_str().to_string(),
+                functions: self.parse_functions(body)?,
+            });
+        }
+
+        Ok(libraries)
+    }
+
+    /// Split what sits between the constructor's parameters and its body into
+    /// base constructor calls and modifiers. Names listed in `inherits`, or
+    /// capitalized names called with parentheses, count as base calls.
+    fn parse_constructor_header(
+        &self,
+        content: &str,
+        inherits: &[String],
+    ) -> Result<(Vec<BaseConstructorCall>, Vec<String>), String> {
+        let constructor_re = Regex::new(CONSTRUCTOR_PATTERN).map_err(|e| format!("Regex error: {}", e))?;
+        let Some(captures) = constructor_re.captures(content) else {
+            return Ok((Vec::new(), Vec::new()));
+        };
+
+        let invocation_re = Regex::new(r"(\w+)\s*(\(((?:[^()]|\([^()]*\))*)\))?").map_err(|e| format!("Regex error: {}", e))?;
+        let mut base_calls = Vec::new();
+        let mut modifiers = Vec::new();
+        for invocation in invocation_re.captures_iter(captures.get(2).unwrap().as_str()) {
+            let name = invocation.get(1).unwrap().as_str();
+            if matches!(name, "public" | "internal" | "payable") {
+                continue;
+            }
+
+            let args = invocation.get(3).map(|a| split_args(a.as_str()));
+            let is_base = inherits.iter().any(|base| base == name)
+                || (args.is_some() && name.starts_with(|c: char| c.is_ascii_uppercase()));
+            if is_base {
+                base_calls.push(BaseConstructorCall {
+                    name: name.to_string(),
+                    args: args.unwrap_or_default(),
+                });
+            } else {
+                modifiers.push(name.to_string());
+            }
+        }
+
+        Ok((base_calls, modifiers))
+    }
+
+    fn parse_inherits(&self, content: &str) -> Result<Vec<String>, String> {
+        let code = strip_comments(content);
+        let inherits_re = Regex::new(r"\bcontract\s+\w+(?:\s+is\s+([^{]+))?\s*\{").map_err(|e| format!("Regex error: {}", e))?;
+
+        Ok(inherits_re
+            .captures(&code)
+            .and_then(|c| c.get(1))
+            .map(|bases| split_bases(bases.as_str()))
+            .unwrap_or_default())
+    }
     
-    fn parse_contract_name(&self, content: &str) -> Result<String, String> {
-        let contract_re = Regex::new(r"contract\s+(\w+)").map_err(|e| format!("Regex error: {}", e))?;
-        if let Some(captures) = contract_re.captures(content) {
+    fn parse_contract_name(&self, content: &str) -> Result<String, ParseError> {
+        // Look for the declaration outside comments so commented-out code and
+        // comment-only files don't count
+        let code = strip_comments(content);
+        let contract_re = Regex::new(r"\bcontract\s+(\w+)").map_err(|e| format!("Regex error: {}", e))?;
+        if let Some(captures) = contract_re.captures(&code) {
             Ok(captures.get(1).unwrap().as_str().to_string())
         } else {
-            Err("No contract name found".to_string())
+            Err(ParseError::NoContract)
         }
     }
     
@@ -91,6 +279,10 @@ impl SolidityParser {
         let var_re = Regex::new(r"(\w+)\s+(public|private|internal)\s+(\w+);").map_err(|e| format!("Regex error: {}", e))?;
         for captures in var_re.captures_iter(content) {
             let type_name = captures.get(1).unwrap().as_str();
+            // `address immutable public owner;` is handled below
+            if matches!(type_name, "constant" | "immutable") {
+                continue;
+            }
             let visibility = captures.get(2).unwrap().as_str();
             let name = captures.get(3).unwrap().as_str();
             
@@ -101,9 +293,41 @@ impl SolidityParser {
                 is_mapping: false,
                 key_type: None,
                 value_type: None,
+                is_constant: false,
+                is_immutable: false,
+                initial_value: None,
             });
         }
         
+        // Parse constants and immutables, which may carry an initializer and
+        // list `constant`/`immutable` before or after the visibility
+        let fixed_re = Regex::new(r"\b(\w+)((?:\s+(?:public|private|internal|constant|immutable))+)\s+(\w+)\s*(?:=\s*([^;]+))?;")
+            .map_err(|e| format!("Regex error: {}", e))?;
+        for captures in fixed_re.captures_iter(content) {
+            let keywords: Vec<&str> = captures.get(2).unwrap().as_str().split_whitespace().collect();
+            let is_constant = keywords.contains(&"constant");
+            let is_immutable = keywords.contains(&"immutable");
+            if !is_constant && !is_immutable {
+                continue;
+            }
+            let visibility = ["public", "private", "internal"]
+                .into_iter()
+                .find(|v| keywords.contains(v))
+                .unwrap_or("internal");
+
+            variables.push(SolidityStateVariable {
+                name: captures.get(3).unwrap().as_str().to_string(),
+                type_name: captures.get(1).unwrap().as_str().to_string(),
+                visibility: visibility.to_string(),
+                is_mapping: false,
+                key_type: None,
+                value_type: None,
+                is_constant,
+                is_immutable,
+                initial_value: captures.get(4).map(|v| v.as_str().trim().to_string()),
+            });
+        }
+
         // Parse mappings
         let mapping_re = Regex::new(r"mapping\((\w+)\s*=>\s*(\w+)\)\s+(public|private|internal)\s+(\w+);").map_err(|e| format!("Regex error: {}", e))?;
         for captures in mapping_re.captures_iter(content) {
@@ -119,6 +343,9 @@ impl SolidityParser {
                 is_mapping: true,
                 key_type: Some(key_type.to_string()),
                 value_type: Some(value_type.to_string()),
+                is_constant: false,
+                is_immutable: false,
+                initial_value: None,
             });
         }
         
@@ -138,6 +365,9 @@ impl SolidityParser {
                 is_mapping: true,
                 key_type: Some(key_type.to_string()),
                 value_type: Some(format!("mapping({} => {})", inner_key_type, value_type)),
+                is_constant: false,
+                is_immutable: false,
+                initial_value: None,
             });
         }
         
@@ -148,10 +378,11 @@ impl SolidityParser {
         let mut functions = Vec::new();
         
         // Parse constructor - handle multiline with dot-all modifier
-        let constructor_re = Regex::new(r"(?s)constructor\s*\((.*?)\)\s*\{(.*?)\}").map_err(|e| format!("Regex error: {}", e))?;
+        let constructor_re = Regex::new(CONSTRUCTOR_PATTERN).map_err(|e| format!("Regex error: {}", e))?;
         if let Some(captures) = constructor_re.captures(content) {
             let params_str = captures.get(1).unwrap().as_str();
-            let body = captures.get(2).unwrap().as_str();
+            let header: Vec<&str> = captures.get(2).unwrap().as_str().split_whitespace().collect();
+            let body = captures.get(3).unwrap().as_str();
             
             let parameters = self.parse_parameters(params_str)?;
             
@@ -159,19 +390,41 @@ impl SolidityParser {
                 name: "constructor".to_string(),
                 parameters,
                 return_type: None,
-                visibility: "public".to_string(),
-                mutability: None,
+                visibility: if header.contains(&"internal") { "internal" } else { "public" }.to_string(),
+                mutability: header.contains(&"payable").then(|| "payable".to_string()),
                 body: body.to_string(),
+                is_virtual: false,
+                is_override: false,
+                modifiers: Vec::new(),
             });
         }
         
         // Parse regular functions - handle multiline with dot-all modifier
-        let function_re = Regex::new(r"(?s)function\s+(\w+)\s*\((.*?)\)\s+(public|private|internal|external)(?:\s+(view|pure|payable))?\s*(?:returns\s*\(([^)]*)\))?\s*\{(.*?)\}").map_err(|e| format!("Regex error: {}", e))?;
+        // Mutability, `virtual`, `override` (optionally listing bases) and
+        // modifier invocations may follow the visibility in any order
+        let function_re = Regex::new(r"(?s)function\s+(\w+)\s*\((.*?)\)\s+(public|private|internal|external)((?:\s+\w+(?:\s*\([^)]*\))?)*?)\s*(?:returns\s*\(([^)]*)\))?\s*\{").map_err(|e| format!("Regex error: {}", e))?;
+        let specifier_re = Regex::new(r"(\w+)(?:\s*\(([^)]*)\))?").map_err(|e| format!("Regex error: {}", e))?;
         for captures in function_re.captures_iter(content) {
             let name = captures.get(1).unwrap().as_str();
             let params_str = captures.get(2).unwrap().as_str();
             let visibility = captures.get(3).unwrap().as_str();
-            let mutability = captures.get(4).map(|m| m.as_str().to_string());
+            let mut specifiers = Vec::new();
+            let mut modifiers = Vec::new();
+            for specifier in specifier_re.captures_iter(captures.get(4).unwrap().as_str()) {
+                let specifier_name = specifier.get(1).unwrap().as_str();
+                if FUNCTION_SPECIFIERS.contains(&specifier_name) {
+                    specifiers.push(specifier_name);
+                } else {
+                    modifiers.push(ModifierInvocation {
+                        name: specifier_name.to_string(),
+                        args: specifier.get(2).map(|args| split_args(args.as_str())).unwrap_or_default(),
+                    });
+                }
+            }
+            let mutability = ["view", "pure", "payable"]
+                .into_iter()
+                .find(|m| specifiers.contains(m))
+                .map(|m| m.to_string());
             let return_type = captures.get(5).map(|r| {
                 // Extract just the type part from "type name" format
                 let return_str = r.as_str().trim();
@@ -181,7 +434,8 @@ impl SolidityParser {
                     return_str.to_string()
                 }
             });
-            let body = captures.get(6).unwrap().as_str();
+            // Up to the matching brace, so `call{value: x}` doesn't end the body
+            let body = block_body(&content[captures.get(0).unwrap().end()..]);
             
             let parameters = self.parse_parameters(params_str)?;
             
@@ -192,13 +446,16 @@ impl SolidityParser {
                 visibility: visibility.to_string(),
                 mutability,
                 body: body.to_string(),
+                is_virtual: specifiers.contains(&"virtual"),
+                is_override: specifiers.contains(&"override"),
+                modifiers,
             });
         }
         
         Ok(functions)
     }
     
-    fn parse_parameters(&self, params_str: &str) -> Result<Vec<SolidityParameter>, String> {
+    pub(crate) fn parse_parameters(&self, params_str: &str) -> Result<Vec<SolidityParameter>, String> {
         let mut parameters = Vec::new();
         
         if params_str.trim().is_empty() {
@@ -237,7 +494,7 @@ impl SolidityParser {
     fn parse_events(&self, content: &str) -> Result<Vec<SolidityEvent>, String> {
         let mut events = Vec::new();
         
-        let event_re = Regex::new(r"event\s+(\w+)\s*\((.*?)\);").map_err(|e| format!("Regex error: {}", e))?;
+        let event_re = Regex::new(r"event\s+(\w+)\s*\((.*?)\)\s*(anonymous)?\s*;").map_err(|e| format!("Regex error: {}", e))?;
         for captures in event_re.captures_iter(content) {
             let name = captures.get(1).unwrap().as_str();
             let params_str = captures.get(2).unwrap().as_str();
@@ -247,6 +504,7 @@ impl SolidityParser {
             events.push(SolidityEvent {
                 name: name.to_string(),
                 parameters,
+                is_anonymous: captures.get(3).is_some(),
             });
         }
         
@@ -261,27 +519,22 @@ impl SolidityParser {
         }
         
         // Split by comma and parse each parameter
-        for param in params_str.split(',') {
-            let param = param.trim();
-            if param.is_empty() {
-                continue;
-            }
-            
-            // Check if indexed
-            let is_indexed = param.contains("indexed");
-            
-            // Parse parameter format: "type indexed name" or "type name"
+        for (position, param) in params_str.split(',').map(str::trim).filter(|p| !p.is_empty()).enumerate() {
+            // Parse parameter format: "type indexed name", "type name", "type indexed" or "type"
             let parts: Vec<&str> = param.split_whitespace().collect();
-            if parts.len() >= 2 {
-                let type_name = parts[0].to_string();
-                let name = parts.last().unwrap().to_string();
-                
-                parameters.push(SolidityParameter {
-                    name,
-                    type_name,
-                    is_indexed,
-                });
-            }
+            let is_indexed = parts[1..].contains(&"indexed");
+            let type_name = parts[0].to_string();
+            // Unnamed params get a positional name so they can still be emitted as fields
+            let name = match parts[1..].iter().rfind(|part| !["indexed", "payable"].contains(*part)) {
+                Some(name) => name.to_string(),
+                None => format!("param{}", position),
+            };
+
+            parameters.push(SolidityParameter {
+                name,
+                type_name,
+                is_indexed,
+            });
         }
         
         Ok(parameters)
@@ -298,6 +551,141 @@ impl SolidityParser {
         
         Ok(errors)
     }
+    
+    fn parse_modifiers(&self, content: &str) -> Result<Vec<SolidityModifier>, String> {
+        let mut modifiers = Vec::new();
+        
+        let modifier_re = Regex::new(r"modifier\s+(\w+)\s*(?:\(([^)]*)\))?\s*(?:virtual\s*|override\s*)*\{").map_err(|e| format!("Regex error: {}", e))?;
+        for captures in modifier_re.captures_iter(content) {
+            let name = captures.get(1).unwrap().as_str();
+            let params = self.parse_parameters(captures.get(2).map(|p| p.as_str()).unwrap_or(""))?;
+            let body = block_body(&content[captures.get(0).unwrap().end()..]);
+            
+            let (before_placeholder, after_placeholder) = match body.find("_;") {
+                Some(pos) => (body[..pos].trim(), body[pos + 2..].trim()),
+                None => (body.trim(), ""),
+            };
+            
+            modifiers.push(SolidityModifier {
+                name: name.to_string(),
+                params,
+                body: body.trim().to_string(),
+                before_placeholder: before_placeholder.to_string(),
+                after_placeholder: after_placeholder.to_string(),
+            });
+        }
+        
+        Ok(modifiers)
+    }
+}
+
+/// Header keywords that are not modifier invocations
+const FUNCTION_SPECIFIERS: &[&str] = &["view", "pure", "payable", "virtual", "override"];
+
+/// `constructor(params) header { body }`; the header holds visibility,
+/// `payable`, modifiers and base constructor calls
+const CONSTRUCTOR_PATTERN: &str = r"(?s)\bconstructor\s*\(([^)]*)\)([^{;]*)\{(.*?)\}";
+
+/// Split a call's argument list on top-level commas, ignoring commas inside
+/// nested parentheses and string literals.
+fn split_args(list: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let (mut depth, mut quote, mut start) = (0, None, 0);
+    for (i, c) in list.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(' | '[') => depth += 1,
+            (None, ')' | ']') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                args.push(list[start..i].trim().to_string());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    let last = list[start..].trim();
+    if !last.is_empty() || !args.is_empty() {
+        args.push(last.to_string());
+    }
+    args
+}
+
+/// Paths of the top-level imports in `content`, covering `import "x";`,
+/// `import "x" as Y;`, `import {Y} from "x";` and `import * as Y from "x";`.
+pub(crate) fn parse_imports(content: &str) -> Result<Vec<String>, String> {
+    let import_re = Regex::new(r#"(?m)^\s*import\s+(?:[^"';]*\s+from\s+)?["']([^"']+)["']"#)
+        .map_err(|e| format!("Regex error: {}", e))?;
+
+    Ok(import_re
+        .captures_iter(&strip_comments(content))
+        .map(|c| c.get(1).unwrap().as_str().to_string())
+        .collect())
+}
+
+/// Base contract names from an `is A, B(arg)` list, without constructor arguments.
+pub(crate) fn split_bases(list: &str) -> Vec<String> {
+    // Only commas outside constructor arguments separate bases
+    let mut bases = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in list.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                bases.push(&list[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    bases.push(&list[start..]);
+
+    bases
+        .into_iter()
+        .filter_map(|base| base.split_whitespace().next())
+        .map(|base| base.split('(').next().unwrap_or(base).to_string())
+        .filter(|base| !base.is_empty())
+        .collect()
+}
+
+/// Remove `//` line comments and `/* */` block comments.
+pub(crate) fn strip_comments(content: &str) -> String {
+    let mut code = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(start) = rest.find("//").into_iter().chain(rest.find("/*")).min() {
+        code.push_str(&rest[..start]);
+        let end = if rest[start..].starts_with("//") {
+            rest[start..].find('\n').map(|i| start + i).unwrap_or(rest.len())
+        } else {
+            rest[start + 2..].find("*/").map(|i| start + 2 + i + 2).unwrap_or(rest.len())
+        };
+        rest = &rest[end..];
+    }
+
+    code.push_str(rest);
+    code
+}
+
+/// Text between an opening brace (already consumed) and its matching close.
+pub(crate) fn block_body(rest: &str) -> &str {
+    let mut depth = 1;
+    for (i, c) in rest.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return &rest[..i];
+                }
+            }
+            _ => {}
+        }
+    }
+    rest
 }
 
 #[cfg(test)]
@@ -415,4 +803,273 @@ contract SimpleERC20 {
         assert!(contract.custom_errors.contains(&"InsufficientBalance".to_string()));
         assert!(contract.custom_errors.contains(&"InsufficientAllowance".to_string()));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn should_parse_modifier_definitions() {
+        let solidity_code = r#"
+pragma solidity ^0.8.0;
+
+contract Owned {
+    address public owner;
+    bool private locked;
+
+    modifier onlyOwner() {
+        require(msg.sender == owner, "Not owner");
+        _;
+    }
+
+    modifier nonReentrant {
+        require(!locked, "Reentrant call");
+        locked = true;
+        _;
+        locked = false;
+    }
+
+    modifier validAmount(uint256 amount) {
+        if (amount == 0) {
+            revert("Zero amount");
+        }
+        _;
+    }
+
+    function setOwner(address newOwner) public onlyOwner {
+        owner = newOwner;
+    }
+}
+"#;
+
+        let parser = SolidityParser::new();
+        let contract = parser.parse_contract(solidity_code).unwrap();
+
+        assert_eq!(contract.modifiers.len(), 3);
+
+        let only_owner = &contract.modifiers[0];
+        assert_eq!(only_owner.name, "onlyOwner");
+        assert!(only_owner.params.is_empty());
+        assert_eq!(only_owner.before_placeholder, r#"require(msg.sender == owner, "Not owner");"#);
+        assert_eq!(only_owner.after_placeholder, "");
+        assert!(only_owner.body.contains("_;"));
+
+        let non_reentrant = &contract.modifiers[1];
+        assert_eq!(non_reentrant.name, "nonReentrant");
+        assert_eq!(non_reentrant.after_placeholder, "locked = false;");
+
+        let valid_amount = &contract.modifiers[2];
+        assert_eq!(valid_amount.params[0].name, "amount");
+        assert_eq!(valid_amount.params[0].type_name, "uint256");
+        assert!(valid_amount.before_placeholder.contains("revert(\"Zero amount\");\n        }"));
+    }
+
+    #[test]
+    fn should_reject_input_without_a_contract() {
+        let parser = SolidityParser::new();
+
+        assert_eq!(parser.parse_contract(""), Err(ParseError::NoContract));
+        assert_eq!(parser.parse_contract("  \n\t  "), Err(ParseError::NoContract));
+
+        let comments_only = r#"
+// SPDX-License-Identifier: MIT
+/* contract Commented {
+    function f() public {}
+} */
+// contract AlsoCommented {}
+"#;
+        assert_eq!(parser.parse_contract(comments_only), Err(ParseError::NoContract));
+    }
+
+    #[test]
+    fn should_parse_bodyless_contract() {
+        let parser = SolidityParser::new();
+        let contract = parser.parse_contract("contract Empty {}").unwrap();
+
+        assert_eq!(contract.name, "Empty");
+        assert!(contract.functions.is_empty());
+        assert!(contract.state_variables.is_empty());
+        assert!(contract.events.is_empty());
+        assert!(contract.custom_errors.is_empty());
+        assert!(contract.modifiers.is_empty());
+        assert!(contract.inherits.is_empty());
+    }
+
+    #[test]
+    fn should_parse_inherited_base_contracts() {
+        let parser = SolidityParser::new();
+        let contract = parser
+            .parse_contract("contract Vault is Ownable, ERC20(\"Vault\", \"VLT\") {\n    uint256 public total;\n}")
+            .unwrap();
+
+        assert_eq!(contract.inherits, vec!["Ownable", "ERC20"]);
+
+        let contract = parser.parse_contract("contract MyToken is ERC20 {}").unwrap();
+        assert_eq!(contract.name, "MyToken");
+        assert_eq!(contract.inherits, vec!["ERC20"]);
+    }
+
+    #[test]
+    fn should_parse_both_import_forms() {
+        let parser = SolidityParser::new();
+        let contract = parser
+            .parse_contract(
+                r#"
+// import "./Commented.sol";
+import "./Base.sol";
+import {Ownable} from "@openzeppelin/contracts/access/Ownable.sol";
+import { ERC20, IERC20 } from './token/ERC20.sol';
+
+contract MyToken is ERC20, Ownable {
+    uint256 public cap;
+}
+"#,
+            )
+            .unwrap();
+
+        assert_eq!(
+            contract.imports,
+            vec!["./Base.sol", "@openzeppelin/contracts/access/Ownable.sol", "./token/ERC20.sol"]
+        );
+        assert_eq!(contract.inherits, vec!["ERC20", "Ownable"]);
+        assert!(parser.parse_contract("contract Empty {}").unwrap().imports.is_empty());
+    }
+
+    #[test]
+    fn should_capture_constructor_base_calls_and_modifiers() {
+        let parser = SolidityParser::new();
+        let contract = parser
+            .parse_contract(
+                r#"
+contract Token is ERC20, Ownable {
+    uint256 public cap;
+
+    constructor(uint256 s) ERC20("N", "S") Ownable() initializer payable {
+        cap = s;
+    }
+}
+"#,
+            )
+            .unwrap();
+
+        assert_eq!(
+            contract.constructor_base_calls,
+            vec![
+                BaseConstructorCall { name: "ERC20".to_string(), args: vec!["\"N\"".to_string(), "\"S\"".to_string()] },
+                BaseConstructorCall { name: "Ownable".to_string(), args: vec![] },
+            ]
+        );
+        assert_eq!(contract.constructor_modifiers, vec!["initializer"]);
+
+        let constructor = contract.functions.iter().find(|f| f.name == "constructor").unwrap();
+        assert_eq!(constructor.parameters.len(), 1);
+        assert_eq!(constructor.parameters[0].name, "s");
+        assert_eq!(constructor.mutability.as_deref(), Some("payable"));
+        assert!(constructor.body.contains("cap = s;"));
+    }
+
+    #[test]
+    fn should_detect_receive_and_payable_fallback() {
+        let parser = SolidityParser::new();
+        let receive = parser.parse_contract("contract Vault {\n    receive() external payable {}\n}").unwrap();
+        assert!(receive.accepts_plain_transfers);
+
+        let fallback = parser.parse_contract("contract Proxy {\n    fallback() external payable {}\n}").unwrap();
+        assert!(fallback.accepts_plain_transfers);
+
+        let plain = parser.parse_contract("contract Proxy {\n    fallback() external {}\n}").unwrap();
+        assert!(!plain.accepts_plain_transfers);
+    }
+
+    #[test]
+    fn should_name_unnamed_event_params_by_position() {
+        let contract = SolidityParser::new()
+            .parse_contract("contract Token {\n    event Transfer(address indexed, address, uint256 value);\n}")
+            .unwrap();
+
+        let event = &contract.events[0];
+        assert!(!event.is_anonymous);
+        let params: Vec<(&str, &str, bool)> = event
+            .parameters
+            .iter()
+            .map(|p| (p.type_name.as_str(), p.name.as_str(), p.is_indexed))
+            .collect();
+        assert_eq!(
+            params,
+            vec![("address", "param0", true), ("address", "param1", false), ("uint256", "value", false)]
+        );
+    }
+
+    #[test]
+    fn should_flag_anonymous_events() {
+        let contract = SolidityParser::new()
+            .parse_contract("contract Log {\n    event Logged(bytes32 indexed topic, address indexed sender) anonymous;\n    event Plain(uint256 amount);\n}")
+            .unwrap();
+
+        assert_eq!(contract.events.len(), 2);
+        assert!(contract.events[0].is_anonymous);
+        assert_eq!(contract.events[0].parameters[1].name, "sender");
+        assert!(contract.events[0].parameters.iter().all(|p| p.is_indexed));
+        assert!(!contract.events[1].is_anonymous);
+    }
+
+    #[test]
+    fn should_capture_virtual_and_override_specifiers() {
+        let contract = SolidityParser::new()
+            .parse_contract(
+                r#"
+contract Token is Base {
+    function foo() public virtual override returns (uint256) {
+        return 1;
+    }
+
+    function bar() external view override(Base, IToken) returns (bool ok) {
+        return true;
+    }
+
+    function baz() public {
+    }
+}
+"#,
+            )
+            .unwrap();
+
+        assert_eq!(contract.functions.len(), 3);
+        let foo = &contract.functions[0];
+        assert_eq!(foo.name, "foo");
+        assert!(foo.is_virtual);
+        assert!(foo.is_override);
+        assert_eq!(foo.return_type.as_deref(), Some("uint256"));
+        assert_eq!(foo.mutability, None);
+
+        let bar = &contract.functions[1];
+        assert!(!bar.is_virtual);
+        assert!(bar.is_override);
+        assert_eq!(bar.mutability.as_deref(), Some("view"));
+        assert_eq!(bar.return_type.as_deref(), Some("bool"));
+
+        assert!(!contract.functions[2].is_virtual && !contract.functions[2].is_override);
+    }
+
+    #[test]
+    fn should_capture_every_applied_modifier() {
+        let parser = SolidityParser::new();
+        let contract = parser
+            .parse_contract(
+                r#"
+contract Vault is Ownable, ReentrancyGuard, AccessControl {
+    function withdraw() public onlyOwner nonReentrant {
+        payable(msg.sender).transfer(address(this).balance);
+    }
+
+    function mint(address to, uint256 amount) external onlyRole(MINTER_ROLE) whenNotPaused returns (bool) {
+        return true;
+    }
+}
+"#,
+            )
+            .unwrap();
+
+        let names = |index: usize| contract.functions[index].modifiers.iter().map(|m| m.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names(0), vec!["onlyOwner", "nonReentrant"]);
+        assert_eq!(names(1), vec!["onlyRole", "whenNotPaused"]);
+        assert_eq!(contract.functions[1].modifiers[0].args, vec!["MINTER_ROLE"]);
+        assert_eq!(contract.functions[1].return_type.as_deref(), Some("bool"));
+    }
+}
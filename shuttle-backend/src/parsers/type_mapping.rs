@@ -0,0 +1,173 @@
+/// An ink! type, keeping nested mappings flat so they can be rendered as a
+/// single `Mapping` keyed by a tuple.
+enum InkType {
+    Plain(String),
+    Mapping { keys: Vec<String>, value: String },
+}
+
+impl InkType {
+    fn render(self) -> String {
+        match self {
+            InkType::Plain(ty) => ty,
+            InkType::Mapping { keys, value } if keys.len() == 1 => format!("Mapping<{}, {}>", keys[0], value),
+            InkType::Mapping { keys, value } => format!("Mapping<({}), {}>", keys.join(", "), value),
+        }
+    }
+}
+
+/// Map a Solidity type to its ink! equivalent.
+///
+/// `uint256`/`int256` narrow to `u128`/`i128` (ink!'s `Balance` width),
+/// arrays become `Vec<T>` or `[T; N]`, and nested mappings flatten into one
+/// `Mapping` keyed by a tuple, e.g. `mapping(address => mapping(address => uint256))`
+/// becomes `Mapping<(AccountId, AccountId), u128>`.
+pub fn map_solidity_type(solidity_type: &str) -> Result<String, String> {
+    map_type(strip_location(solidity_type)).map(InkType::render)
+}
+
+fn strip_location(solidity_type: &str) -> &str {
+    let trimmed = solidity_type.trim();
+    ["memory", "storage", "calldata"]
+        .iter()
+        .find_map(|location| trimmed.strip_suffix(location).filter(|rest| rest.ends_with(char::is_whitespace)))
+        .map(str::trim_end)
+        .unwrap_or(trimmed)
+}
+
+fn map_type(solidity_type: &str) -> Result<InkType, String> {
+    let ty = solidity_type.trim();
+
+    if let Some(rest) = ty.strip_prefix("mapping") {
+        return map_mapping(ty, rest.trim_start());
+    }
+
+    if let Some(element) = ty.strip_suffix(']') {
+        let open = element.rfind('[').ok_or_else(|| format!("Unbalanced brackets in type: {}", ty))?;
+        let (element, size) = (&element[..open], element[open + 1..].trim());
+        let element = match map_type(element)? {
+            InkType::Plain(element) => element,
+            InkType::Mapping { .. } => return Err(format!("Arrays of mappings are not supported: {}", ty)),
+        };
+
+        return if size.is_empty() {
+            Ok(InkType::Plain(format!("Vec<{}>", element)))
+        } else {
+            let size: usize = size.parse().map_err(|_| format!("Invalid array length in type: {}", ty))?;
+            Ok(InkType::Plain(format!("[{}; {}]", element, size)))
+        };
+    }
+
+    map_elementary(ty).map(InkType::Plain)
+}
+
+fn map_mapping(ty: &str, rest: &str) -> Result<InkType, String> {
+    let inner = rest
+        .strip_prefix('(')
+        .and_then(|r| r.trim_end().strip_suffix(')'))
+        .ok_or_else(|| format!("Malformed mapping: {}", ty))?;
+    let (key, value) = inner.split_once("=>").ok_or_else(|| format!("Mapping without '=>': {}", ty))?;
+
+    // Solidity 0.8.18+ allows named keys/values: mapping(address owner => uint256 balance)
+    let key = key.split_whitespace().next().ok_or_else(|| format!("Mapping without key type: {}", ty))?;
+    let key = map_elementary(key)?;
+
+    match map_type(strip_value_name(value))? {
+        InkType::Plain(value) => Ok(InkType::Mapping { keys: vec![key], value }),
+        InkType::Mapping { keys, value } => {
+            let mut all_keys = vec![key];
+            all_keys.extend(keys);
+            Ok(InkType::Mapping { keys: all_keys, value })
+        }
+    }
+}
+
+/// Drop a trailing value name from `uint256 balance`, leaving nested
+/// mappings and arrays intact.
+fn strip_value_name(value: &str) -> &str {
+    let value = value.trim();
+    if value.starts_with("mapping") || value.ends_with(']') {
+        return value;
+    }
+    match value.rsplit_once(char::is_whitespace) {
+        Some((ty, name)) if ty.trim() != "address" || name != "payable" => ty.trim(),
+        _ => value,
+    }
+}
+
+fn map_elementary(ty: &str) -> Result<String, String> {
+    let mapped = match ty {
+        "address" | "address payable" => "AccountId".to_string(),
+        "bool" => "bool".to_string(),
+        "string" => "String".to_string(),
+        "bytes" => "Vec<u8>".to_string(),
+        "uint" => "u128".to_string(),
+        "int" => "i128".to_string(),
+        _ => {
+            if let Some(bits) = ty.strip_prefix("uint") {
+                format!("u{}", integer_width(ty, bits)?)
+            } else if let Some(bits) = ty.strip_prefix("int") {
+                format!("i{}", integer_width(ty, bits)?)
+            } else if let Some(len) = ty.strip_prefix("bytes") {
+                match len.parse::<usize>() {
+                    Ok(len @ 1..=32) => format!("[u8; {}]", len),
+                    _ => return Err(format!("Unsupported Solidity type: {}", ty)),
+                }
+            } else if ty.chars().next().is_some_and(|c| c.is_ascii_uppercase())
+                && ty.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                // Structs, enums and contract types keep their name
+                ty.to_string()
+            } else {
+                return Err(format!("Unsupported Solidity type: {}", ty));
+            }
+        }
+    };
+    Ok(mapped)
+}
+
+/// Smallest Rust integer width holding a Solidity `uintN`/`intN`, capped at 128.
+fn integer_width(ty: &str, bits: &str) -> Result<u32, String> {
+    let bits: u32 = bits.parse().map_err(|_| format!("Unsupported Solidity type: {}", ty))?;
+    if bits == 0 || bits > 256 || !bits.is_multiple_of(8) {
+        return Err(format!("Unsupported Solidity type: {}", ty));
+    }
+    Ok(bits.next_power_of_two().clamp(8, 128))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_map_scalars() {
+        assert_eq!(map_solidity_type("uint256").unwrap(), "u128");
+        assert_eq!(map_solidity_type("uint24").unwrap(), "u32");
+        assert_eq!(map_solidity_type("int8").unwrap(), "i8");
+        assert_eq!(map_solidity_type("address").unwrap(), "AccountId");
+        assert_eq!(map_solidity_type("bytes32").unwrap(), "[u8; 32]");
+        assert_eq!(map_solidity_type("string memory").unwrap(), "String");
+    }
+
+    #[test]
+    fn should_map_arrays_and_mappings() {
+        assert_eq!(map_solidity_type("uint256[]").unwrap(), "Vec<u128>");
+        assert_eq!(map_solidity_type("address[3]").unwrap(), "[AccountId; 3]");
+        assert_eq!(map_solidity_type("mapping(address => uint256)").unwrap(), "Mapping<AccountId, u128>");
+        assert_eq!(
+            map_solidity_type("mapping(address => mapping(address => uint256))").unwrap(),
+            "Mapping<(AccountId, AccountId), u128>"
+        );
+        assert_eq!(
+            map_solidity_type("mapping(address => uint256[])").unwrap(),
+            "Mapping<AccountId, Vec<u128>>"
+        );
+    }
+
+    #[test]
+    fn should_reject_unparseable_types() {
+        assert!(map_solidity_type("uint7").is_err());
+        assert!(map_solidity_type("mapping(address uint256)").is_err());
+        assert!(map_solidity_type("bytes33").is_err());
+        assert!(map_solidity_type("").is_err());
+    }
+}
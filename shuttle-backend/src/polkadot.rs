@@ -89,6 +89,7 @@ pub struct PolkadotClient {
     client: Option<OnlineClient<SubxtPolkadotConfig>>,
     #[allow(dead_code)]
     config: PolkadotConfig,
+    #[allow(dead_code)]
     is_mock: bool,
 }
 
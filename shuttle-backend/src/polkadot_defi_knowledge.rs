@@ -1,5 +1,9 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{info, warn};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PolkadotProtocol {
@@ -133,4 +137,160 @@ pub fn search_polkadot_protocols(query: &str) -> Vec<PolkadotProtocol> {
         })
         .cloned()
         .collect()
-}
\ No newline at end of file
+}
+
+/// Protocol snapshot served by `/polkadot/protocols`, kept fresh by
+/// `spawn_protocol_refresher`.
+#[derive(Clone)]
+pub struct ProtocolCache {
+    protocols: Arc<RwLock<HashMap<String, PolkadotProtocol>>>,
+}
+
+impl ProtocolCache {
+    /// Seeded with the built-in protocol data.
+    pub fn new() -> Self {
+        Self {
+            protocols: Arc::new(RwLock::new(get_polkadot_protocols())),
+        }
+    }
+
+    pub fn protocols(&self) -> HashMap<String, PolkadotProtocol> {
+        self.protocols.read().unwrap().clone()
+    }
+
+    /// Replace the snapshot with whatever `source` returns. On error the
+    /// previous snapshot is kept.
+    pub async fn refresh<F, Fut>(&self, source: &F) -> anyhow::Result<usize>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = anyhow::Result<HashMap<String, PolkadotProtocol>>>,
+    {
+        let protocols = source().await?;
+        let count = protocols.len();
+        *self.protocols.write().unwrap() = protocols;
+        Ok(count)
+    }
+}
+
+impl Default for ProtocolCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Load protocols from `POLKADOT_PROTOCOLS_URL` (a JSON object keyed by
+/// protocol id) when set, otherwise from the built-in data.
+pub async fn refresh_polkadot_protocols(client: &reqwest::Client) -> anyhow::Result<HashMap<String, PolkadotProtocol>> {
+    match std::env::var("POLKADOT_PROTOCOLS_URL") {
+        Ok(url) => {
+            let protocols = client
+                .get(&url)
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<HashMap<String, PolkadotProtocol>>()
+                .await?;
+            Ok(protocols)
+        }
+        Err(_) => Ok(get_polkadot_protocols()),
+    }
+}
+
+/// Refresh interval from `POLKADOT_PROTOCOL_REFRESH_SECS` (default hourly),
+/// or `None` when `POLKADOT_PROTOCOL_REFRESH_ENABLED=false`.
+pub fn protocol_refresh_interval() -> Option<Duration> {
+    let enabled = std::env::var("POLKADOT_PROTOCOL_REFRESH_ENABLED")
+        .map(|v| !v.eq_ignore_ascii_case("false"))
+        .unwrap_or(true);
+    if !enabled {
+        return None;
+    }
+
+    let secs = std::env::var("POLKADOT_PROTOCOL_REFRESH_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(3600);
+    Some(Duration::from_secs(secs))
+}
+
+/// Periodically refresh `cache` from `source`.
+pub fn spawn_protocol_refresher<F, Fut>(
+    cache: ProtocolCache,
+    interval: Duration,
+    source: F,
+) -> tokio::task::JoinHandle<()>
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = anyhow::Result<HashMap<String, PolkadotProtocol>>> + Send,
+{
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // The cache is already seeded, so skip the immediate first tick
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            match cache.refresh(&source).await {
+                Ok(count) => info!("Refreshed Polkadot protocol cache with {} protocols", count),
+                Err(e) => warn!("Failed to refresh Polkadot protocol cache, keeping previous snapshot: {}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn protocol(name: &str) -> PolkadotProtocol {
+        PolkadotProtocol {
+            name: name.to_string(),
+            liquid_staking_apr: None,
+            lending_yield_apr: None,
+            highlights: vec![],
+            category: "Test".to_string(),
+            risk_level: 3,
+            tvl_usd: None,
+            supported_assets: vec!["DOT".to_string()],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresher_updates_cache() {
+        let cache = ProtocolCache::new();
+        assert!(cache.protocols().contains_key("acala"));
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let handle = spawn_protocol_refresher(cache.clone(), Duration::from_millis(10), move || {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(HashMap::from([("moonwell".to_string(), protocol("Moonwell"))]))
+            }
+        });
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        handle.abort();
+
+        assert!(calls.load(Ordering::SeqCst) >= 1);
+        let protocols = cache.protocols();
+        assert_eq!(protocols.len(), 1);
+        assert_eq!(protocols["moonwell"].name, "Moonwell");
+    }
+
+    #[tokio::test]
+    async fn test_failed_refresh_keeps_previous_snapshot() {
+        let cache = ProtocolCache::new();
+        let before = cache.protocols().len();
+
+        let result = cache
+            .refresh(&|| async { Err(anyhow::anyhow!("source unavailable")) })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(cache.protocols().len(), before);
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+use tracing::{info, warn};
+
+/// Directory holding template files that override the built-in prompts
+pub const PROMPTS_DIR_ENV: &str = "PROMPTS_DIR";
+
+/// A prompt sent to the LLM, overridable by a `<name>.txt` file in
+/// `PROMPTS_DIR`. Placeholders are written `{name}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Prompt {
+    /// Sorts a DeFi request into an intent; `{input}`
+    Classification,
+    /// Asks for a DeFi strategy as JSON; `{request}`
+    Strategy,
+    /// Asks for a whole-portfolio plan; `{request}`
+    Portfolio,
+    /// Explains a Solidity to ink! migration question; `{question}`
+    Migration,
+    /// Lists search terms related to a question; `{question}`
+    QueryExpansion,
+    /// One-paragraph summary over matching examples; `{question}`
+    Summary,
+    /// DynaVest chat turn; `{context}`, `{history}` and `{question}`
+    Chat,
+    /// Folds old chat turns into a summary; `{summary}` and `{turns}`
+    ChatSummary,
+    /// Instruction placed before the question in every Gemini prompt
+    System,
+}
+
+impl Prompt {
+    pub const ALL: [Prompt; 9] = [
+        Prompt::Classification,
+        Prompt::Strategy,
+        Prompt::Portfolio,
+        Prompt::Migration,
+        Prompt::QueryExpansion,
+        Prompt::Summary,
+        Prompt::Chat,
+        Prompt::ChatSummary,
+        Prompt::System,
+    ];
+
+    /// File stem of the template overriding this prompt
+    pub fn name(self) -> &'static str {
+        match self {
+            Prompt::Classification => "classification",
+            Prompt::Strategy => "strategy",
+            Prompt::Portfolio => "portfolio",
+            Prompt::Migration => "migration",
+            Prompt::QueryExpansion => "query_expansion",
+            Prompt::Summary => "summary",
+            Prompt::Chat => "chat",
+            Prompt::ChatSummary => "chat_summary",
+            Prompt::System => "system",
+        }
+    }
+
+    pub fn default_template(self) -> &'static str {
+        match self {
+            Prompt::Classification => CLASSIFICATION_PROMPT,
+            Prompt::Strategy => STRATEGY_PROMPT,
+            Prompt::Portfolio => PORTFOLIO_PROMPT,
+            Prompt::Migration => MIGRATION_PROMPT,
+            Prompt::QueryExpansion => QUERY_EXPANSION_PROMPT,
+            Prompt::Summary => SUMMARY_PROMPT,
+            Prompt::Chat => CHAT_PROMPT,
+            Prompt::ChatSummary => CHAT_SUMMARY_PROMPT,
+            Prompt::System => crate::gemini_client::SYSTEM_PROMPT,
+        }
+    }
+}
+
+const CLASSIFICATION_PROMPT: &str = "Classify the following user input into one of these categories: strategies, analyze_portfolio, build_portfolio, question, price_check. \n            \n            Input: {input}\n            \n            Return only the category name.";
+
+const STRATEGY_PROMPT: &str = "Based on the user request: '{request}', generate a DeFi strategy. \n            \n            Return a JSON object with:\n            - name: strategy name\n            - risk_level: low, medium, or high\n            - chain: preferred blockchain (Polkadot, Ethereum, Base, etc.)\n            - parameters: detailed strategy parameters\n            - recommended_amount: suggested investment amount\n            - protocols: list of DeFi protocols to use\n            \n            Focus on Polkadot ecosystem when possible.";
+
+const PORTFOLIO_PROMPT: &str = "Based on the user request: '{request}', suggest a complete DeFi portfolio strategy.\n            \n            Return suggestions for:\n            - Asset allocation percentages\n            - Risk distribution (low/medium/high)\n            - Recommended protocols\n            - Diversification strategy\n            - Rebalancing schedule\n            \n            Focus on Polkadot ecosystem opportunities.";
+
+const MIGRATION_PROMPT: &str = "You are an expert in both Solidity and ink! smart contracts. The user is asking: '{question}'
+
+Please provide a detailed, step-by-step explanation based on the provided code examples. Focus on:
+
+1. **Key Differences**: Explain main conceptual differences between Solidity and ink!
+2. **Migration Steps**: Provide clear, actionable steps for converting patterns
+3. **Code Examples**: Show concrete before/after examples from the context
+4. **Best Practices**: Highlight important considerations and gotchas
+5. **Practical Guide**: Make it actionable for developers
+
+Format your response clearly with specific code snippets and explanations, not just raw code dumps.";
+
+const QUERY_EXPANSION_PROMPT: &str = "List up to five short technical terms related to this question about Solidity and ink! smart contracts, as a comma-separated list with no other text: {question}";
+
+const SUMMARY_PROMPT: &str = "{question}\n\nAnswer in a single short paragraph about ink! smart contracts, without code blocks.";
+
+const CHAT_PROMPT: &str = "You are DynaVest AI, a DeFi strategy advisor. Use the following context to answer questions about DeFi strategies, yield farming, and investment opportunities.\n\nContext:\n{context}\n\n{history}Question: {question}\n\nProvide helpful, accurate advice about DeFi strategies. Include relevant keywords and UI suggestions in your response.";
+
+const CHAT_SUMMARY_PROMPT: &str = "Summarize the following conversation between a user and DynaVest AI in a few sentences, keeping any facts, preferences and decisions the user stated.\n\nPrevious summary:\n{summary}\n\nNew turns:\n{turns}";
+
+/// Prompt templates: the built-in defaults, with any overrides from files.
+#[derive(Debug, Clone, Default)]
+pub struct PromptTemplates {
+    overrides: HashMap<Prompt, String>,
+}
+
+impl PromptTemplates {
+    /// Templates from `dir`, one `<name>.txt` per overridden prompt. A
+    /// missing file keeps the default; one trailing newline is dropped.
+    pub fn load(dir: &Path) -> Self {
+        let mut overrides = HashMap::new();
+        for prompt in Prompt::ALL {
+            let path = dir.join(format!("{}.txt", prompt.name()));
+            match std::fs::read_to_string(&path) {
+                Ok(template) => {
+                    let trimmed = template.strip_suffix("\r\n").or_else(|| template.strip_suffix('\n'));
+                    overrides.insert(prompt, trimmed.unwrap_or(&template).to_string());
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => warn!("Failed to read prompt template {}: {}", path.display(), e),
+            }
+        }
+        if !overrides.is_empty() {
+            info!("Loaded {} prompt template(s) from {}", overrides.len(), dir.display());
+        }
+        Self { overrides }
+    }
+
+    /// Process-wide templates, loaded once from `PROMPTS_DIR` if set.
+    pub fn shared() -> Arc<PromptTemplates> {
+        static TEMPLATES: OnceLock<Arc<PromptTemplates>> = OnceLock::new();
+        TEMPLATES
+            .get_or_init(|| {
+                let templates = match std::env::var(PROMPTS_DIR_ENV) {
+                    Ok(dir) => PromptTemplates::load(Path::new(&dir)),
+                    Err(_) => PromptTemplates::default(),
+                };
+                Arc::new(templates)
+            })
+            .clone()
+    }
+
+    pub fn template(&self, prompt: Prompt) -> &str {
+        self.overrides.get(&prompt).map(String::as_str).unwrap_or(prompt.default_template())
+    }
+
+    /// `prompt` with each `{name}` in `values` substituted. Substituted text
+    /// is not scanned again, and unknown placeholders are left as written.
+    pub fn render(&self, prompt: Prompt, values: &[(&str, &str)]) -> String {
+        let template = self.template(prompt);
+        let mut rendered = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(open) = rest.find('{') {
+            rendered.push_str(&rest[..open]);
+            let after = &rest[open + 1..];
+            let value = after
+                .find('}')
+                .and_then(|close| values.iter().find(|(name, _)| *name == &after[..close]).map(|(_, value)| (close, value)));
+            match value {
+                Some((close, value)) => {
+                    rendered.push_str(value);
+                    rest = &after[close + 1..];
+                }
+                None => {
+                    rendered.push('{');
+                    rest = after;
+                }
+            }
+        }
+        rendered.push_str(rest);
+        rendered
+    }
+}
+
+/// `prompt` rendered with the process-wide templates.
+pub fn render(prompt: Prompt, values: &[(&str, &str)]) -> String {
+    PromptTemplates::shared().render(prompt, values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_file_overrides_one_prompt() {
+        let dir = std::env::temp_dir().join(format!("prompts-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("classification.txt"), "Intent of {input}? One of: strategies, question.\n").unwrap();
+
+        let templates = PromptTemplates::load(&dir);
+        assert_eq!(
+            templates.render(Prompt::Classification, &[("input", "stake {DOT}")]),
+            "Intent of stake {DOT}? One of: strategies, question."
+        );
+        assert_eq!(templates.template(Prompt::Strategy), Prompt::Strategy.default_template());
+
+        let defaults = PromptTemplates::default();
+        assert!(defaults
+            .render(Prompt::Classification, &[("input", "stake DOT")])
+            .contains("Input: stake DOT\n"));
+        assert_eq!(
+            defaults.render(Prompt::ChatSummary, &[("summary", "(none)")]),
+            CHAT_SUMMARY_PROMPT.replace("{summary}", "(none)")
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,87 @@
+use qdrant_client::config::CompressionEncoding;
+use std::time::Duration;
+
+/// Connection settings for Qdrant clients built by the service.
+/// Defaults match `qdrant-client`'s own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QdrantConfig {
+    /// Per-request timeout; raise it for large upserts
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+    /// Send HTTP/2 keep-alive pings while the connection is idle
+    pub keep_alive_while_idle: bool,
+    /// gzip-compress gRPC messages
+    pub compression: bool,
+}
+
+impl Default for QdrantConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+            connect_timeout: Duration::from_secs(5),
+            keep_alive_while_idle: true,
+            compression: false,
+        }
+    }
+}
+
+impl QdrantConfig {
+    /// Read `QDRANT_TIMEOUT_SECS`, `QDRANT_CONNECT_TIMEOUT_SECS`,
+    /// `QDRANT_KEEP_ALIVE` and `QDRANT_COMPRESSION` (`gzip` to enable),
+    /// keeping the default for anything unset or unparseable.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let var = |name: &str| std::env::var(name).ok();
+        let secs = |name: &str, default: Duration| {
+            var(name).and_then(|v| v.parse().ok()).map(Duration::from_secs).unwrap_or(default)
+        };
+
+        Self {
+            timeout: secs("QDRANT_TIMEOUT_SECS", defaults.timeout),
+            connect_timeout: secs("QDRANT_CONNECT_TIMEOUT_SECS", defaults.connect_timeout),
+            keep_alive_while_idle: var("QDRANT_KEEP_ALIVE")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.keep_alive_while_idle),
+            compression: var("QDRANT_COMPRESSION").is_some_and(|v| v.eq_ignore_ascii_case("gzip")),
+        }
+    }
+
+    /// Apply these settings to a client builder from `Qdrant::from_url`.
+    pub fn apply(&self, mut builder: qdrant_client::config::QdrantConfig) -> qdrant_client::config::QdrantConfig {
+        builder.timeout = self.timeout;
+        builder.connect_timeout = self.connect_timeout;
+        builder.keep_alive_while_idle = self.keep_alive_while_idle;
+        builder.compression = self.compression.then_some(CompressionEncoding::Gzip);
+        builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qdrant_client::Qdrant;
+
+    #[test]
+    fn test_config_is_applied_to_client_builder() {
+        let config = QdrantConfig {
+            timeout: Duration::from_secs(120),
+            connect_timeout: Duration::from_secs(10),
+            keep_alive_while_idle: false,
+            compression: true,
+        };
+
+        let builder = config.apply(Qdrant::from_url("http://localhost:6334").api_key("secret"));
+        assert_eq!(builder.timeout, Duration::from_secs(120));
+        assert_eq!(builder.connect_timeout, Duration::from_secs(10));
+        assert!(!builder.keep_alive_while_idle);
+        assert!(matches!(builder.compression, Some(CompressionEncoding::Gzip)));
+        // Settings outside the config are left alone
+        assert_eq!(builder.uri, "http://localhost:6334");
+        assert_eq!(builder.api_key.as_deref(), Some("secret"));
+
+        let builder = QdrantConfig::default().apply(Qdrant::from_url("http://localhost:6334"));
+        assert!(builder.compression.is_none());
+        assert!(builder.keep_alive_while_idle);
+        assert!(builder.build().is_ok());
+    }
+}
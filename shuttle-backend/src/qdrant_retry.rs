@@ -0,0 +1,126 @@
+use qdrant_client::QdrantError;
+use std::future::Future;
+use tracing::warn;
+
+use crate::retry::RetryPolicy;
+
+/// gRPC codes worth retrying, by wire value: `DEADLINE_EXCEEDED` (4) and
+/// `UNAVAILABLE` (14), i.e. the server was briefly unreachable or slow.
+/// Anything else (e.g. `INVALID_ARGUMENT`) would fail the same way again.
+/// Comparing wire values keeps this independent of the tonic version
+/// qdrant-client is built against.
+const TRANSIENT_CODES: &[i32] = &[4, 14];
+
+/// Whether a gRPC status code, by wire value, marks a transient failure.
+pub fn is_transient_code(code: i32) -> bool {
+    TRANSIENT_CODES.contains(&code)
+}
+
+/// Whether a Qdrant call that failed with `error` may succeed if repeated.
+pub fn is_transient(error: &QdrantError) -> bool {
+    match error {
+        QdrantError::ResponseError { status } => is_transient_code(status.code().into()),
+        _ => false,
+    }
+}
+
+/// Run a Qdrant call, repeating it with backoff while it fails with a
+/// transient error, up to `policy.max_attempts` calls in total.
+pub async fn with_qdrant_retry<T, F, Fut>(policy: &RetryPolicy, operation: &str, call: F) -> Result<T, QdrantError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, QdrantError>>,
+{
+    retry_transient(policy, operation, is_transient, call).await
+}
+
+/// The retry loop behind `with_qdrant_retry`, with the transient check
+/// passed in.
+async fn retry_transient<T, E, F, Fut>(
+    policy: &RetryPolicy,
+    operation: &str,
+    transient: impl Fn(&E) -> bool,
+    mut call: F,
+) -> Result<T, E>
+where
+    E: std::fmt::Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+
+        match call().await {
+            Ok(value) => return Ok(value),
+            Err(e) if transient(&e) && attempt < policy.max_attempts => {
+                let delay = policy.delay_for(attempt, None);
+                warn!(
+                    "Qdrant {} failed (attempt {}/{}): {}, retrying in {:?}",
+                    operation, attempt, policy.max_attempts, e, delay
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    const UNAVAILABLE: i32 = 14;
+    const DEADLINE_EXCEEDED: i32 = 4;
+    const INVALID_ARGUMENT: i32 = 3;
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+        }
+    }
+
+    /// A store that answers with each of `responses` in turn, counting
+    /// calls; failures carry a gRPC code.
+    async fn mock_store(calls: &AtomicU32, responses: &[Result<&'static str, i32>]) -> Result<&'static str, i32> {
+        let call = calls.fetch_add(1, Ordering::SeqCst) as usize;
+        responses[call.min(responses.len() - 1)]
+    }
+
+    async fn retry_mock(calls: &AtomicU32, responses: &[Result<&'static str, i32>]) -> Result<&'static str, i32> {
+        retry_transient(&fast_policy(), "upsert", |code: &i32| is_transient_code(*code), || mock_store(calls, responses)).await
+    }
+
+    #[tokio::test]
+    async fn test_transient_failure_then_success_is_retried() {
+        let calls = AtomicU32::new(0);
+        let responses = [Err(UNAVAILABLE), Err(DEADLINE_EXCEEDED), Ok("upserted")];
+
+        assert_eq!(retry_mock(&calls, &responses).await.unwrap(), "upserted");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_argument_fails_fast() {
+        let calls = AtomicU32::new(0);
+        let responses = [Err(INVALID_ARGUMENT), Ok("upserted")];
+
+        assert_eq!(retry_mock(&calls, &responses).await.unwrap_err(), INVALID_ARGUMENT);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(!is_transient(&QdrantError::ConversionError("bad vector".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_attempts() {
+        let calls = AtomicU32::new(0);
+        let responses = [Err(UNAVAILABLE)];
+
+        assert_eq!(retry_mock(&calls, &responses).await.unwrap_err(), UNAVAILABLE);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+}
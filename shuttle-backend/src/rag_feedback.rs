@@ -0,0 +1,199 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::analytics::question_contract_type;
+
+/// Pattern for feedback on questions that name no known contract type
+const OTHER_PATTERN: &str = "other";
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RagFeedbackRequest {
+    pub query: String,
+    /// Id of the answer being rated, as returned to the client
+    pub answer_id: String,
+    pub helpful: bool,
+    pub comment: Option<String>,
+}
+
+/// One rating of a RAG answer.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct RagFeedback {
+    pub id: Uuid,
+    pub query: String,
+    pub answer_id: String,
+    pub helpful: bool,
+    pub comment: Option<String>,
+    /// Contract type the query mentions, e.g. `ERC20`, or `other`
+    pub query_pattern: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl RagFeedback {
+    pub fn new(request: RagFeedbackRequest) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            query_pattern: question_contract_type(&request.query).unwrap_or(OTHER_PATTERN).to_string(),
+            query: request.query,
+            answer_id: request.answer_id,
+            helpful: request.helpful,
+            comment: request.comment.filter(|c| !c.trim().is_empty()),
+            created_at: Utc::now(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct FeedbackCounts {
+    pub helpful: i64,
+    pub unhelpful: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct FeedbackSummary {
+    pub total: i64,
+    /// Fraction rated helpful, 0.0 when there is no feedback
+    pub helpful_rate: f64,
+    /// Counts keyed by query pattern
+    pub by_pattern: BTreeMap<String, FeedbackCounts>,
+}
+
+/// Answer ratings in the `rag_feedback` table.
+#[derive(Debug, Clone)]
+pub struct FeedbackLog {
+    db: PgPool,
+}
+
+impl FeedbackLog {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    pub async fn migrate(db: &PgPool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS rag_feedback (
+                id UUID PRIMARY KEY,
+                query TEXT NOT NULL,
+                answer_id TEXT NOT NULL,
+                helpful BOOLEAN NOT NULL,
+                comment TEXT,
+                query_pattern TEXT NOT NULL,
+                created_at TIMESTAMP WITH TIME ZONE NOT NULL
+            )
+            "#,
+        )
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn record(&self, feedback: &RagFeedback) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO rag_feedback (id, query, answer_id, helpful, comment, query_pattern, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(feedback.id)
+        .bind(&feedback.query)
+        .bind(&feedback.answer_id)
+        .bind(feedback.helpful)
+        .bind(&feedback.comment)
+        .bind(&feedback.query_pattern)
+        .bind(feedback.created_at)
+        .execute(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn summary(&self) -> Result<FeedbackSummary, sqlx::Error> {
+        let rows: Vec<(String, i64, i64)> = sqlx::query_as(
+            r#"
+            SELECT query_pattern, COUNT(*) FILTER (WHERE helpful), COUNT(*) FILTER (WHERE NOT helpful)
+            FROM rag_feedback
+            GROUP BY query_pattern
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(summarize(rows))
+    }
+}
+
+/// Build the summary from `(pattern, helpful, unhelpful)` rows.
+fn summarize(rows: Vec<(String, i64, i64)>) -> FeedbackSummary {
+    let mut summary = FeedbackSummary::default();
+    let mut helpful = 0;
+    for (pattern, ok, not_ok) in rows {
+        summary.total += ok + not_ok;
+        helpful += ok;
+        let counts = summary.by_pattern.entry(pattern).or_default();
+        counts.helpful += ok;
+        counts.unhelpful += not_ok;
+    }
+    if summary.total > 0 {
+        summary.helpful_rate = helpful as f64 / summary.total as f64;
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(query: &str, helpful: bool) -> RagFeedbackRequest {
+        RagFeedbackRequest {
+            query: query.to_string(),
+            answer_id: "answer-1".to_string(),
+            helpful,
+            comment: None,
+        }
+    }
+
+    #[test]
+    fn test_feedback_is_grouped_by_contract_type() {
+        assert_eq!(RagFeedback::new(request("How do I port an ERC20 approve?", true)).query_pattern, "ERC20");
+        assert_eq!(RagFeedback::new(request("what is ink!?", false)).query_pattern, "other");
+
+        let summary = summarize(vec![("ERC20".to_string(), 3, 1), ("other".to_string(), 0, 2)]);
+        assert_eq!(summary.total, 6);
+        assert_eq!(summary.helpful_rate, 0.5);
+        assert_eq!(summary.by_pattern["ERC20"], FeedbackCounts { helpful: 3, unhelpful: 1 });
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a scratch Postgres database at TEST_DATABASE_URL"]
+    async fn test_feedback_is_persisted_and_summarized() {
+        let database_url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL");
+        let db = PgPool::connect(&database_url).await.unwrap();
+        FeedbackLog::migrate(&db).await.unwrap();
+        let log = FeedbackLog::new(db.clone());
+        let before = log.summary().await.unwrap().by_pattern.remove("MultiSig").unwrap_or_default();
+
+        let helpful = RagFeedback::new(RagFeedbackRequest {
+            comment: Some("Clear example".to_string()),
+            ..request("Porting a multisig confirmTransaction", true)
+        });
+        log.record(&helpful).await.unwrap();
+        log.record(&RagFeedback::new(request("multisig owners storage?", false))).await.unwrap();
+
+        let row: RagFeedback = sqlx::query_as("SELECT * FROM rag_feedback WHERE id = $1")
+            .bind(helpful.id)
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        assert_eq!(row.comment.as_deref(), Some("Clear example"));
+        assert_eq!(row.query_pattern, "MultiSig");
+
+        let after = log.summary().await.unwrap().by_pattern["MultiSig"].clone();
+        assert_eq!(after.helpful - before.helpful, 1);
+        assert_eq!(after.unhelpful - before.unhelpful, 1);
+    }
+}
@@ -1,17 +1,27 @@
 use qdrant_client::Qdrant;
 use qdrant_client::qdrant::{
-    CreateCollectionBuilder, Distance, PointStruct, SearchPointsBuilder, VectorParamsBuilder,
-    UpsertPointsBuilder,
+    point_id::PointIdOptions, vector_output::Vector, vectors_config::Config as VectorsConfigKind, CollectionInfo, Condition, CountPointsBuilder, CreateCollectionBuilder, DeletePointsBuilder, Distance, Filter, PointId,
+    PointStruct, PointsIdsList, RetrievedPoint, ScrollPointsBuilder,
+    SearchPointsBuilder, VectorParamsBuilder, VectorsOutput, UpsertPointsBuilder,
 };
 use qdrant_client::Payload;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use tracing::{info, error};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use tracing::{info, error, warn};
 use anyhow::Result;
-use uuid::Uuid;
 use utoipa::ToSchema;
 
-use crate::gemini_client::GeminiClient;
+use crate::concurrency::{map_concurrent, max_concurrency_from_env};
+use crate::dead_letter::{document_source_id, DeadLetterLog};
+use crate::embedder::{Embedder, EmbedderStatus, EMBEDDING_DIMENSIONS};
+use crate::gemini_client::{GeminiClient, ResponseChunks};
+use crate::id_generator::{IdGenerator, UuidV4Generator};
+use crate::prompts::{render, Prompt};
+use crate::qdrant_retry::with_qdrant_retry;
+use crate::retry::RetryPolicy;
+use crate::single_flight::{normalize_query, SingleFlight};
+use crate::tenant;
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct EmbeddingRequest {
@@ -23,27 +33,554 @@ pub struct SearchRequest {
     pub query: String,
     pub limit: u64,
     pub score_threshold: Option<f32>,
+    /// Payload values results must all have, e.g. `{"contract_type": "ERC721"}`.
+    /// Only semantic search applies it.
+    pub filter: Option<HashMap<String, String>>,
 }
 
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SearchResult {
+    pub id: String,
     pub content: String,
     pub score: f32,
     pub metadata: HashMap<String, String>,
 }
 
+/// Points per scroll page when exporting, and per upsert when importing
+pub const EXPORT_PAGE_SIZE: u32 = 256;
+
+/// One point of the regular collection, as written by `export_documents`
+/// and read back by `import_documents`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct ExportedPoint {
+    pub id: String,
+    pub content: String,
+    /// Every other string payload field, including `timestamp`
+    pub metadata: HashMap<String, String>,
+    pub vector: Vec<f32>,
+}
+
+impl ExportedPoint {
+    fn from_point(point: RetrievedPoint) -> Self {
+        let mut content = String::new();
+        let mut metadata = HashMap::new();
+        for (key, value) in point.payload {
+            if let Some(value) = value.as_str() {
+                if key == "content" {
+                    content = value.to_string();
+                } else {
+                    metadata.insert(key, value.to_string());
+                }
+            }
+        }
+
+        let vector = dense_vector(point.vectors);
+
+        Self {
+            id: point.id.as_ref().map(point_id_to_string).unwrap_or_default(),
+            content,
+            metadata,
+            vector,
+        }
+    }
+
+    fn to_point(&self) -> Result<PointStruct> {
+        let mut payload = serde_json::json!({ "content": self.content });
+        for (key, value) in &self.metadata {
+            payload[key] = serde_json::Value::String(value.clone());
+        }
+
+        Ok(PointStruct::new(self.point_id(), self.vector.clone(), Payload::try_from(payload)?))
+    }
+
+    /// Ids keep their kind, so re-imported points replace rather than duplicate
+    fn point_id(&self) -> PointId {
+        match self.id.parse::<u64>() {
+            Ok(num) => PointId::from(num),
+            Err(_) => PointId::from(self.id.clone()),
+        }
+    }
+}
+
+/// Returned (inside `anyhow::Error`) by `import_documents` when a tenant's
+/// import would overwrite a point it doesn't own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForeignPoints {
+    pub count: u64,
+}
+
+impl std::fmt::Display for ForeignPoints {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} imported point ids belong to documents outside this tenant", self.count)
+    }
+}
+
+impl std::error::Error for ForeignPoints {}
+
+/// Parse an export, one `ExportedPoint` per non-blank line. The error names
+/// the first bad line.
+pub fn parse_export(jsonl: &str) -> Result<Vec<ExportedPoint>, String> {
+    let mut points = Vec::new();
+    for (index, line) in jsonl.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let point: ExportedPoint = serde_json::from_str(line).map_err(|e| format!("Line {}: {}", index + 1, e))?;
+        if point.vector.is_empty() {
+            return Err(format!("Line {}: point {} has no vector", index + 1, point.id));
+        }
+        points.push(point);
+    }
+    Ok(points)
+}
+
+/// A source document whose content went into an answer's context.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct Citation {
+    pub file_path: String,
+    pub score: f32,
+}
+
+/// A prose answer together with the documents it was built from.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CitedAnswer {
+    pub answer: String,
+    /// One entry per context document with a `file_path`, best first
+    pub citations: Vec<Citation>,
+    /// Written by the LLM, rather than a fallback or the no-documents reply
+    #[serde(skip)]
+    pub generated: bool,
+}
+
+/// What a RAG query would send to the LLM, for tuning retrieval.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RagExplanation {
+    pub query: String,
+    /// Retrieved documents with their similarity scores, best first
+    pub documents: Vec<SearchResult>,
+    /// Context entries built from the top documents that fit the token budget
+    pub context: Vec<String>,
+    /// The final prompt string, including the system prompt
+    pub prompt: String,
+    /// False when nothing was retrieved; the query is then answered with a fixed message
+    pub would_call_llm: bool,
+}
+
+/// Point counts in the regular collection grouped by metadata field.
+/// Points without the field are counted under "unknown".
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct CollectionBreakdown {
+    pub total: u64,
+    pub by_contract_type: BTreeMap<String, u64>,
+    pub by_language: BTreeMap<String, u64>,
+    pub by_source: BTreeMap<String, u64>,
+}
+
+impl CollectionBreakdown {
+    fn add<'a>(&mut self, field: impl Fn(&str) -> Option<&'a str>) {
+        self.total += 1;
+        for (key, counts) in [
+            ("contract_type", &mut self.by_contract_type),
+            ("language", &mut self.by_language),
+            ("source", &mut self.by_source),
+        ] {
+            let value = field(key).unwrap_or("unknown").to_string();
+            *counts.entry(value).or_insert(0) += 1;
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct CacheEntry {
     pub query: String,
     pub answer: String,
     pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
+/// What `generate_rag_response` returns when the LLM call fails
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RagFallback {
+    /// Retrieved context concatenated verbatim
+    RawContext,
+    /// Titled code examples with a short lead-in
+    Templated,
+    /// Propagate the LLM error to the caller
+    Error,
+}
+
+impl RagFallback {
+    /// Read from `RAG_LLM_FALLBACK` (`raw`, `templated` or `error`),
+    /// defaulting to `templated`.
+    pub fn from_env() -> Self {
+        match std::env::var("RAG_LLM_FALLBACK").unwrap_or_default().to_lowercase().as_str() {
+            "raw" => RagFallback::RawContext,
+            "error" => RagFallback::Error,
+            _ => RagFallback::Templated,
+        }
+    }
+}
+
+/// How `generate_rag_response` broadens short queries before searching
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QueryExpansion {
+    Off,
+    /// Append related terms from a built-in synonym table
+    Synonyms,
+    /// Ask the LLM for related terms, falling back to the synonym table
+    Llm,
+}
+
+impl QueryExpansion {
+    /// Read from `RAG_QUERY_EXPANSION` (`off`, `synonyms` or `llm`),
+    /// defaulting to `off`.
+    pub fn from_env() -> Self {
+        match std::env::var("RAG_QUERY_EXPANSION").unwrap_or_default().to_lowercase().as_str() {
+            "synonyms" => QueryExpansion::Synonyms,
+            "llm" => QueryExpansion::Llm,
+            _ => QueryExpansion::Off,
+        }
+    }
+}
+
+/// How embeddings are produced and compared. `normalize` should match the
+/// collections' `distance`: cosine expects unit vectors, while dot product
+/// and Euclidean distance use magnitudes as they are.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EmbeddingConfig {
+    /// Scale every embedding to unit length
+    pub normalize: bool,
+    /// Distance metric new collections are created with
+    pub distance: Distance,
+}
+
+impl Default for EmbeddingConfig {
+    fn default() -> Self {
+        Self {
+            normalize: true,
+            distance: Distance::Cosine,
+        }
+    }
+}
+
+impl EmbeddingConfig {
+    /// Read `RAG_EMBEDDING_NORMALIZE` (`true`/`false`) and `RAG_VECTOR_DISTANCE`
+    /// (`cosine`, `dot` or `euclid`), defaulting to normalized cosine.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let normalize = std::env::var("RAG_EMBEDDING_NORMALIZE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.normalize);
+        let distance = match std::env::var("RAG_VECTOR_DISTANCE").unwrap_or_default().to_lowercase().as_str() {
+            "dot" => Distance::Dot,
+            "euclid" => Distance::Euclid,
+            _ => defaults.distance,
+        };
+        Self { normalize, distance }
+    }
+
+    /// A warning when embeddings aren't normalized but the metric assumes they are.
+    pub fn mismatch_warning(&self) -> Option<String> {
+        (self.distance == Distance::Cosine && !self.normalize).then(|| {
+            "Embedding normalization is off but collections use cosine distance; set RAG_EMBEDDING_NORMALIZE=true or RAG_VECTOR_DISTANCE=dot".to_string()
+        })
+    }
+}
+
+/// What startup does when a collection's stored vectors don't have the
+/// dimension the embedder produces.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DimensionCheck {
+    /// Refuse to start
+    Strict,
+    /// Log the mismatch and how to fix it, then carry on
+    Warn,
+}
+
+impl DimensionCheck {
+    /// Read `RAG_DIMENSION_CHECK` (`strict` or `warn`), defaulting to `warn`.
+    pub fn from_env() -> Self {
+        match std::env::var("RAG_DIMENSION_CHECK").unwrap_or_default().to_lowercase().as_str() {
+            "strict" => DimensionCheck::Strict,
+            _ => DimensionCheck::Warn,
+        }
+    }
+}
+
+/// A collection whose vectors are a different size than new embeddings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DimensionDrift {
+    pub collection: String,
+    /// Dimension the active embedder produces
+    pub expected: u64,
+    /// Dimension the collection was created with
+    pub actual: u64,
+}
+
+impl std::fmt::Display for DimensionDrift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Collection '{}' stores {}-dimensional vectors but the embedder produces {}; searches against it will fail. \
+             Recreate the collection and re-embed its documents (POST /rag/reindex), or switch back to the embedding model it was built with",
+            self.collection, self.actual, self.expected
+        )
+    }
+}
+
+/// Vector size a collection was created with, if it has a single unnamed vector.
+pub fn collection_vector_size(info: &CollectionInfo) -> Option<u64> {
+    let vectors = info.config.as_ref()?.params.as_ref()?.vectors_config.as_ref()?;
+    match vectors.config.as_ref()? {
+        VectorsConfigKind::Params(params) => Some(params.size),
+        _ => None,
+    }
+}
+
+/// Size limit `add_document` applies before embedding, so oversized text
+/// fails clearly instead of blowing a model's token limit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DocumentLimit {
+    Unlimited,
+    /// Refuse documents longer than this many characters
+    Reject(usize),
+    /// Split longer documents into chunks of at most this many characters
+    Chunk(usize),
+}
+
+impl DocumentLimit {
+    /// Read the limit from `RAG_MAX_DOCUMENT_CHARS` (unset means unlimited);
+    /// oversized documents are rejected unless `RAG_CHUNK_OVERSIZED_DOCUMENTS=true`.
+    pub fn from_env() -> Self {
+        let max_chars = std::env::var("RAG_MAX_DOCUMENT_CHARS")
+            .ok()
+            .and_then(|v| v.trim().parse::<usize>().ok())
+            .filter(|max| *max > 0);
+        let chunk = std::env::var("RAG_CHUNK_OVERSIZED_DOCUMENTS")
+            .map(|v| v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+
+        match max_chars {
+            None => DocumentLimit::Unlimited,
+            Some(max) if chunk => DocumentLimit::Chunk(max),
+            Some(max) => DocumentLimit::Reject(max),
+        }
+    }
+
+    /// The pieces to embed for `text`: the text itself when it fits, its
+    /// chunks in `Chunk` mode, or `DocumentTooLarge` in `Reject` mode.
+    pub fn apply(&self, text: &str) -> Result<Vec<String>> {
+        let chars = text.chars().count();
+        match *self {
+            DocumentLimit::Reject(max_chars) if chars > max_chars => Err(DocumentTooLarge { chars, max_chars }.into()),
+            DocumentLimit::Chunk(max_chars) if chars > max_chars => Ok(chunk_text(text, max_chars)),
+            _ => Ok(vec![text.to_string()]),
+        }
+    }
+}
+
+/// Returned (inside `anyhow::Error`) by `add_document` for text over a `Reject` limit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentTooLarge {
+    pub chars: usize,
+    pub max_chars: usize,
+}
+
+impl std::fmt::Display for DocumentTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Document is {} characters; the limit is {}", self.chars, self.max_chars)
+    }
+}
+
+impl std::error::Error for DocumentTooLarge {}
+
+/// Split `text` into pieces of at most `max_chars` characters, breaking after
+/// the last newline in each window when there is one.
+fn chunk_text(text: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut rest = text;
+    while rest.chars().count() > max_chars {
+        let limit = rest.char_indices().nth(max_chars).map(|(i, _)| i).unwrap_or(rest.len());
+        let split = rest[..limit].rfind('\n').map(|i| i + 1).unwrap_or(limit);
+        chunks.push(rest[..split].to_string());
+        rest = &rest[split..];
+    }
+    if !rest.is_empty() {
+        chunks.push(rest.to_string());
+    }
+    chunks
+}
+
+/// How many of the top retrieved documents go into an answer's context
+const CONTEXT_DOCUMENTS: usize = 5;
+
+/// Retrieval fetches this many times the requested documents, so some are
+/// left after near-duplicates are removed
+const RERANK_OVERFETCH: u64 = 2;
+
+/// How retrieved documents are reranked before the top ones become context.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RerankConfig {
+    /// Documents whose embeddings are at least this cosine-similar to a
+    /// higher-ranked one are dropped as duplicates
+    pub duplicate_threshold: f32,
+    /// With a value, order by maximal marginal relevance: `lambda * score -
+    /// (1 - lambda) * similarity to the closest document already chosen`.
+    /// 1.0 is pure relevance
+    pub mmr_lambda: Option<f32>,
+}
+
+impl Default for RerankConfig {
+    fn default() -> Self {
+        Self {
+            duplicate_threshold: 0.95,
+            mmr_lambda: None,
+        }
+    }
+}
+
+impl RerankConfig {
+    /// Read `RAG_DUPLICATE_THRESHOLD` and `RAG_MMR_LAMBDA` (0.0 to 1.0; MMR
+    /// is off when unset).
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let read = |name: &str| std::env::var(name).ok().and_then(|v| v.trim().parse::<f32>().ok());
+        Self {
+            duplicate_threshold: read("RAG_DUPLICATE_THRESHOLD").unwrap_or(defaults.duplicate_threshold),
+            mmr_lambda: read("RAG_MMR_LAMBDA").filter(|lambda| (0.0..=1.0).contains(lambda)),
+        }
+    }
+}
+
+/// Cosine similarity of two embeddings, 0.0 when either is all zeros.
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let (norm_a, norm_b) = (norm(a), norm(b));
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+/// Default budget, in estimated tokens, for the whole prompt sent to the LLM
+pub const DEFAULT_MAX_CONTEXT_TOKENS: usize = 8_000;
+
+/// Read the prompt budget from `RAG_MAX_CONTEXT_TOKENS`, defaulting to
+/// `DEFAULT_MAX_CONTEXT_TOKENS`.
+pub fn max_context_tokens_from_env() -> usize {
+    std::env::var("RAG_MAX_CONTEXT_TOKENS")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .filter(|max| *max > 0)
+        .unwrap_or(DEFAULT_MAX_CONTEXT_TOKENS)
+}
+
+/// Rough token count for `text`, at about four characters per token.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// How many code examples a structured response shows and how relevant
+/// they must be.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExampleRetrieval {
+    /// Most examples to return
+    pub count: usize,
+    /// Search results scoring below this are not shown as examples
+    pub min_score: f32,
+    /// With fewer relevant examples than this, the help text suggests
+    /// broadening the query
+    pub min_relevant: usize,
+}
+
+impl Default for ExampleRetrieval {
+    fn default() -> Self {
+        Self {
+            count: 3,
+            min_score: 0.3,
+            min_relevant: 2,
+        }
+    }
+}
+
+impl ExampleRetrieval {
+    /// Read `RAG_EXAMPLE_COUNT`, `RAG_EXAMPLE_MIN_SCORE` and
+    /// `RAG_MIN_RELEVANT_EXAMPLES`, keeping the default for any unset or
+    /// invalid value.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let read = |name: &str| std::env::var(name).ok().map(|v| v.trim().to_string());
+        Self {
+            count: read("RAG_EXAMPLE_COUNT")
+                .and_then(|v| v.parse().ok())
+                .filter(|count| *count > 0)
+                .unwrap_or(defaults.count),
+            min_score: read("RAG_EXAMPLE_MIN_SCORE")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.min_score),
+            min_relevant: read("RAG_MIN_RELEVANT_EXAMPLES")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.min_relevant),
+        }
+    }
+}
+
+/// Default cosine similarity a cached query must reach to be reused
+pub const DEFAULT_CACHE_THRESHOLD: f32 = 0.95;
+
+/// Read the cache-hit threshold from `RAG_CACHE_THRESHOLD`, defaulting to
+/// `DEFAULT_CACHE_THRESHOLD`. Range checking happens in `with_cache_threshold`.
+pub fn cache_threshold_from_env() -> f32 {
+    std::env::var("RAG_CACHE_THRESHOLD")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_CACHE_THRESHOLD)
+}
+
+/// Qdrant filter requiring every `metadata` key/value and, with a tenant,
+/// restricting to points that tenant may see. `None` when nothing is filtered.
+pub fn payload_filter(tenant: Option<&str>, metadata: &HashMap<String, String>) -> Option<Filter> {
+    let mut metadata: Vec<(&String, &String)> = metadata.iter().collect();
+    metadata.sort();
+    let mut conditions: Vec<Condition> = metadata
+        .into_iter()
+        .map(|(key, value)| Condition::matches(key.as_str(), value.clone()))
+        .collect();
+    if let Some(tenant) = tenant {
+        conditions.push(tenant::visible_to(tenant).into());
+    }
+    (!conditions.is_empty()).then(|| Filter::must(conditions))
+}
+
 pub struct RAGSystem {
     qdrant_client: Qdrant,
     gemini_client: GeminiClient,
     regular_collection: String,
     cache_collection: String,
+    fallback: RagFallback,
+    query_expansion: QueryExpansion,
+    cache_threshold: f32,
+    id_generator: std::sync::Arc<dyn IdGenerator>,
+    dead_letters: Option<DeadLetterLog>,
+    document_limit: DocumentLimit,
+    embedding: EmbeddingConfig,
+    embedder: Embedder,
+    embedder_status: EmbedderStatus,
+    /// Answers being generated, so identical concurrent queries share one LLM call
+    in_flight: SingleFlight<Result<CitedAnswer, String>>,
+    /// How many documents `bulk_insert_documents` embeds at once
+    max_concurrency: usize,
+    /// Backoff for Qdrant calls that fail with a transient gRPC status
+    qdrant_retry: RetryPolicy,
+    /// Estimated tokens the assembled prompt may use; lower-scoring
+    /// documents are left out of the context to stay within it
+    max_context_tokens: usize,
+    /// Example count and relevance for `generate_structured_response`
+    example_retrieval: ExampleRetrieval,
+    /// Duplicate removal and diversity for RAG context
+    rerank: RerankConfig,
 }
 
 impl RAGSystem {
@@ -55,12 +592,143 @@ impl RAGSystem {
             gemini_client,
             regular_collection: "code_knowledge".to_string(),
             cache_collection: "code_knowledge_cache".to_string(),
+            fallback: RagFallback::from_env(),
+            query_expansion: QueryExpansion::from_env(),
+            cache_threshold: DEFAULT_CACHE_THRESHOLD,
+            id_generator: std::sync::Arc::new(UuidV4Generator),
+            dead_letters: None,
+            document_limit: DocumentLimit::from_env(),
+            embedding: EmbeddingConfig::from_env(),
+            embedder: Embedder::Hash,
+            embedder_status: EmbedderStatus::default(),
+            in_flight: SingleFlight::new(),
+            max_concurrency: max_concurrency_from_env(),
+            qdrant_retry: RetryPolicy::default(),
+            max_context_tokens: max_context_tokens_from_env(),
+            example_retrieval: ExampleRetrieval::from_env(),
+            rerank: RerankConfig::from_env(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn with_rerank(mut self, rerank: RerankConfig) -> Self {
+        self.rerank = rerank;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_example_retrieval(mut self, example_retrieval: ExampleRetrieval) -> Self {
+        self.example_retrieval = example_retrieval;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_max_context_tokens(mut self, max_context_tokens: usize) -> Self {
+        self.max_context_tokens = max_context_tokens;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_qdrant_retry(mut self, qdrant_retry: RetryPolicy) -> Self {
+        self.qdrant_retry = qdrant_retry;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Embed with `embedder`, as picked by `embedder::load_chain`.
+    pub fn with_embedder(mut self, embedder: Embedder, status: EmbedderStatus) -> Self {
+        self.embedder = embedder;
+        self.embedder_status = status;
+        self
+    }
+
+    /// Run the embedder's warm-up and record its duration in the status.
+    pub async fn warm_up_embedder(&mut self) -> Result<()> {
+        let duration = self.embedder.warm_up().await?;
+        info!("Embedder {} warmed up in {:?}", self.embedder.name(), duration);
+        self.embedder_status.record_warm_up(duration);
+        Ok(())
+    }
+
+    pub fn embedder_status(&self) -> &EmbedderStatus {
+        &self.embedder_status
+    }
+
+    #[allow(dead_code)]
+    pub fn with_embedding_config(mut self, embedding: EmbeddingConfig) -> Self {
+        self.embedding = embedding;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_document_limit(mut self, document_limit: DocumentLimit) -> Self {
+        self.document_limit = document_limit;
+        self
+    }
+
+    /// Record documents that fail to embed in `bulk_insert_documents`.
+    pub fn with_dead_letters(mut self, dead_letters: DeadLetterLog) -> Self {
+        self.dead_letters = Some(dead_letters);
+        self
+    }
+
+    /// Replace the generator used for document and cache entry ids.
+    #[allow(dead_code)]
+    pub fn with_id_generator(mut self, id_generator: std::sync::Arc<dyn IdGenerator>) -> Self {
+        self.id_generator = id_generator;
+        self
+    }
+
+    /// Set the cosine similarity needed for a cache hit, e.g. 0.90 for
+    /// aggressive caching or 0.98 for strict. Must be in (0.0, 1.0].
+    pub fn with_cache_threshold(mut self, threshold: f32) -> Result<Self> {
+        if !(threshold > 0.0 && threshold <= 1.0) {
+            return Err(anyhow::anyhow!(
+                "Cache threshold must be in (0.0, 1.0], got {}",
+                threshold
+            ));
+        }
+        self.cache_threshold = threshold;
+        Ok(self)
+    }
+
+    fn is_cache_hit(&self, score: f32) -> bool {
+        similarity(self.embedding.distance, score) >= self.cache_threshold
+    }
+
+    /// Compare the vector size of each existing collection with the
+    /// embedder's. Missing collections are skipped.
+    pub async fn check_collection_dimensions(&self) -> Result<Vec<DimensionDrift>> {
+        let collections = self.qdrant_client.list_collections().await?;
+        let mut drift = Vec::new();
+        for collection in [&self.regular_collection, &self.cache_collection] {
+            if !collections.collections.iter().any(|c| &c.name == collection) {
+                continue;
+            }
+            let info = self.qdrant_client.collection_info(collection.as_str()).await?;
+            let actual = info.result.as_ref().and_then(collection_vector_size);
+            if let Some(actual) = actual.filter(|&size| size != EMBEDDING_DIMENSIONS as u64) {
+                drift.push(DimensionDrift {
+                    collection: collection.clone(),
+                    expected: EMBEDDING_DIMENSIONS as u64,
+                    actual,
+                });
+            }
         }
+        Ok(drift)
     }
 
     /// Initialize both regular and cache collections
     pub async fn initialize_collections(&self) -> Result<()> {
         info!("Initializing RAG system collections...");
+        if let Some(warning) = self.embedding.mismatch_warning() {
+            warn!("{}", warning);
+        }
         
         // Initialize regular collection
         self.create_regular_collection().await?;
@@ -85,12 +753,12 @@ impl RAGSystem {
             self.qdrant_client.delete_collection(&self.regular_collection).await?;
         }
 
-        info!("Creating regular collection with 384 dimensions: {}", self.regular_collection);
+        info!("Creating regular collection with {} dimensions: {}", EMBEDDING_DIMENSIONS, self.regular_collection);
         
         self.qdrant_client
             .create_collection(
                 CreateCollectionBuilder::new(&self.regular_collection)
-                    .vectors_config(VectorParamsBuilder::new(384, Distance::Cosine))
+                    .vectors_config(VectorParamsBuilder::new(EMBEDDING_DIMENSIONS as u64, self.embedding.distance))
             )
             .await?;
 
@@ -110,57 +778,64 @@ impl RAGSystem {
             self.qdrant_client.delete_collection(&self.cache_collection).await?;
         }
 
-        info!("Creating cache collection with 384 dimensions: {}", self.cache_collection);
+        info!("Creating cache collection with {} dimensions: {}", EMBEDDING_DIMENSIONS, self.cache_collection);
         
         self.qdrant_client
             .create_collection(
                 CreateCollectionBuilder::new(&self.cache_collection)
-                    .vectors_config(VectorParamsBuilder::new(384, Distance::Euclid))
+                    .vectors_config(VectorParamsBuilder::new(EMBEDDING_DIMENSIONS as u64, self.embedding.distance))
             )
             .await?;
 
         Ok(())
     }
 
-    /// Generate embeddings for text using sentence-transformers (Python) via HTTP
+    /// Generate embeddings for text with the active embedder
     pub async fn embed_text(&self, text: &str) -> Result<Vec<f32>> {
-        // For now, create a simple embedding using the same model as Python
-        // This is a temporary solution - in production you'd want to:
-        // 1. Run a separate embedding service
-        // 2. Use ONNX runtime for sentence-transformers in Rust
-        // 3. Or call a Python microservice
-        
-        // Simple hash-based embedding for demo (384 dimensions to match Python model)
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-        
-        let mut hasher = DefaultHasher::new();
-        text.hash(&mut hasher);
-        let hash = hasher.finish();
-        
-        // Create a deterministic but pseudo-random embedding
-        let mut embedding = Vec::with_capacity(384);
-        let mut seed = hash;
-        for _ in 0..384 {
-            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
-            embedding.push((seed as f32 / u64::MAX as f32) * 2.0 - 1.0);
+        self.embedder.embed(text, self.embedding.normalize).await
+    }
+
+    /// Embed a user query in its `normalize_query` form, so queries that
+    /// differ only in case, spacing or trailing punctuation search alike
+    /// and share cache entries.
+    async fn embed_query(&self, query: &str) -> Result<Vec<f32>> {
+        self.embed_text(&normalize_query(query)).await
+    }
+
+    /// Add document to regular collection
+    ///
+    /// Text over the configured `DocumentLimit` is either rejected with
+    /// `DocumentTooLarge` or stored as several chunks. Chunks carry
+    /// `chunk_index`/`chunk_count` metadata, and all but the first point at
+    /// the first via `chunk_of`. The returned id is the first chunk's, and
+    /// `delete_document` with it removes every chunk.
+    pub async fn add_document(&self, text: &str, metadata: HashMap<String, String>) -> Result<String> {
+        let chunks = self.document_limit.apply(text)?;
+        if chunks.len() == 1 {
+            return self.add_point(text, metadata).await;
         }
-        
-        // Normalize the vector
-        let magnitude: f32 = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
-        if magnitude > 0.0 {
-            for val in &mut embedding {
-                *val /= magnitude;
+
+        let chunk_count = chunks.len().to_string();
+        let mut first_id: Option<String> = None;
+        for (index, chunk) in chunks.iter().enumerate() {
+            let mut chunk_metadata = metadata.clone();
+            chunk_metadata.insert("chunk_index".to_string(), index.to_string());
+            chunk_metadata.insert("chunk_count".to_string(), chunk_count.clone());
+            if let Some(first_id) = &first_id {
+                chunk_metadata.insert("chunk_of".to_string(), first_id.clone());
             }
+
+            let id = self.add_point(chunk, chunk_metadata).await?;
+            first_id.get_or_insert(id);
         }
-        
-        Ok(embedding)
+
+        info!("Document split into {} chunks", chunk_count);
+        Ok(first_id.unwrap_or_default())
     }
 
-    /// Add document to regular collection
-    pub async fn add_document(&self, text: &str, metadata: HashMap<String, String>) -> Result<String> {
+    async fn add_point(&self, text: &str, metadata: HashMap<String, String>) -> Result<String> {
         let embedding = self.embed_text(text).await?;
-        let document_id = Uuid::new_v4().to_string();
+        let document_id = self.id_generator.next_id().to_string();
         
         let mut payload = serde_json::json!({
             "content": text,
@@ -178,30 +853,102 @@ impl RAGSystem {
             Payload::try_from(payload)?,
         )];
 
-        self.qdrant_client
-            .upsert_points(UpsertPointsBuilder::new(&self.regular_collection, points))
-            .await?;
+        with_qdrant_retry(&self.qdrant_retry, "upsert", || {
+            self.qdrant_client
+                .upsert_points(UpsertPointsBuilder::new(&self.regular_collection, points.clone()))
+        })
+        .await?;
 
         info!("Document added to regular collection with ID: {}", document_id);
         Ok(document_id)
     }
 
+    /// Remove a document from the regular collection
+    pub async fn delete_document(&self, document_id: &str) -> Result<()> {
+        with_qdrant_retry(&self.qdrant_retry, "delete", || {
+            self.qdrant_client.delete_points(
+                DeletePointsBuilder::new(&self.regular_collection)
+                    .points(PointsIdsList { ids: vec![PointId::from(document_id.to_string())] }),
+            )
+        })
+        .await?;
+
+        // Remaining chunks of a document split by `add_document`
+        if !matches!(self.document_limit, DocumentLimit::Unlimited) {
+            with_qdrant_retry(&self.qdrant_retry, "delete", || {
+                self.qdrant_client.delete_points(
+                    DeletePointsBuilder::new(&self.regular_collection)
+                        .points(Filter::must([Condition::matches("chunk_of", document_id.to_string())])),
+                )
+            })
+            .await?;
+        }
+
+        info!("Document deleted from regular collection: {}", document_id);
+        Ok(())
+    }
+
     /// Search regular collection for similar documents
-    pub async fn search_documents(&self, query: &str, limit: u64, score_threshold: Option<f32>) -> Result<Vec<SearchResult>> {
-        let embedding = self.embed_text(query).await?;
+    ///
+    /// Results are ordered by `score desc, id asc`, so documents with equal
+    /// scores come back in the same order on every call. With a `tenant`,
+    /// only that tenant's and shared documents are searched.
+    pub async fn search_documents(
+        &self,
+        query: &str,
+        limit: u64,
+        score_threshold: Option<f32>,
+        tenant: Option<&str>,
+    ) -> Result<Vec<SearchResult>> {
+        self.search_documents_matching(query, limit, score_threshold, tenant, &HashMap::new()).await
+    }
+
+    /// `search_documents`, keeping only points whose payload has every
+    /// key/value in `metadata`.
+    pub async fn search_documents_matching(
+        &self,
+        query: &str,
+        limit: u64,
+        score_threshold: Option<f32>,
+        tenant: Option<&str>,
+        metadata: &HashMap<String, String>,
+    ) -> Result<Vec<SearchResult>> {
+        let results = self.search_points(query, limit, score_threshold, tenant, metadata, false).await?;
+        Ok(results.into_iter().map(|(result, _)| result).collect())
+    }
+
+    /// Search shared by `search_documents_matching` and retrieval, returning
+    /// each result's stored vector when `with_vectors` is set (empty otherwise).
+    async fn search_points(
+        &self,
+        query: &str,
+        limit: u64,
+        score_threshold: Option<f32>,
+        tenant: Option<&str>,
+        metadata: &HashMap<String, String>,
+        with_vectors: bool,
+    ) -> Result<Vec<(SearchResult, Vec<f32>)>> {
+        let embedding = self.embed_query(query).await?;
         
         let mut search_builder = SearchPointsBuilder::new(&self.regular_collection, embedding, limit)
-            .with_payload(true);
+            .with_payload(true)
+            .with_vectors(with_vectors);
             
-        if let Some(threshold) = score_threshold {
+        // Qdrant would treat a threshold on Euclidean distances as a maximum,
+        // so those are filtered once converted to similarities below
+        if let Some(threshold) = score_threshold.filter(|_| self.embedding.distance != Distance::Euclid) {
             search_builder = search_builder.score_threshold(threshold);
         }
 
-        let search_result = self.qdrant_client
-            .search_points(search_builder)
-            .await?;
+        if let Some(filter) = payload_filter(tenant, metadata) {
+            search_builder = search_builder.filter(filter);
+        }
+
+        let search_result =
+            with_qdrant_retry(&self.qdrant_retry, "search", || self.qdrant_client.search_points(search_builder.clone())).await?;
 
         let mut results = Vec::new();
+        let mut vectors: HashMap<String, Vec<f32>> = HashMap::new();
         for point in search_result.result {
             let content = point.payload
                 .get("content")
@@ -218,29 +965,44 @@ impl RAGSystem {
                 }
             }
 
+            let id = point.id.as_ref().map(point_id_to_string).unwrap_or_default();
+            if with_vectors {
+                vectors.insert(id.clone(), dense_vector(point.vectors));
+            }
+
             results.push(SearchResult {
+                id,
                 content,
                 score: point.score,
                 metadata,
             });
         }
 
-        Ok(results)
+        Ok(ranked_results(self.embedding.distance, score_threshold, results)
+            .into_iter()
+            .map(|result| {
+                let vector = vectors.remove(&result.id).unwrap_or_default();
+                (result, vector)
+            })
+            .collect())
     }
 
     /// Search cache collection for similar queries
     pub async fn search_cache(&self, query: &str) -> Result<Option<String>> {
-        let embedding = self.embed_text(query).await?;
+        let embedding = self.embed_query(query).await?;
 
-        let search_result = self.qdrant_client
-            .search_points(
-                SearchPointsBuilder::new(&self.cache_collection, embedding, 1)
-                    .with_payload(true)
-                    .score_threshold(0.95) // High threshold for cache hits
-            )
-            .await?;
+        let search_result = with_qdrant_retry(&self.qdrant_retry, "cache search", || {
+            self.qdrant_client
+                .search_points(SearchPointsBuilder::new(&self.cache_collection, embedding.clone(), 1).with_payload(true))
+        })
+        .await?;
 
         if let Some(point) = search_result.result.first() {
+            if !self.is_cache_hit(point.score) {
+                info!("Cache miss: closest query scored {} (threshold {})", point.score, self.cache_threshold);
+                return Ok(None);
+            }
+
             let answer = point.payload
                 .get("answer")
                 .and_then(|v| v.as_str())
@@ -253,13 +1015,15 @@ impl RAGSystem {
         Ok(None)
     }
 
-    /// Add response to cache
+    /// Add response to cache, keyed by the normalized query. The payload
+    /// keeps the query as the user typed it.
     pub async fn add_to_cache(&self, query: &str, answer: &str) -> Result<String> {
-        let embedding = self.embed_text(query).await?;
-        let cache_id = Uuid::new_v4().to_string();
+        let embedding = self.embed_query(query).await?;
+        let cache_id = self.id_generator.next_id().to_string();
         
         let payload = serde_json::json!({
             "query": query,
+            "normalized_query": normalize_query(query),
             "answer": answer,
             "timestamp": chrono::Utc::now().to_rfc3339(),
         });
@@ -270,82 +1034,312 @@ impl RAGSystem {
             Payload::try_from(payload)?,
         )];
 
-        self.qdrant_client
-            .upsert_points(UpsertPointsBuilder::new(&self.cache_collection, points))
-            .await?;
+        with_qdrant_retry(&self.qdrant_retry, "cache upsert", || {
+            self.qdrant_client
+                .upsert_points(UpsertPointsBuilder::new(&self.cache_collection, points.clone()))
+        })
+        .await?;
 
         info!("Response cached with ID: {}", cache_id);
         Ok(cache_id)
     }
 
     /// Generate AI response using RAG
-    pub async fn generate_rag_response(&self, query: &str, context_limit: u64) -> Result<String> {
+    ///
+    /// A cached answer to an equivalent earlier query is returned without
+    /// retrieval or an LLM call, and fresh LLM answers are cached. Fallback
+    /// answers are not cached, so they are retried next time. Tenant queries
+    /// skip the cache, since its entries are shared across tenants. Cache
+    /// errors are logged and the query is answered as if it missed.
+    pub async fn generate_rag_response(&self, query: &str, context_limit: u64, tenant: Option<&str>) -> Result<String> {
+        if tenant.is_some() {
+            return Ok(self.generate_cited_response(query, context_limit, tenant).await?.answer);
+        }
+        through_cache(
+            self.search_cache(query),
+            self.generate_cited_response(query, context_limit, None),
+            |answer| async move { self.add_to_cache(query, &answer).await },
+        )
+        .await
+    }
+
+    /// Answer like `generate_rag_response`, also citing the documents the
+    /// context was built from. Concurrent calls for the same question,
+    /// ignoring case and spacing, share a single retrieval and LLM call.
+    pub async fn generate_cited_response(&self, query: &str, context_limit: u64, tenant: Option<&str>) -> Result<CitedAnswer> {
+        let key = format!("{}|{}|{}", tenant.unwrap_or(""), context_limit, normalize_query(query));
+        self.in_flight
+            .run(&key, || async {
+                self.compute_cited_response(query, context_limit, tenant)
+                    .await
+                    .map_err(|e| e.to_string())
+            })
+            .await
+            .map_err(|e| anyhow::anyhow!(e))
+    }
+
+    async fn compute_cited_response(&self, query: &str, context_limit: u64, tenant: Option<&str>) -> Result<CitedAnswer> {
         info!("Starting RAG response generation for query: {}", query);
         
-        // Search for relevant documents (skip cache for now to avoid delays)
-        info!("Searching for relevant documents");
-        let search_results = self.search_documents(query, context_limit, Some(0.0)).await?;
-        info!("Found {} search results", search_results.len());
+        let search_results = self.retrieve(query, context_limit, tenant).await?;
         
         if search_results.is_empty() {
             info!("No relevant documents found for query");
-            return Ok("I don't have enough information to answer that question about ink! smart contracts.".to_string());
+            return Ok(CitedAnswer {
+                answer: "I don't have enough information to answer that question about ink! smart contracts.".to_string(),
+                citations: Vec::new(),
+                generated: false,
+            });
         }
 
-        // Prepare context from search results
-        let context: Vec<String> = search_results.iter()
-            .take(5)
-            .map(|result| {
-                let mut context_item = String::new();
-                
-                if let Some(file_path) = result.metadata.get("file_path") {
-                    context_item.push_str(&format!("Source: {}\n", file_path));
-                }
-                
-                if let Some(contract_name) = self.extract_contract_name(&result.content) {
-                    context_item.push_str(&format!("Contract: {}\n", contract_name));
-                }
-                
-                if let Some(description) = self.extract_description(&result.content) {
+        let migration_prompt = migration_prompt(query);
+        let used = self.fit_context_budget(&migration_prompt, &search_results);
+        let context = self.build_context(&used);
+
+        // Use Gemini AI to generate proper response
+        let examples = self.build_examples(&search_results, ExampleRetrieval::default().count);
+        let (answer, generated) = self.answer_or_fallback(&migration_prompt, &context, &examples).await?;
+        Ok(CitedAnswer {
+            answer,
+            citations: citations(&used),
+            generated,
+        })
+    }
+
+    /// Answer like `generate_rag_response`, streaming the LLM's text as it
+    /// is generated. Retrieval finishes before anything is returned; the
+    /// answer is not cached and there is no fallback if the LLM fails.
+    pub async fn stream_rag_response(&self, query: &str, context_limit: u64, tenant: Option<&str>) -> Result<ResponseChunks> {
+        info!("Starting streamed RAG response for query: {}", query);
+
+        let search_results = self.retrieve(query, context_limit, tenant).await?;
+        if search_results.is_empty() {
+            info!("No relevant documents found for query");
+            let (sender, chunks) = tokio::sync::mpsc::channel(1);
+            let _ = sender
+                .send(Ok("I don't have enough information to answer that question about ink! smart contracts.".to_string()))
+                .await;
+            return Ok(chunks);
+        }
+
+        let migration_prompt = migration_prompt(query);
+        let used = self.fit_context_budget(&migration_prompt, &search_results);
+        let context = self.build_context(&used);
+        self.gemini_client.stream_response(&migration_prompt, &context).await
+    }
+
+    /// Run retrieval and prompt assembly for `query` exactly as
+    /// `generate_rag_response` does, without calling the LLM.
+    pub async fn explain_rag_query(&self, query: &str, context_limit: u64, tenant: Option<&str>) -> Result<RagExplanation> {
+        let search_results = self.retrieve(query, context_limit, tenant).await?;
+        Ok(self.explanation(query, search_results))
+    }
+
+    fn explanation(&self, query: &str, documents: Vec<SearchResult>) -> RagExplanation {
+        let migration_prompt = migration_prompt(query);
+        let context = self.build_context(&self.fit_context_budget(&migration_prompt, &documents));
+        let prompt = crate::gemini_client::build_prompt(&migration_prompt, &context);
+        RagExplanation {
+            query: query.to_string(),
+            would_call_llm: !documents.is_empty(),
+            documents,
+            context,
+            prompt,
+        }
+    }
+
+    /// Search with the expanded query, retrying with the original when expansion finds nothing.
+    ///
+    /// Extra candidates are fetched and reranked, so near-duplicates don't
+    /// crowd out other documents in the `context_limit` returned.
+    async fn retrieve(&self, query: &str, context_limit: u64, tenant: Option<&str>) -> Result<Vec<SearchResult>> {
+        info!("Searching for relevant documents");
+        let search_query = self.expand_query(query).await;
+        let fetch = context_limit.saturating_mul(RERANK_OVERFETCH);
+        let no_filter = HashMap::new();
+        let mut candidates = self.search_points(&search_query, fetch, Some(0.0), tenant, &no_filter, true).await?;
+        if candidates.is_empty() && search_query != query {
+            info!("Expanded query found nothing, retrying with the original query");
+            candidates = self.search_points(query, fetch, Some(0.0), tenant, &no_filter, true).await?;
+        }
+        let search_results = self.rerank(candidates, context_limit as usize);
+        info!("Found {} search results", search_results.len());
+        Ok(search_results)
+    }
+
+    /// Up to `limit` of `candidates` (ranked best first, with their
+    /// embeddings) after dropping near-duplicates of better-ranked ones and,
+    /// if configured, reordering by maximal marginal relevance.
+    fn rerank(&self, candidates: Vec<(SearchResult, Vec<f32>)>, limit: usize) -> Vec<SearchResult> {
+        let mut distinct: Vec<(SearchResult, Vec<f32>)> = Vec::new();
+        for (result, vector) in candidates {
+            let duplicate = distinct
+                .iter()
+                .any(|(_, kept)| cosine_similarity(kept, &vector) >= self.rerank.duplicate_threshold);
+            if duplicate {
+                info!("Dropping {} as a near-duplicate of a better match", result.id);
+            } else {
+                distinct.push((result, vector));
+            }
+        }
+
+        let Some(lambda) = self.rerank.mmr_lambda else {
+            return distinct.into_iter().take(limit).map(|(result, _)| result).collect();
+        };
+
+        let mut selected: Vec<(SearchResult, Vec<f32>)> = Vec::new();
+        while selected.len() < limit && !distinct.is_empty() {
+            let mmr = |(result, vector): &(SearchResult, Vec<f32>)| {
+                let redundancy = selected
+                    .iter()
+                    .map(|(_, chosen)| cosine_similarity(chosen, vector))
+                    .fold(0.0, f32::max);
+                lambda * result.score - (1.0 - lambda) * redundancy
+            };
+            // On a tie keep the better-ranked candidate
+            let best = distinct
+                .iter()
+                .enumerate()
+                .rev()
+                .max_by(|(_, a), (_, b)| mmr(a).total_cmp(&mmr(b)))
+                .map(|(i, _)| i)
+                .unwrap_or(0);
+            selected.push(distinct.remove(best));
+        }
+        selected.into_iter().map(|(result, _)| result).collect()
+    }
+
+    /// Prepare context from search results
+    /// The top documents whose context lets `prompt` fit in
+    /// `max_context_tokens`, dropping the lowest-scoring first. The rest keep
+    /// their order. A prompt too long even without context gets none.
+    fn fit_context_budget(&self, prompt: &str, search_results: &[SearchResult]) -> Vec<SearchResult> {
+        let mut used: Vec<SearchResult> = search_results.iter().take(CONTEXT_DOCUMENTS).cloned().collect();
+        while !used.is_empty() {
+            let context = self.build_context(&used);
+            let tokens = estimate_tokens(&crate::gemini_client::build_prompt(prompt, &context));
+            if tokens <= self.max_context_tokens {
+                break;
+            }
+            // On a tie, drop the later (lower-ranked) document
+            let lowest = used
+                .iter()
+                .enumerate()
+                .rev()
+                .min_by(|(_, a), (_, b)| a.score.total_cmp(&b.score))
+                .map(|(i, _)| i)
+                .unwrap_or(0);
+            let dropped = used.remove(lowest);
+            info!(
+                "Prompt is ~{} tokens, over the {} budget; leaving out {} (score {:.3})",
+                tokens, self.max_context_tokens, dropped.id, dropped.score
+            );
+        }
+        used
+    }
+
+    fn build_context(&self, search_results: &[SearchResult]) -> Vec<String> {
+        search_results.iter()
+            .take(CONTEXT_DOCUMENTS)
+            .map(|result| {
+                let mut context_item = String::new();
+                
+                if let Some(file_path) = result.metadata.get("file_path") {
+                    context_item.push_str(&format!("Source: {}\n", file_path));
+                }
+                
+                if let Some(contract_name) = self.extract_contract_name(&result.content) {
+                    context_item.push_str(&format!("Contract: {}\n", contract_name));
+                }
+                
+                if let Some(description) = self.extract_description(&result.content) {
                     context_item.push_str(&format!("Description: {}\n", description));
                 }
                 
                 context_item.push_str(&format!("Code:\n{}\n", self.format_code(&result.content)));
                 context_item
             })
-            .collect();
-
-        // Create specialized migration prompt
-        let migration_prompt = format!(
-            "You are an expert in both Solidity and ink! smart contracts. The user is asking: '{}'
+            .collect()
+    }
 
-Please provide a detailed, step-by-step explanation based on the provided code examples. Focus on:
+    /// Append related terms to the query according to `query_expansion`.
+    async fn expand_query(&self, query: &str) -> String {
+        let terms = match self.query_expansion {
+            QueryExpansion::Off => return query.to_string(),
+            QueryExpansion::Synonyms => synonym_terms(query),
+            QueryExpansion::Llm => {
+                let prompt = render(Prompt::QueryExpansion, &[("question", query)]);
+                match self.gemini_client.try_generate_response(&prompt, &[]).await {
+                    Ok(response) => response
+                        .split([',', '\n'])
+                        .map(|term| term.trim().trim_start_matches(['-', '*']).trim().to_string())
+                        .filter(|term| !term.is_empty())
+                        .take(5)
+                        .collect(),
+                    Err(e) => {
+                        error!("Query expansion via LLM failed, using synonyms: {}", e);
+                        synonym_terms(query)
+                    }
+                }
+            }
+        };
 
-1. **Key Differences**: Explain main conceptual differences between Solidity and ink!
-2. **Migration Steps**: Provide clear, actionable steps for converting patterns
-3. **Code Examples**: Show concrete before/after examples from the context
-4. **Best Practices**: Highlight important considerations and gotchas
-5. **Practical Guide**: Make it actionable for developers
+        let query_lower = query.to_lowercase();
+        let extra: Vec<String> = terms
+            .into_iter()
+            .filter(|term| !query_lower.contains(&term.to_lowercase()))
+            .collect();
 
-Format your response clearly with specific code snippets and explanations, not just raw code dumps.",
-            query
-        );
+        if extra.is_empty() {
+            query.to_string()
+        } else {
+            let expanded = format!("{} {}", query, extra.join(" "));
+            info!("Expanded query '{}' to '{}'", query, expanded);
+            expanded
+        }
+    }
 
-        // Use Gemini AI to generate proper response
-        match self.gemini_client.generate_response(&migration_prompt, &context).await {
+    /// Ask the LLM, falling back according to the configured `RagFallback`.
+    async fn answer_or_fallback(
+        &self,
+        prompt: &str,
+        context: &[String],
+        examples: &[crate::CodeExample],
+    ) -> Result<(String, bool)> {
+        match self.gemini_client.try_generate_response(prompt, context).await {
             Ok(ai_response) => {
                 info!("Successfully generated AI response");
-                Ok(ai_response)
+                Ok((ai_response, true))
             },
             Err(e) => {
                 error!("Failed to generate AI response: {}", e);
-                Ok(format!(
-                    "I'm having trouble generating a detailed response right now. Here are the most relevant code examples I found:\n\n{}",
-                    context.join("\n\n---\n\n")
-                ))
+                let fallback = match self.fallback {
+                    RagFallback::RawContext => format!(
+                        "I'm having trouble generating a detailed response right now. Here are the most relevant code examples I found:\n\n{}",
+                        context.join("\n\n---\n\n")
+                    ),
+                    RagFallback::Templated => format_templated_fallback(examples),
+                    RagFallback::Error => return Err(e.context("AI response generation is unavailable")),
+                };
+                Ok((fallback, false))
             }
         }
     }
+
+    fn build_examples(&self, search_results: &[SearchResult], count: usize) -> Vec<crate::CodeExample> {
+        search_results
+            .iter()
+            .take(count)
+            .map(|result| crate::CodeExample {
+                title: self.extract_contract_name(&result.content)
+                    .unwrap_or_else(|| "Smart Contract".to_string()),
+                description: self.extract_description(&result.content),
+                code: self.format_code(&result.content),
+                source_file: result.metadata.get("file_path").cloned(),
+                relevance_score: result.score * 100.0,
+            })
+            .collect()
+    }
     
     /// Extract contract name from code content
     fn extract_contract_name(&self, content: &str) -> Option<String> {
@@ -375,11 +1369,11 @@ Format your response clearly with specific code snippets and explanations, not j
         
         for line in lines {
             let trimmed = line.trim();
-            if trimmed.starts_with("/// ") {
+            if let Some(doc) = trimmed.strip_prefix("/// ") {
                 if !description.is_empty() {
                     description.push(' ');
                 }
-                description.push_str(&trimmed[4..]);
+                description.push_str(doc);
             } else if trimmed.starts_with("//") && trimmed.contains("contract") {
                 return Some(trimmed[2..].trim().to_string());
             }
@@ -396,7 +1390,6 @@ Format your response clearly with specific code snippets and explanations, not j
     fn format_code(&self, content: &str) -> String {
         let lines: Vec<&str> = content.lines().collect();
         let mut formatted = String::new();
-        let mut in_important_section = false;
         let mut line_count = 0;
         
         for line in lines {
@@ -407,11 +1400,6 @@ Format your response clearly with specific code snippets and explanations, not j
                 continue;
             }
             
-            // Highlight important sections
-            if line.contains("#[ink(") || line.contains("pub fn") || line.contains("impl") {
-                in_important_section = true;
-            }
-            
             formatted.push_str(line);
             formatted.push('\n');
             
@@ -426,61 +1414,122 @@ Format your response clearly with specific code snippets and explanations, not j
     }
     
     /// Generate structured response for API consumption
-    pub async fn generate_structured_response(&self, query: &str, context_limit: u64) -> Result<crate::FormattedResponse> {
+    pub async fn generate_structured_response(
+        &self,
+        query: &str,
+        context_limit: u64,
+        tenant: Option<&str>,
+    ) -> Result<crate::FormattedResponse> {
         info!("Starting structured response generation for query: {}", query);
         
-        // Search for relevant documents
-        let search_results = self.search_documents(query, context_limit, Some(0.0)).await?;
-        
-        if search_results.is_empty() {
-            return Ok(crate::FormattedResponse {
+        // Search for relevant documents, enough to fill every example slot
+        let limit = context_limit.max(self.example_retrieval.count as u64);
+        let search_results = self.search_documents(query, limit, Some(0.0), tenant).await?;
+        Ok(self.format_structured_response(query, &search_results).await)
+    }
+
+    /// The response for `query` from its search results, showing only
+    /// examples that reach the configured minimum score.
+    async fn format_structured_response(&self, query: &str, search_results: &[SearchResult]) -> crate::FormattedResponse {
+        let retrieval = self.example_retrieval;
+        let relevant: Vec<SearchResult> = search_results
+            .iter()
+            .filter(|result| result.score >= retrieval.min_score)
+            .cloned()
+            .collect();
+        if relevant.len() < search_results.len() {
+            info!(
+                "Dropped {} search results scoring below {}",
+                search_results.len() - relevant.len(),
+                retrieval.min_score
+            );
+        }
+
+        if relevant.is_empty() {
+            return crate::FormattedResponse {
                 query: query.to_string(),
                 summary: "No relevant ink! smart contract examples found for your query.".to_string(),
                 examples: vec![],
                 help_text: "Try refining your search terms or asking about specific ink! concepts like 'storage', 'messages', 'events', or 'constructors'.".to_string(),
-            });
-        }
-        
-        let mut examples = Vec::new();
-        
-        for result in search_results.iter().take(3) {
-            let example = crate::CodeExample {
-                title: self.extract_contract_name(&result.content)
-                    .unwrap_or_else(|| "Smart Contract".to_string()),
-                description: self.extract_description(&result.content),
-                code: self.format_code(&result.content),
-                source_file: result.metadata.get("file_path").cloned(),
-                relevance_score: result.score * 100.0,
             };
-            examples.push(example);
         }
         
-        let summary = format!(
-            "Found {} relevant ink! smart contract examples matching your query. These examples demonstrate best practices and common patterns in ink! development.",
-            examples.len()
-        );
+        let examples = self.build_examples(&relevant, retrieval.count);
+        let summary = self.summarize_examples(query, &examples).await;
         
-        let help_text = "These examples are from the official ink! examples repository. You can use them as templates for building your own smart contracts on Polkadot.".to_string();
+        let help_text = if examples.len() < retrieval.min_relevant {
+            format!(
+                "Only {} closely matching example{} found. Try a broader query, such as a general ink! concept like 'storage', 'events' or 'cross-contract calls' rather than a specific contract or function.",
+                examples.len(),
+                if examples.len() == 1 { " was" } else { "s were" }
+            )
+        } else {
+            "These examples are from the official ink! examples repository. You can use them as templates for building your own smart contracts on Polkadot.".to_string()
+        };
         
-        Ok(crate::FormattedResponse {
+        crate::FormattedResponse {
             query: query.to_string(),
             summary,
             examples,
             help_text,
-        })
+        }
+    }
+
+    /// One-paragraph answer to the query grounded in the retrieved examples,
+    /// or a fixed summary when the LLM is unavailable.
+    async fn summarize_examples(&self, query: &str, examples: &[crate::CodeExample]) -> String {
+        let context: Vec<String> = examples
+            .iter()
+            .map(|example| {
+                format!(
+                    "{}: {}\n```rust\n{}\n```",
+                    example.title,
+                    example.description.as_deref().unwrap_or(""),
+                    example.code
+                )
+            })
+            .collect();
+
+        let prompt = render(Prompt::Summary, &[("question", query)]);
+
+        match self.gemini_client.try_generate_response(&prompt, &context).await {
+            Ok(summary) if !summary.trim().is_empty() => summary.trim().to_string(),
+            Ok(_) => templated_summary(examples.len()),
+            Err(e) => {
+                error!("Summary generation failed, using templated summary: {}", e);
+                templated_summary(examples.len())
+            }
+        }
     }
 
     /// Bulk insert documents from text data
+    ///
+    /// Up to `MAX_CONCURRENCY` documents are embedded at a time; the returned
+    /// ids keep the input order.
+    #[allow(dead_code)]
     pub async fn bulk_insert_documents(&self, documents: Vec<(String, HashMap<String, String>)>) -> Result<Vec<String>> {
+        let results = map_concurrent(documents, self.max_concurrency, |(text, metadata)| async move {
+            let result = self.add_document(&text, metadata.clone()).await;
+            (text, metadata, result)
+        })
+        .await;
+
         let mut document_ids = Vec::new();
-        
-        for (text, metadata) in documents {
-            match self.add_document(&text, metadata).await {
+        for (text, metadata, result) in results {
+            match result {
                 Ok(doc_id) => {
                     document_ids.push(doc_id);
                 }
                 Err(e) => {
                     error!("Failed to insert document: {}", e);
+                    if let Some(dead_letters) = &self.dead_letters {
+                        if let Err(e) = dead_letters
+                            .record(&document_source_id(&metadata), &text, &metadata, &e.to_string())
+                            .await
+                        {
+                            error!("Failed to record dead letter: {}", e);
+                        }
+                    }
                 }
             }
         }
@@ -489,9 +1538,155 @@ Format your response clearly with specific code snippets and explanations, not j
         Ok(document_ids)
     }
 
+    /// Scroll the regular collection and count points per contract type,
+    /// language and source, limited to `tenant`'s own points when given.
+    pub async fn collection_breakdown(&self, tenant: Option<&str>) -> Result<CollectionBreakdown> {
+        const PAGE_SIZE: u32 = 256;
+
+        let mut breakdown = CollectionBreakdown::default();
+        let mut offset: Option<PointId> = None;
+
+        loop {
+            let mut request = ScrollPointsBuilder::new(&self.regular_collection)
+                .limit(PAGE_SIZE)
+                .with_payload(true)
+                .with_vectors(false);
+            if let Some(tenant) = tenant {
+                request = request.filter(tenant::owned_by(tenant));
+            }
+            if let Some(offset) = offset.take() {
+                request = request.offset(offset);
+            }
+
+            let page = with_qdrant_retry(&self.qdrant_retry, "scroll", || self.qdrant_client.scroll(request.clone())).await?;
+            for point in &page.result {
+                breakdown.add(|key| point.payload.get(key).and_then(|v| v.as_str()).map(|s| s.as_str()));
+            }
+
+            match page.next_page_offset {
+                Some(next) => offset = Some(next),
+                None => break,
+            }
+        }
+
+        Ok(breakdown)
+    }
+
+    /// Send every point of the regular collection to `tx`, vectors
+    /// included, one scroll page at a time, limited to `tenant`'s own points
+    /// when given. The bounded channel keeps at most a page or so in memory;
+    /// a dropped receiver ends the export and a failed scroll is sent as the
+    /// last item.
+    pub async fn export_documents(&self, tenant: Option<&str>, tx: tokio::sync::mpsc::Sender<Result<ExportedPoint>>) {
+        if let Err(e) = self.export_pages(tenant, &tx).await {
+            error!("Knowledge base export failed: {}", e);
+            let _ = tx.send(Err(e)).await;
+        }
+    }
+
+    async fn export_pages(&self, tenant: Option<&str>, tx: &tokio::sync::mpsc::Sender<Result<ExportedPoint>>) -> Result<()> {
+        let mut offset: Option<PointId> = None;
+
+        loop {
+            let mut request = ScrollPointsBuilder::new(&self.regular_collection)
+                .limit(EXPORT_PAGE_SIZE)
+                .with_payload(true)
+                .with_vectors(true);
+            if let Some(tenant) = tenant {
+                request = request.filter(tenant::owned_by(tenant));
+            }
+            if let Some(offset) = offset.take() {
+                request = request.offset(offset);
+            }
+
+            let page = with_qdrant_retry(&self.qdrant_retry, "scroll", || self.qdrant_client.scroll(request.clone())).await?;
+            for point in page.result {
+                if tx.send(Ok(ExportedPoint::from_point(point))).await.is_err() {
+                    info!("Knowledge base export abandoned by the client");
+                    return Ok(());
+                }
+            }
+
+            match page.next_page_offset {
+                Some(next) => offset = Some(next),
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Upsert exported points into the regular collection with their
+    /// original ids and vectors, `EXPORT_PAGE_SIZE` at a time.
+    ///
+    /// A tenant's import is stamped as its own whatever the file says, and
+    /// fails with `ForeignPoints` before writing a batch whose ids are taken
+    /// by shared or other tenants' points.
+    pub async fn import_documents(&self, points: &[ExportedPoint], tenant: Option<&str>) -> Result<usize> {
+        for batch in points.chunks(EXPORT_PAGE_SIZE as usize) {
+            let batch = match tenant {
+                Some(tenant) => {
+                    self.check_owned(batch, tenant).await?;
+                    batch
+                        .iter()
+                        .map(|point| {
+                            let mut point = point.clone();
+                            point.metadata.insert(tenant::TENANT_FIELD.to_string(), tenant.to_string());
+                            point.to_point()
+                        })
+                        .collect::<Result<Vec<_>>>()?
+                }
+                None => batch.iter().map(ExportedPoint::to_point).collect::<Result<Vec<_>>>()?,
+            };
+            with_qdrant_retry(&self.qdrant_retry, "upsert", || {
+                self.qdrant_client
+                    .upsert_points(UpsertPointsBuilder::new(&self.regular_collection, batch.clone()).wait(true))
+            })
+            .await?;
+        }
+
+        info!("Imported {} points into {}", points.len(), self.regular_collection);
+        Ok(points.len())
+    }
+
+    /// Fail with `ForeignPoints` if any of `points` would replace an existing
+    /// point not owned by `tenant`.
+    async fn check_owned(&self, points: &[ExportedPoint], tenant: &str) -> Result<()> {
+        let ids: Vec<PointId> = points.iter().map(ExportedPoint::point_id).collect();
+        let foreign = Filter {
+            must: vec![Condition::has_id(ids)],
+            must_not: vec![Condition::matches(tenant::TENANT_FIELD, tenant.to_string())],
+            ..Default::default()
+        };
+        let count = with_qdrant_retry(&self.qdrant_retry, "count", || {
+            self.qdrant_client
+                .count(CountPointsBuilder::new(&self.regular_collection).filter(foreign.clone()).exact(true))
+        })
+        .await?
+        .result
+        .map(|r| r.count)
+        .unwrap_or(0);
+
+        if count > 0 {
+            return Err(ForeignPoints { count }.into());
+        }
+        Ok(())
+    }
+
     /// Get collection statistics
-    pub async fn get_collection_stats(&self) -> Result<HashMap<String, u64>> {
+    ///
+    /// For a tenant only its own point count is reported, as
+    /// `tenant_documents`; collection-wide totals would leak other tenants' sizes.
+    pub async fn get_collection_stats(&self, tenant: Option<&str>) -> Result<HashMap<String, u64>> {
         let mut stats = HashMap::new();
+
+        if let Some(tenant) = tenant {
+            let count = with_qdrant_retry(&self.qdrant_retry, "count", || {
+                self.qdrant_client
+                    .count(CountPointsBuilder::new(&self.regular_collection).filter(tenant::owned_by(tenant)).exact(true))
+            })
+            .await?;
+            stats.insert("tenant_documents".to_string(), count.result.map(|r| r.count).unwrap_or(0));
+            return Ok(stats);
+        }
         
         // Get regular collection info
         if let Ok(regular_info) = self.qdrant_client.collection_info(&self.regular_collection).await {
@@ -509,4 +1704,961 @@ Format your response clearly with specific code snippets and explanations, not j
         
         Ok(stats)
     }
-}
\ No newline at end of file
+}
+
+/// Related ink!/Solidity terms for words appearing in the query.
+fn synonym_terms(query: &str) -> Vec<String> {
+    const SYNONYMS: &[(&str, &[&str])] = &[
+        ("mapping", &["Mapping", "storage", "key value"]),
+        ("storage", &["#[ink(storage)]", "state", "Mapping"]),
+        ("event", &["#[ink(event)]", "emit_event", "topic"]),
+        ("emit", &["emit_event", "#[ink(event)]"]),
+        ("modifier", &["access control", "ensure", "caller"]),
+        ("require", &["Result", "Error", "ensure"]),
+        ("constructor", &["#[ink(constructor)]", "new"]),
+        ("msg.sender", &["self.env().caller()", "caller"]),
+        ("payable", &["#[ink(payable)]", "transferred_value"]),
+        ("erc20", &["PSP22", "token", "balance", "allowance"]),
+        ("erc721", &["PSP34", "NFT", "token owner"]),
+    ];
+
+    let query_lower = query.to_lowercase();
+    let mut terms: Vec<String> = Vec::new();
+    for (word, related) in SYNONYMS {
+        if query_lower.contains(word) {
+            for term in related.iter() {
+                if !terms.iter().any(|t| t == term) {
+                    terms.push(term.to_string());
+                }
+            }
+        }
+    }
+    terms
+}
+
+fn format_templated_fallback(examples: &[crate::CodeExample]) -> String {
+    let mut response = String::from(
+        "I couldn't generate a detailed explanation right now, but here are relevant examples for your question:",
+    );
+
+    for (i, example) in examples.iter().enumerate() {
+        response.push_str(&format!("\n\n### {}. {}\n", i + 1, example.title));
+        if let Some(description) = &example.description {
+            response.push_str(&format!("{}\n", description));
+        }
+        if let Some(source_file) = &example.source_file {
+            response.push_str(&format!("Source: {}\n", source_file));
+        }
+        response.push_str(&format!("```rust\n{}```", example.code));
+    }
+
+    response
+}
+
+/// `lookup`'s cached answer on a hit; on a miss, or when the lookup fails,
+/// `compute`'s answer, passed to `store` when the LLM wrote it.
+async fn through_cache<S, F>(
+    lookup: impl Future<Output = Result<Option<String>>>,
+    compute: impl Future<Output = Result<CitedAnswer>>,
+    store: S,
+) -> Result<String>
+where
+    S: FnOnce(String) -> F,
+    F: Future<Output = Result<String>>,
+{
+    match lookup.await {
+        Ok(Some(answer)) => return Ok(answer),
+        Ok(None) => {}
+        Err(e) => warn!("Semantic cache lookup failed: {}", e),
+    }
+
+    let cited = compute.await?;
+    if cited.generated {
+        if let Err(e) = store(cited.answer.clone()).await {
+            warn!("Failed to cache RAG response: {}", e);
+        }
+    }
+    Ok(cited.answer)
+}
+
+/// Cosine similarity for a search `score` under `distance`, so one
+/// threshold works for every metric and higher is always better. Cosine and
+/// dot product scores already are the similarity of the unit-length vectors
+/// `embed_text` produces. Euclidean scores are distances, and for unit
+/// vectors `cos = 1 - d² / 2`: the default 0.95 cache threshold is a distance
+/// of about 0.316. With embedding normalization turned off only cosine
+/// scores mean this, though the order is still right.
+fn similarity(distance: Distance, score: f32) -> f32 {
+    match distance {
+        Distance::Euclid => 1.0 - score * score / 2.0,
+        _ => score,
+    }
+}
+
+fn point_id_to_string(id: &PointId) -> String {
+    match &id.point_id_options {
+        Some(PointIdOptions::Uuid(uuid)) => uuid.clone(),
+        Some(PointIdOptions::Num(num)) => num.to_string(),
+        None => String::new(),
+    }
+}
+
+/// The unnamed dense vector of a point, empty when it wasn't returned.
+fn dense_vector(vectors: Option<VectorsOutput>) -> Vec<f32> {
+    match vectors.and_then(|v| v.get_vector()) {
+        Some(Vector::Dense(dense)) => dense.data,
+        _ => Vec::new(),
+    }
+}
+
+/// Specialized migration prompt for a user question
+fn migration_prompt(query: &str) -> String {
+    render(Prompt::Migration, &[("question", query)])
+}
+
+fn templated_summary(example_count: usize) -> String {
+    format!(
+        "Found {} relevant ink! smart contract examples matching your query. These examples demonstrate best practices and common patterns in ink! development.",
+        example_count
+    )
+}
+
+/// Search `results` with Qdrant's scores under `distance` turned into
+/// similarities, dropping those below `score_threshold`, best first.
+fn ranked_results(distance: Distance, score_threshold: Option<f32>, mut results: Vec<SearchResult>) -> Vec<SearchResult> {
+    for result in &mut results {
+        result.score = similarity(distance, result.score);
+    }
+    if let Some(threshold) = score_threshold {
+        results.retain(|result| result.score >= threshold);
+    }
+    sort_search_results(&mut results);
+    results
+}
+
+/// Sort by score descending, breaking ties by id ascending.
+fn sort_search_results(results: &mut [SearchResult]) {
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+}
+
+/// Citations for the documents `build_context` turns into context; ones
+/// without a `file_path` can't be traced and are left out.
+fn citations(search_results: &[SearchResult]) -> Vec<Citation> {
+    search_results
+        .iter()
+        .take(CONTEXT_DOCUMENTS)
+        .filter_map(|result| {
+            result.metadata.get("file_path").map(|file_path| Citation {
+                file_path: file_path.clone(),
+                score: result.score,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shuttle_axum::axum::{http::StatusCode, routing::post, Router};
+
+    /// Serve a fake Gemini endpoint that always answers with `status` and `text`.
+    async fn mock_llm_url(status: StatusCode, text: &'static str) -> String {
+        let app = Router::new().route(
+            "/models/{model}",
+            post(move || async move {
+                let body = serde_json::json!({
+                    "candidates": [{"content": {"parts": [{"text": text}]}}]
+                });
+                (status, shuttle_axum::axum::Json(body))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            shuttle_axum::axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    fn test_rag(llm_url: String) -> RAGSystem {
+        RAGSystem {
+            qdrant_client: Qdrant::from_url("http://localhost:6334").build().unwrap(),
+            gemini_client: GeminiClient::with_base_url("test-key".to_string(), llm_url)
+                .with_retry_policy(crate::retry::RetryPolicy { max_attempts: 1, ..Default::default() }),
+            regular_collection: "code_knowledge".to_string(),
+            cache_collection: "code_knowledge_cache".to_string(),
+            fallback: RagFallback::Templated,
+            query_expansion: QueryExpansion::Off,
+            cache_threshold: DEFAULT_CACHE_THRESHOLD,
+            id_generator: std::sync::Arc::new(UuidV4Generator),
+            dead_letters: None,
+            document_limit: DocumentLimit::Unlimited,
+            embedding: EmbeddingConfig::default(),
+            embedder: Embedder::Hash,
+            embedder_status: EmbedderStatus::default(),
+            in_flight: SingleFlight::new(),
+            max_concurrency: 2,
+            qdrant_retry: RetryPolicy { max_attempts: 1, ..Default::default() },
+            max_context_tokens: DEFAULT_MAX_CONTEXT_TOKENS,
+            example_retrieval: ExampleRetrieval::default(),
+            rerank: RerankConfig::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embedding_normalization_can_be_turned_off() {
+        let magnitude = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+
+        let rag = test_rag("http://127.0.0.1:9".to_string());
+        let normalized = rag.embed_text("flipper").await.unwrap();
+        assert!((magnitude(&normalized) - 1.0).abs() < 1e-4);
+
+        let config = EmbeddingConfig { normalize: false, distance: Distance::Dot };
+        let rag = test_rag("http://127.0.0.1:9".to_string()).with_embedding_config(config);
+        let raw = rag.embed_text("flipper").await.unwrap();
+        assert_eq!(raw.len(), 384);
+        assert!((magnitude(&raw) - 1.0).abs() > 1e-2);
+        assert!(config.mismatch_warning().is_none());
+
+        let cosine_raw = EmbeddingConfig { normalize: false, distance: Distance::Cosine };
+        assert!(cosine_raw.mismatch_warning().is_some());
+        assert!(EmbeddingConfig::default().mismatch_warning().is_none());
+    }
+
+    #[test]
+    fn test_oversized_document_is_rejected() {
+        let text = "x".repeat(101);
+        let err = DocumentLimit::Reject(100).apply(&text).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<DocumentTooLarge>(),
+            Some(&DocumentTooLarge { chars: 101, max_chars: 100 })
+        );
+
+        assert_eq!(DocumentLimit::Reject(100).apply("short").unwrap(), vec!["short"]);
+    }
+
+    #[test]
+    fn test_oversized_document_is_chunked() {
+        let text = format!("{}\n{}\n{}", "a".repeat(60), "b".repeat(60), "c".repeat(30));
+        let chunks = DocumentLimit::Chunk(100).apply(&text).unwrap();
+
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks.iter().all(|chunk| chunk.chars().count() <= 100));
+        // Breaks after a newline rather than mid-line
+        assert_eq!(chunks[0], format!("{}\n", "a".repeat(60)));
+        assert_eq!(chunks.concat(), text);
+
+        // A single long line still splits at the limit
+        let line = "z".repeat(250);
+        let chunks = DocumentLimit::Chunk(100).apply(&line).unwrap();
+        assert_eq!(chunks.iter().map(|c| c.len()).collect::<Vec<_>>(), vec![100, 100, 50]);
+    }
+
+    #[tokio::test]
+    async fn test_equivalent_queries_embed_the_same() {
+        let rag = test_rag("http://127.0.0.1:9".to_string());
+        let typed = rag.embed_query("What is ERC20?").await.unwrap();
+        assert_eq!(typed, rag.embed_query("  what is   erc20").await.unwrap());
+        assert_ne!(typed, rag.embed_text("What is ERC20?").await.unwrap());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a scratch Qdrant instance at TEST_QDRANT_URL"]
+    async fn test_equivalent_queries_share_cache_entry() {
+        let qdrant_url = std::env::var("TEST_QDRANT_URL").expect("TEST_QDRANT_URL");
+        let mut rag = test_rag("http://127.0.0.1:9".to_string());
+        rag.qdrant_client = Qdrant::from_url(&qdrant_url).build().unwrap();
+        rag.regular_collection = format!("cache_test_{}", uuid::Uuid::new_v4().simple());
+        rag.cache_collection = format!("{}_cache", rag.regular_collection);
+        rag.initialize_collections().await.unwrap();
+
+        rag.add_to_cache("What is ERC20?", "A fungible token standard").await.unwrap();
+        let hit = rag.search_cache("what is erc20").await.unwrap();
+        assert_eq!(hit.as_deref(), Some("A fungible token standard"));
+
+        for collection in [&rag.regular_collection, &rag.cache_collection] {
+            rag.qdrant_client.delete_collection(collection.as_str()).await.unwrap();
+        }
+    }
+
+    #[test]
+    fn test_dimension_mismatch_is_detected() {
+        use qdrant_client::qdrant::{CollectionConfig, CollectionParams, VectorParams, VectorsConfig};
+
+        let info = |size: u64| CollectionInfo {
+            config: Some(CollectionConfig {
+                params: Some(CollectionParams {
+                    vectors_config: Some(VectorsConfig {
+                        config: Some(VectorsConfigKind::Params(VectorParams { size, ..Default::default() })),
+                    }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        // A collection built by a 768-dimensional model, then redeployed with a 384 one
+        assert_eq!(collection_vector_size(&info(768)), Some(768));
+        assert_eq!(collection_vector_size(&info(EMBEDDING_DIMENSIONS as u64)), Some(384));
+        assert_eq!(collection_vector_size(&CollectionInfo::default()), None);
+
+        let drift = DimensionDrift { collection: "code_knowledge".to_string(), expected: 384, actual: 768 };
+        assert!(drift.to_string().starts_with("Collection 'code_knowledge' stores 768-dimensional vectors but the embedder produces 384"));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a scratch Qdrant instance at TEST_QDRANT_URL"]
+    async fn test_check_reports_drifted_collection() {
+        let qdrant_url = std::env::var("TEST_QDRANT_URL").expect("TEST_QDRANT_URL");
+        let mut rag = test_rag("http://127.0.0.1:9".to_string());
+        rag.qdrant_client = Qdrant::from_url(&qdrant_url).build().unwrap();
+        rag.regular_collection = format!("drift_test_{}", uuid::Uuid::new_v4().simple());
+        rag.cache_collection = format!("{}_cache", rag.regular_collection);
+        rag.qdrant_client
+            .create_collection(
+                CreateCollectionBuilder::new(&rag.regular_collection).vectors_config(VectorParamsBuilder::new(768, Distance::Cosine)),
+            )
+            .await
+            .unwrap();
+
+        let drift = rag.check_collection_dimensions().await.unwrap();
+        assert_eq!(
+            drift,
+            vec![DimensionDrift { collection: rag.regular_collection.clone(), expected: 384, actual: 768 }]
+        );
+
+        rag.qdrant_client.delete_collection(rag.regular_collection.as_str()).await.unwrap();
+    }
+
+    #[test]
+    fn test_euclidean_cache_boundary() {
+        let mut rag = test_rag("http://127.0.0.1:9".to_string());
+        rag.embedding.distance = Distance::Euclid;
+
+        // 0.95 cosine between unit vectors is a distance of sqrt(0.1) ≈ 0.3162
+        assert!(rag.is_cache_hit(0.0));
+        assert!(rag.is_cache_hit(0.31));
+        assert!(!rag.is_cache_hit(0.32));
+        assert!(!rag.is_cache_hit(0.95));
+
+        rag.embedding.distance = Distance::Cosine;
+        assert!(rag.is_cache_hit(0.95));
+        assert!(!rag.is_cache_hit(0.949));
+    }
+
+    #[test]
+    fn test_euclidean_search_ranks_nearest_first() {
+        let distances = vec![result("far", 1.2), result("exact", 0.0), result("near", 0.3), result("opposite", 1.8)];
+        let ranked = ranked_results(Distance::Euclid, Some(0.0), distances);
+
+        // The closest document comes first, and the 0.0 threshold only drops
+        // what cosine would call dissimilar (a distance over sqrt(2))
+        let ids = ranked.iter().map(|r| r.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, vec!["exact", "near", "far"]);
+        assert!((ranked[1].score - 0.955).abs() < 1e-6);
+
+        // Reranking and the example filter see higher-is-better scores too
+        let rag = test_rag("http://127.0.0.1:9".to_string());
+        assert_eq!(ranked.iter().filter(|r| r.score >= rag.example_retrieval.min_score).count(), 2);
+        assert_eq!(rag.rerank(ranked.into_iter().map(|r| (r, Vec::new())).collect(), 1)[0].id, "exact");
+
+        let cosine = ranked_results(Distance::Cosine, Some(0.5), vec![result("low", 0.4), result("high", 0.9)]);
+        assert_eq!(cosine.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), vec!["high"]);
+    }
+
+    /// Serve a fake Gemini endpoint that answers `text` and counts its calls.
+    async fn counting_llm_url(calls: std::sync::Arc<std::sync::atomic::AtomicUsize>, text: &'static str) -> String {
+        let app = Router::new().route(
+            "/models/{model}",
+            post(move || async move {
+                calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                shuttle_axum::axum::Json(serde_json::json!({
+                    "candidates": [{"content": {"parts": [{"text": text}]}}]
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            shuttle_axum::axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_cache_hit_skips_the_model() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Mutex;
+
+        let cache: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
+        let calls = AtomicUsize::new(0);
+        let ask = |query: &'static str, generated: bool| {
+            let (cache, calls) = (&cache, &calls);
+            through_cache(
+                async move { Ok(cache.lock().unwrap().get(&normalize_query(query)).cloned()) },
+                async move {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Ok(CitedAnswer { answer: format!("answer {}", calls.load(Ordering::SeqCst)), citations: Vec::new(), generated })
+                },
+                move |answer| async move {
+                    cache.lock().unwrap().insert(normalize_query(query), answer);
+                    Ok(String::new())
+                },
+            )
+        };
+
+        assert_eq!(ask("How do I store a mapping?", true).await.unwrap(), "answer 1");
+        assert_eq!(ask("how do I store a mapping", true).await.unwrap(), "answer 1");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Fallback answers aren't cached, so the next query tries the model again
+        assert_eq!(ask("What is a flipper?", false).await.unwrap(), "answer 2");
+        assert_eq!(ask("What is a flipper?", true).await.unwrap(), "answer 3");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // A failed lookup is answered as a miss
+        let answer = through_cache(
+            async { Err(anyhow::anyhow!("cache unreachable")) },
+            async { Ok(CitedAnswer { answer: "fresh".to_string(), citations: Vec::new(), generated: true }) },
+            |_| async { Ok(String::new()) },
+        )
+        .await;
+        assert_eq!(answer.unwrap(), "fresh");
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a scratch Qdrant instance at TEST_QDRANT_URL"]
+    async fn test_repeated_query_is_answered_from_cache() {
+        let qdrant_url = std::env::var("TEST_QDRANT_URL").expect("TEST_QDRANT_URL");
+        let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut rag = test_rag(counting_llm_url(calls.clone(), "Use ink::storage::Mapping").await);
+        rag.qdrant_client = Qdrant::from_url(&qdrant_url).build().unwrap();
+        rag.regular_collection = format!("rag_cache_test_{}", uuid::Uuid::new_v4().simple());
+        rag.cache_collection = format!("{}_cache", rag.regular_collection);
+        rag.initialize_collections().await.unwrap();
+        // Hash embeddings only match on identical text, so store the normalized query
+        rag.add_document("how do i store a mapping", HashMap::new()).await.unwrap();
+
+        let first = rag.generate_rag_response("How do I store a mapping?", 3, None).await.unwrap();
+        assert_eq!(first, "Use ink::storage::Mapping");
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        let second = rag.generate_rag_response("how do I store a mapping", 3, None).await.unwrap();
+        assert_eq!(second, first);
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        for collection in [&rag.regular_collection, &rag.cache_collection] {
+            rag.qdrant_client.delete_collection(collection.as_str()).await.unwrap();
+        }
+    }
+
+    #[test]
+    fn test_lower_cache_threshold_turns_near_miss_into_hit() {
+        let rag = test_rag("http://127.0.0.1:9".to_string());
+        assert!(!rag.is_cache_hit(0.93));
+
+        let rag = rag.with_cache_threshold(0.90).unwrap();
+        assert!(rag.is_cache_hit(0.93));
+        assert!(!rag.is_cache_hit(0.85));
+    }
+
+    #[test]
+    fn test_cache_threshold_out_of_range_is_rejected() {
+        for threshold in [0.0, -0.5, 1.01, f32::NAN] {
+            assert!(test_rag("http://127.0.0.1:9".to_string()).with_cache_threshold(threshold).is_err());
+        }
+        assert!(test_rag("http://127.0.0.1:9".to_string()).with_cache_threshold(1.0).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_templated_fallback_when_llm_fails() {
+        let rag = test_rag(mock_llm_url(StatusCode::SERVICE_UNAVAILABLE, "").await);
+        let examples = vec![crate::CodeExample {
+            title: "flipper".to_string(),
+            description: Some("Flips a boolean".to_string()),
+            code: "pub fn flip(&mut self) {}\n".to_string(),
+            source_file: Some("flipper/lib.rs".to_string()),
+            relevance_score: 90.0,
+        }];
+
+        let (response, generated) = rag
+            .answer_or_fallback("How do I flip?", &["raw context".to_string()], &examples)
+            .await
+            .unwrap();
+        assert!(!generated);
+
+        assert!(response.starts_with("I couldn't generate a detailed explanation right now"));
+        assert!(response.contains("### 1. flipper\nFlips a boolean\nSource: flipper/lib.rs\n```rust\npub fn flip(&mut self) {}\n```"));
+        assert!(!response.contains("raw context"));
+    }
+
+    fn flipper_example() -> crate::CodeExample {
+        crate::CodeExample {
+            title: "flipper".to_string(),
+            description: Some("Flips a boolean".to_string()),
+            code: "pub fn flip(&mut self) {}\n".to_string(),
+            source_file: Some("flipper/lib.rs".to_string()),
+            relevance_score: 90.0,
+        }
+    }
+
+    #[test]
+    fn test_explain_lists_documents_and_system_prompt() {
+        let rag = test_rag("http://127.0.0.1:9".to_string());
+        let documents = vec![
+            SearchResult {
+                id: "doc-1".to_string(),
+                content: "mod flipper {\n    pub fn flip(&mut self) {}\n}".to_string(),
+                score: 0.91,
+                metadata: HashMap::from([("file_path".to_string(), "flipper/lib.rs".to_string())]),
+            },
+            SearchResult {
+                id: "doc-2".to_string(),
+                content: "mod erc20 {}".to_string(),
+                score: 0.72,
+                metadata: HashMap::new(),
+            },
+        ];
+
+        let explanation = rag.explanation("How do I flip a flag?", documents);
+
+        let listed: Vec<(&str, f32)> = explanation.documents.iter().map(|d| (d.id.as_str(), d.score)).collect();
+        assert_eq!(listed, vec![("doc-1", 0.91), ("doc-2", 0.72)]);
+        assert_eq!(explanation.context.len(), 2);
+        assert!(explanation.context[0].starts_with("Source: flipper/lib.rs\nContract: flipper\n"));
+        assert!(explanation.prompt.contains(crate::gemini_client::SYSTEM_PROMPT));
+        assert!(explanation.prompt.contains("The user is asking: 'How do I flip a flag?'"));
+        assert!(explanation.prompt.contains(&explanation.context[1]));
+        assert!(explanation.would_call_llm);
+    }
+
+    #[test]
+    fn test_oversized_context_is_trimmed_to_budget() {
+        let document = |id: &str, score: f32| SearchResult {
+            id: id.to_string(),
+            content: format!("// {}\n{}", id, "let value = 1;\n".repeat(40)),
+            score,
+            metadata: HashMap::from([("file_path".to_string(), format!("{}/lib.rs", id))]),
+        };
+        let retrieved = vec![
+            document("flipper", 0.9),
+            document("erc20", 0.4),
+            document("escrow", 0.8),
+            document("multisig", 0.6),
+        ];
+        let prompt = migration_prompt("How do I flip a flag?");
+        let full_rag = test_rag("http://127.0.0.1:9".to_string());
+        let full = full_rag.fit_context_budget(&prompt, &retrieved);
+        assert_eq!(full.len(), 4);
+
+        // Room for the two best documents but not a third
+        let tokens_with = |ids: &[&str]| {
+            let used: Vec<SearchResult> = retrieved.iter().filter(|d| ids.contains(&d.id.as_str())).cloned().collect();
+            estimate_tokens(&crate::gemini_client::build_prompt(&prompt, &full_rag.build_context(&used)))
+        };
+        let budget = tokens_with(&["flipper", "escrow"]);
+        assert!(tokens_with(&["flipper", "escrow", "multisig"]) > budget);
+
+        let rag = test_rag("http://127.0.0.1:9".to_string()).with_max_context_tokens(budget);
+        let used = rag.fit_context_budget(&prompt, &retrieved);
+        assert_eq!(used.iter().map(|d| d.id.as_str()).collect::<Vec<_>>(), vec!["flipper", "escrow"]);
+        assert_eq!(citations(&used).len(), 2);
+        let assembled = crate::gemini_client::build_prompt(&prompt, &rag.build_context(&used));
+        assert!(estimate_tokens(&assembled) <= budget);
+
+        // Nothing fits when the question alone is over budget
+        let tiny = test_rag("http://127.0.0.1:9".to_string()).with_max_context_tokens(10);
+        assert!(tiny.fit_context_budget(&prompt, &retrieved).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_structured_response_example_count_and_relevance() {
+        let document = |id: usize, score: f32| SearchResult {
+            id: format!("doc-{}", id),
+            content: format!("mod example_{} {{}}", id),
+            score,
+            metadata: HashMap::new(),
+        };
+        let retrieved = vec![document(0, 0.9), document(1, 0.8), document(2, 0.7), document(3, 0.6), document(4, 0.1)];
+
+        let retrieval = ExampleRetrieval { count: 4, min_score: 0.3, min_relevant: 2 };
+        let rag = test_rag("http://127.0.0.1:9".to_string()).with_example_retrieval(retrieval);
+        let response = rag.format_structured_response("storage", &retrieved).await;
+        let titles: Vec<&str> = response.examples.iter().map(|e| e.title.as_str()).collect();
+        assert_eq!(titles, vec!["example_0", "example_1", "example_2", "example_3"]);
+        assert!(response.help_text.starts_with("These examples are from"));
+
+        // Only one result clears a stricter minimum score
+        let strict = ExampleRetrieval { min_score: 0.85, ..retrieval };
+        let rag = test_rag("http://127.0.0.1:9".to_string()).with_example_retrieval(strict);
+        let response = rag.format_structured_response("storage", &retrieved).await;
+        assert_eq!(response.examples.len(), 1);
+        assert!(response.help_text.starts_with("Only 1 closely matching example was found. Try a broader query"));
+    }
+
+    #[test]
+    fn test_citations_match_context_documents() {
+        let document = |id: usize, file_path: Option<&str>| SearchResult {
+            id: format!("doc-{}", id),
+            content: "mod example {}".to_string(),
+            score: 1.0 - id as f32 / 10.0,
+            metadata: file_path
+                .map(|path| HashMap::from([("file_path".to_string(), path.to_string())]))
+                .unwrap_or_default(),
+        };
+        let retrieved = vec![
+            document(0, Some("flipper/lib.rs")),
+            document(1, None),
+            document(2, Some("erc20/lib.rs")),
+            document(3, Some("Flipper.sol")),
+            document(4, Some("escrow/lib.rs")),
+            // Beyond the context window, so not used in the answer
+            document(5, Some("dns/lib.rs")),
+        ];
+
+        let cited = citations(&retrieved);
+        let expected: Vec<&str> = retrieved
+            .iter()
+            .take(CONTEXT_DOCUMENTS)
+            .filter_map(|d| d.metadata.get("file_path").map(String::as_str))
+            .collect();
+        assert_eq!(cited.iter().map(|c| c.file_path.as_str()).collect::<Vec<_>>(), expected);
+        assert_eq!(cited[0], Citation { file_path: "flipper/lib.rs".to_string(), score: 1.0 });
+        assert_eq!(cited[1].score, retrieved[2].score);
+    }
+
+    #[tokio::test]
+    async fn test_structured_summary_comes_from_llm() {
+        let rag = test_rag(mock_llm_url(StatusCode::OK, "Store the flag in a bool and negate it in a message.").await);
+
+        let summary = rag.summarize_examples("How do I flip a flag?", &[flipper_example()]).await;
+        assert_eq!(summary, "Store the flag in a bool and negate it in a message.");
+        assert!(!summary.starts_with("Found 1 relevant"));
+    }
+
+    #[tokio::test]
+    async fn test_structured_summary_falls_back_to_template() {
+        let rag = test_rag(mock_llm_url(StatusCode::SERVICE_UNAVAILABLE, "").await);
+
+        let summary = rag.summarize_examples("How do I flip a flag?", &[flipper_example()]).await;
+        assert_eq!(summary, templated_summary(1));
+    }
+
+    #[tokio::test]
+    async fn test_llm_query_expansion_broadens_query() {
+        let mut rag = test_rag(mock_llm_url(StatusCode::OK, "Mapping, storage, HashMap").await);
+        rag.query_expansion = QueryExpansion::Llm;
+
+        let expanded = rag.expand_query("mapping").await;
+        assert_ne!(expanded, "mapping");
+        assert!(expanded.starts_with("mapping "));
+        assert!(expanded.contains("storage"));
+        assert!(expanded.contains("HashMap"));
+
+        rag.query_expansion = QueryExpansion::Off;
+        assert_eq!(rag.expand_query("mapping").await, "mapping");
+    }
+
+    #[tokio::test]
+    async fn test_llm_query_expansion_falls_back_to_synonyms() {
+        let mut rag = test_rag(mock_llm_url(StatusCode::SERVICE_UNAVAILABLE, "").await);
+        rag.query_expansion = QueryExpansion::Llm;
+
+        let expanded = rag.expand_query("how do events work").await;
+        assert!(expanded.contains("#[ink(event)]"));
+    }
+
+    #[test]
+    fn test_collection_breakdown_counts_by_metadata() {
+        let docs: Vec<HashMap<&str, &str>> = vec![
+            HashMap::from([("contract_type", "ERC20"), ("language", "ink"), ("source", "examples")]),
+            HashMap::from([("contract_type", "ERC20"), ("language", "solidity"), ("source", "examples")]),
+            HashMap::from([("contract_type", "ERC721"), ("language", "ink"), ("source", "training")]),
+            HashMap::from([("language", "ink")]),
+        ];
+
+        let mut breakdown = CollectionBreakdown::default();
+        for doc in &docs {
+            breakdown.add(|key| doc.get(key).copied());
+        }
+
+        assert_eq!(breakdown.total, 4);
+        assert_eq!(breakdown.by_contract_type["ERC20"], 2);
+        assert_eq!(breakdown.by_contract_type["ERC721"], 1);
+        assert_eq!(breakdown.by_contract_type["unknown"], 1);
+        assert_eq!(breakdown.by_language["ink"], 3);
+        assert_eq!(breakdown.by_source["examples"], 2);
+    }
+
+    #[test]
+    fn test_payload_filter_combines_metadata_and_tenant() {
+        assert!(payload_filter(None, &HashMap::new()).is_none());
+        let metadata = HashMap::from([("contract_type".to_string(), "ERC721".to_string())]);
+        assert_eq!(
+            payload_filter(Some("team-a"), &metadata),
+            Some(Filter::must([
+                Condition::matches("contract_type", "ERC721".to_string()),
+                tenant::visible_to("team-a").into(),
+            ]))
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a scratch Qdrant instance at TEST_QDRANT_URL"]
+    async fn test_metadata_filter_narrows_search() {
+        let qdrant_url = std::env::var("TEST_QDRANT_URL").expect("TEST_QDRANT_URL");
+        let metadata = HashMap::from([("contract_type".to_string(), "ERC721".to_string())]);
+        let mut rag = test_rag("http://127.0.0.1:9".to_string());
+        rag.qdrant_client = Qdrant::from_url(&qdrant_url).build().unwrap();
+        rag.regular_collection = format!("filter_test_{}", uuid::Uuid::new_v4().simple());
+        rag.cache_collection = format!("{}_cache", rag.regular_collection);
+        rag.initialize_collections().await.unwrap();
+
+        let typed = |contract_type: &str| {
+            HashMap::from([
+                ("contract_type".to_string(), contract_type.to_string()),
+                ("language".to_string(), "ink".to_string()),
+            ])
+        };
+        rag.add_document("erc721 mint token", typed("ERC721")).await.unwrap();
+        rag.add_document("erc721 burn token", typed("ERC721")).await.unwrap();
+        rag.add_document("erc20 transfer token", typed("ERC20")).await.unwrap();
+
+        let results = rag.search_documents_matching("token", 10, None, None, &metadata).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.metadata["contract_type"] == "ERC721"));
+
+        // Every key must match
+        let none_match = HashMap::from([
+            ("contract_type".to_string(), "ERC20".to_string()),
+            ("language".to_string(), "solidity".to_string()),
+        ]);
+        assert!(rag.search_documents_matching("token", 10, None, None, &none_match).await.unwrap().is_empty());
+        assert_eq!(rag.search_documents("token", 10, None, None).await.unwrap().len(), 3);
+
+        for collection in [&rag.regular_collection, &rag.cache_collection] {
+            rag.qdrant_client.delete_collection(collection.as_str()).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a scratch Qdrant instance at TEST_QDRANT_URL"]
+    async fn test_tenants_are_isolated() {
+        let qdrant_url = std::env::var("TEST_QDRANT_URL").expect("TEST_QDRANT_URL");
+        let mut rag = test_rag("http://127.0.0.1:9".to_string());
+        rag.qdrant_client = Qdrant::from_url(&qdrant_url).build().unwrap();
+        rag.regular_collection = format!("tenant_test_{}", uuid::Uuid::new_v4().simple());
+        rag.cache_collection = format!("{}_cache", rag.regular_collection);
+        rag.initialize_collections().await.unwrap();
+
+        let tenant_doc = |tenant: &str| HashMap::from([(tenant::TENANT_FIELD.to_string(), tenant.to_string())]);
+        rag.add_document("team a escrow notes", tenant_doc("team-a")).await.unwrap();
+        rag.add_document("team b token notes", tenant_doc("team-b")).await.unwrap();
+        rag.add_document("shared flipper example", HashMap::new()).await.unwrap();
+
+        // Even an exact match on B's text is not returned to A
+        let results = rag.search_documents("team b token notes", 10, None, Some("team-a")).await.unwrap();
+        let contents: Vec<&str> = results.iter().map(|r| r.content.as_str()).collect();
+        assert!(contents.contains(&"team a escrow notes"));
+        assert!(contents.contains(&"shared flipper example"));
+        assert!(!contents.contains(&"team b token notes"));
+
+        let results = rag.search_documents("team b token notes", 10, None, None).await.unwrap();
+        assert_eq!(results.len(), 3);
+
+        let stats_a = rag.get_collection_stats(Some("team-a")).await.unwrap();
+        assert_eq!(stats_a, HashMap::from([("tenant_documents".to_string(), 1)]));
+        assert_eq!(rag.collection_breakdown(Some("team-b")).await.unwrap().total, 1);
+        assert_eq!(rag.get_collection_stats(None).await.unwrap()["regular_documents"], 3);
+
+        for collection in [&rag.regular_collection, &rag.cache_collection] {
+            rag.qdrant_client.delete_collection(collection.as_str()).await.unwrap();
+        }
+    }
+
+    #[test]
+    fn test_parse_export_names_bad_line() {
+        let line = r#"{"id":"7","content":"flipper","metadata":{"source":"ink"},"vector":[0.5,0.5]}"#;
+        let points = parse_export(&format!("{}\n\n", line)).unwrap();
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].metadata["source"], "ink");
+
+        let error = parse_export(&format!("{}\nnot json\n", line)).unwrap_err();
+        assert!(error.starts_with("Line 2:"), "{}", error);
+        let error = parse_export(r#"{"id":"8","content":"x","metadata":{},"vector":[]}"#).unwrap_err();
+        assert_eq!(error, "Line 1: point 8 has no vector");
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a scratch Qdrant instance at TEST_QDRANT_URL"]
+    async fn test_export_then_import_into_fresh_store() {
+        let qdrant_url = std::env::var("TEST_QDRANT_URL").expect("TEST_QDRANT_URL");
+        let scratch_rag = || {
+            let mut rag = test_rag("http://127.0.0.1:9".to_string());
+            rag.qdrant_client = Qdrant::from_url(&qdrant_url).build().unwrap();
+            rag.regular_collection = format!("export_test_{}", uuid::Uuid::new_v4().simple());
+            rag.cache_collection = format!("{}_cache", rag.regular_collection);
+            rag
+        };
+        let source = scratch_rag();
+        let target = scratch_rag();
+        source.initialize_collections().await.unwrap();
+        target.initialize_collections().await.unwrap();
+
+        for (text, contract_type) in [("erc20 transfer", "ERC20"), ("flipper flip", "Flipper"), ("escrow release", "Escrow")] {
+            let metadata = HashMap::from([("contract_type".to_string(), contract_type.to_string())]);
+            source.add_document(text, metadata).await.unwrap();
+        }
+
+        // Small channel, so the export has to wait on the reader
+        let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+        let export = tokio::spawn(async move {
+            source.export_documents(None, tx).await;
+            source
+        });
+        let mut jsonl = String::new();
+        while let Some(point) = rx.recv().await {
+            jsonl.push_str(&serde_json::to_string(&point.unwrap()).unwrap());
+            jsonl.push('\n');
+        }
+        let source = export.await.unwrap();
+
+        let points = parse_export(&jsonl).unwrap();
+        assert_eq!(points.len(), 3);
+        assert!(points.iter().all(|p| p.vector.len() == 384 && p.metadata.contains_key("timestamp")));
+        assert_eq!(target.import_documents(&points, None).await.unwrap(), 3);
+
+        let stats = target.get_collection_stats(None).await.unwrap();
+        assert_eq!(stats["regular_documents"], 3);
+        assert_eq!(target.collection_breakdown(None).await.unwrap(), source.collection_breakdown(None).await.unwrap());
+        let results = target.search_documents("flipper flip", 1, None, None).await.unwrap();
+        assert_eq!(results[0].content, "flipper flip");
+
+        for rag in [&source, &target] {
+            for collection in [&rag.regular_collection, &rag.cache_collection] {
+                rag.qdrant_client.delete_collection(collection.as_str()).await.unwrap();
+            }
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a scratch Qdrant instance at TEST_QDRANT_URL"]
+    async fn test_export_and_import_are_tenant_scoped() {
+        let qdrant_url = std::env::var("TEST_QDRANT_URL").expect("TEST_QDRANT_URL");
+        let mut rag = test_rag("http://127.0.0.1:9".to_string());
+        rag.qdrant_client = Qdrant::from_url(&qdrant_url).build().unwrap();
+        rag.regular_collection = format!("tenant_export_test_{}", uuid::Uuid::new_v4().simple());
+        rag.cache_collection = format!("{}_cache", rag.regular_collection);
+        rag.initialize_collections().await.unwrap();
+
+        let tenant_doc = |tenant: &str| HashMap::from([(tenant::TENANT_FIELD.to_string(), tenant.to_string())]);
+        rag.add_document("team a escrow notes", tenant_doc("team-a")).await.unwrap();
+        rag.add_document("team b token notes", tenant_doc("team-b")).await.unwrap();
+        let shared_id = rag.add_document("shared flipper example", HashMap::new()).await.unwrap();
+
+        let export = |tenant: Option<&'static str>| {
+            let rag = &rag;
+            async move {
+                let (tx, mut rx) = tokio::sync::mpsc::channel(EXPORT_PAGE_SIZE as usize);
+                rag.export_documents(tenant, tx).await;
+                let mut points = Vec::new();
+                while let Some(point) = rx.recv().await {
+                    points.push(point.unwrap());
+                }
+                points
+            }
+        };
+        let exported = export(Some("team-a")).await;
+        assert_eq!(exported.len(), 1);
+        assert_eq!(exported[0].content, "team a escrow notes");
+        assert_eq!(export(None).await.len(), 3);
+
+        // A forged tenant_id in the file is replaced by the importer's
+        let mut forged = exported[0].clone();
+        forged.id = uuid::Uuid::new_v4().to_string();
+        forged.metadata.insert(tenant::TENANT_FIELD.to_string(), "team-a".to_string());
+        assert_eq!(rag.import_documents(&[forged], Some("team-b")).await.unwrap(), 1);
+        assert_eq!(rag.collection_breakdown(Some("team-b")).await.unwrap().total, 2);
+        assert_eq!(rag.collection_breakdown(Some("team-a")).await.unwrap().total, 1);
+
+        // Reusing another tenant's or a shared point's id is refused
+        let mut hijack = exported[0].clone();
+        hijack.id = shared_id;
+        let error = rag.import_documents(&[exported[0].clone(), hijack], Some("team-b")).await.unwrap_err();
+        assert_eq!(error.downcast_ref::<ForeignPoints>(), Some(&ForeignPoints { count: 2 }));
+        assert_eq!(export(None).await.len(), 4);
+
+        for collection in [&rag.regular_collection, &rag.cache_collection] {
+            rag.qdrant_client.delete_collection(collection.as_str()).await.unwrap();
+        }
+    }
+
+    fn result(id: &str, score: f32) -> SearchResult {
+        SearchResult {
+            id: id.to_string(),
+            content: format!("doc {}", id),
+            score,
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rerank_removes_duplicates_from_context() {
+        let rag = test_rag("http://127.0.0.1:9".to_string());
+        let blurb = "ink! is an eDSL for writing smart contracts in Rust.";
+        let candidates = vec![
+            (SearchResult { content: blurb.to_string(), ..result("docs-1", 0.9) }, rag.embed_text(blurb).await.unwrap()),
+            (SearchResult { content: blurb.to_string(), ..result("docs-2", 0.89) }, rag.embed_text(blurb).await.unwrap()),
+            (result("flipper", 0.8), rag.embed_text("mod flipper { fn flip() }").await.unwrap()),
+            (result("erc20", 0.7), rag.embed_text("mod erc20 { fn transfer() }").await.unwrap()),
+        ];
+
+        let reranked = rag.rerank(candidates.clone(), 3);
+        let ids: Vec<&str> = reranked.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["docs-1", "flipper", "erc20"]);
+        let context = rag.build_context(&reranked);
+        assert_eq!(context.iter().filter(|c| c.contains(blurb)).count(), 1);
+
+        // Without the threshold the duplicate takes a slot
+        let lenient = test_rag("http://127.0.0.1:9".to_string())
+            .with_rerank(RerankConfig { duplicate_threshold: 1.1, mmr_lambda: None });
+        assert_eq!(lenient.rerank(candidates, 3)[1].id, "docs-2");
+    }
+
+    #[test]
+    fn test_mmr_prefers_diverse_documents() {
+        let candidates = vec![
+            (result("a", 0.9), vec![1.0, 0.0]),
+            (result("a-variant", 0.88), vec![0.9, 0.1]),
+            (result("b", 0.7), vec![0.0, 1.0]),
+        ];
+        let relevance_only = test_rag("http://127.0.0.1:9".to_string())
+            .with_rerank(RerankConfig { duplicate_threshold: 0.999, mmr_lambda: None });
+        let ids = |results: Vec<SearchResult>| results.into_iter().map(|r| r.id).collect::<Vec<_>>();
+        assert_eq!(ids(relevance_only.rerank(candidates.clone(), 2)), vec!["a", "a-variant"]);
+
+        let mmr = test_rag("http://127.0.0.1:9".to_string())
+            .with_rerank(RerankConfig { duplicate_threshold: 0.999, mmr_lambda: Some(0.5) });
+        assert_eq!(ids(mmr.rerank(candidates, 2)), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_equal_scores_are_ordered_by_id() {
+        for _ in 0..5 {
+            let mut forward = vec![result("a", 0.5), result("b", 0.5), result("c", 0.9)];
+            let mut reversed = vec![result("b", 0.5), result("a", 0.5), result("c", 0.9)];
+            sort_search_results(&mut forward);
+            sort_search_results(&mut reversed);
+
+            let ids = |r: &[SearchResult]| r.iter().map(|x| x.id.clone()).collect::<Vec<_>>();
+            assert_eq!(ids(&forward), vec!["c", "a", "b"]);
+            assert_eq!(ids(&forward), ids(&reversed));
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use shuttle_axum::axum::http::{HeaderMap, HeaderValue};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Drop expired buckets once this many callers are tracked
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// Fixed-window request quota per caller for the LLM-backed routes.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    limit: u32,
+    window: Duration,
+    buckets: Arc<Mutex<HashMap<String, Bucket>>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    used: u32,
+    reset_at: Instant,
+}
+
+/// Where a caller stands after a request was counted against its bucket.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quota {
+    pub limit: u32,
+    pub remaining: u32,
+    /// Seconds until the window resets, rounded up
+    pub reset_secs: u64,
+    /// False when the request was over the limit and should be rejected
+    pub allowed: bool,
+}
+
+impl Quota {
+    /// `X-RateLimit-Limit`, `X-RateLimit-Remaining` and `X-RateLimit-Reset`.
+    pub fn apply(&self, headers: &mut HeaderMap) {
+        headers.insert("x-ratelimit-limit", HeaderValue::from(self.limit));
+        headers.insert("x-ratelimit-remaining", HeaderValue::from(self.remaining));
+        headers.insert("x-ratelimit-reset", HeaderValue::from(self.reset_secs));
+    }
+}
+
+impl RateLimiter {
+    pub fn new(limit: u32, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Read `LLM_RATE_LIMIT` requests per `LLM_RATE_LIMIT_WINDOW_SECS`
+    /// (default 60s). `None` when no limit is set, leaving the routes unlimited.
+    pub fn from_env() -> Option<Self> {
+        let limit = std::env::var("LLM_RATE_LIMIT").ok()?.parse().ok()?;
+        let window = std::env::var("LLM_RATE_LIMIT_WINDOW_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60u64);
+        Some(Self::new(limit, Duration::from_secs(window.max(1))))
+    }
+
+    /// Count a request against `caller`'s bucket.
+    pub fn check(&self, caller: &str) -> Quota {
+        self.check_at(caller, Instant::now())
+    }
+
+    fn check_at(&self, caller: &str, now: Instant) -> Quota {
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= PRUNE_THRESHOLD {
+            buckets.retain(|_, bucket| bucket.reset_at > now);
+        }
+
+        let bucket = buckets.entry(caller.to_string()).or_insert(Bucket {
+            used: 0,
+            reset_at: now + self.window,
+        });
+        if bucket.reset_at <= now {
+            *bucket = Bucket {
+                used: 0,
+                reset_at: now + self.window,
+            };
+        }
+
+        let allowed = bucket.used < self.limit;
+        if allowed {
+            bucket.used += 1;
+        }
+        let until_reset = bucket.reset_at - now;
+
+        Quota {
+            limit: self.limit,
+            remaining: self.limit - bucket.used,
+            reset_secs: until_reset.as_secs() + u64::from(until_reset.subsec_nanos() > 0),
+            allowed,
+        }
+    }
+}
+
+/// The bucket a request is counted in: its authenticated tenant when
+/// multi-tenancy is on, otherwise the peer address of the connection.
+/// Neither can be picked by the client. Callers with neither, as when the
+/// server isn't given connection info, share one deployment-wide bucket.
+pub fn caller_key(tenant: Option<&str>, peer: Option<IpAddr>) -> String {
+    match (tenant, peer) {
+        (Some(tenant), _) => format!("tenant:{}", tenant),
+        (None, Some(peer)) => format!("ip:{}", peer),
+        (None, None) => "shared".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remaining_decrements_until_window_resets() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+        let start = Instant::now();
+
+        let first = limiter.check_at("alice", start);
+        assert_eq!(first, Quota { limit: 2, remaining: 1, reset_secs: 60, allowed: true });
+        let second = limiter.check_at("alice", start + Duration::from_millis(1500));
+        assert_eq!(second, Quota { limit: 2, remaining: 0, reset_secs: 59, allowed: true });
+        let third = limiter.check_at("alice", start + Duration::from_secs(2));
+        assert_eq!(third, Quota { limit: 2, remaining: 0, reset_secs: 58, allowed: false });
+
+        // Callers don't share buckets, and a new window starts full
+        assert_eq!(limiter.check_at("bob", start + Duration::from_secs(2)).remaining, 1);
+        let next_window = limiter.check_at("alice", start + Duration::from_secs(60));
+        assert_eq!(next_window, Quota { limit: 2, remaining: 1, reset_secs: 60, allowed: true });
+    }
+
+    #[test]
+    fn test_caller_key_prefers_tenant() {
+        let peer: IpAddr = "203.0.113.7".parse().unwrap();
+        assert_eq!(caller_key(Some("team-a"), Some(peer)), "tenant:team-a");
+        assert_eq!(caller_key(None, Some(peer)), "ip:203.0.113.7");
+        assert_eq!(caller_key(None, None), "shared");
+    }
+}
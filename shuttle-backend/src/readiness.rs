@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SubsystemStatus {
+    pub name: String,
+    pub ready: bool,
+    pub error: Option<String>,
+}
+
+/// Initialization status of each subsystem, collected during startup.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct Readiness {
+    pub subsystems: Vec<SubsystemStatus>,
+}
+
+impl Readiness {
+    pub fn record<E: Display>(&mut self, name: &str, result: Result<(), E>) {
+        let error = match result {
+            Ok(()) => {
+                info!("{} initialized", name);
+                None
+            }
+            Err(e) => {
+                warn!("Warning: Failed to initialize {}: {}", name, e);
+                Some(e.to_string())
+            }
+        };
+
+        self.subsystems.push(SubsystemStatus {
+            name: name.to_string(),
+            ready: error.is_none(),
+            error,
+        });
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.subsystems.iter().all(|s| s.ready)
+    }
+
+    /// Fail when any subsystem is down and `require_all` is set; otherwise
+    /// the server comes up degraded.
+    pub fn check(&self, require_all: bool) -> Result<(), String> {
+        if !require_all || self.is_ready() {
+            return Ok(());
+        }
+
+        let failed = self
+            .subsystems
+            .iter()
+            .filter(|s| !s.ready)
+            .map(|s| format!("{} ({})", s.name, s.error.as_deref().unwrap_or("unknown error")))
+            .collect::<Vec<_>>();
+
+        Err(format!("Required services failed to initialize: {}", failed.join(", ")))
+    }
+}
+
+/// Whether `REQUIRE_ALL_SERVICES=true` is set.
+pub fn require_all_services() -> bool {
+    std::env::var("REQUIRE_ALL_SERVICES")
+        .map(|v| v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strict_mode_fails_on_failed_subsystem() {
+        let mut readiness = Readiness::default();
+        readiness.record::<String>("chat_collection", Ok(()));
+        readiness.record("rag_collections", Err("connection refused"));
+
+        assert!(!readiness.is_ready());
+        assert!(readiness.check(false).is_ok());
+
+        let err = readiness.check(true).unwrap_err();
+        assert!(err.contains("rag_collections (connection refused)"));
+        assert!(!err.contains("chat_collection"));
+    }
+
+    #[test]
+    fn test_all_ready_passes_strict_mode() {
+        let mut readiness = Readiness::default();
+        readiness.record::<String>("chat_collection", Ok(()));
+        assert!(readiness.check(true).is_ok());
+    }
+}
@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::hyperbridge::StrategyRecommendation;
+
+/// Trades smaller than this many USD are skipped unless the request sets its own
+pub const DEFAULT_MIN_TRADE_USD: f64 = 10.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Holding {
+    /// e.g. `Acala aUSD/DOT`, as returned in a plan's `target`
+    pub asset: String,
+    pub amount_usd: f64,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RebalanceRequest {
+    pub holdings: Vec<Holding>,
+    pub risk_level: u8,
+    /// Skip trades below this USD value; defaults to `DEFAULT_MIN_TRADE_USD`
+    pub min_trade_usd: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct TargetAllocation {
+    pub asset: String,
+    /// Share of the portfolio, summing to 100 across the target
+    pub percentage: f64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TradeAction {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct RebalanceStep {
+    pub asset: String,
+    pub action: TradeAction,
+    /// USD value to buy or sell
+    pub amount: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RebalancePlan {
+    pub total_value_usd: f64,
+    pub target: Vec<TargetAllocation>,
+    /// Sells first, so they fund the buys; largest trades first within each
+    pub steps: Vec<RebalanceStep>,
+}
+
+/// Name under which a recommendation is bought and sold.
+pub fn asset_name(recommendation: &StrategyRecommendation) -> String {
+    format!("{} {}", recommendation.protocol, recommendation.token_pair)
+}
+
+/// Target shares from recommended allocations, scaled to sum to 100 since
+/// the recommendations' own percentages need not.
+pub fn target_allocations(recommendations: &[StrategyRecommendation]) -> Vec<TargetAllocation> {
+    let total: f64 = recommendations.iter().map(|r| r.allocation_percentage.max(0.0)).sum();
+    if total <= 0.0 {
+        return Vec::new();
+    }
+
+    let mut target: Vec<TargetAllocation> = Vec::new();
+    for recommendation in recommendations {
+        let asset = asset_name(recommendation);
+        let percentage = recommendation.allocation_percentage.max(0.0) / total * 100.0;
+        match target.iter_mut().find(|t| t.asset == asset) {
+            Some(existing) => existing.percentage += percentage,
+            None => target.push(TargetAllocation { asset, percentage }),
+        }
+    }
+    target
+}
+
+/// Trades that move `holdings` to `target` at the same total value. Assets
+/// held but not in the target are sold off, and any delta under
+/// `min_trade_usd` is left alone to avoid dust moves.
+pub fn rebalance(holdings: &[Holding], target: &[TargetAllocation], min_trade_usd: f64) -> RebalancePlan {
+    let total_value_usd: f64 = holdings.iter().map(|h| h.amount_usd).sum();
+    let held = |asset: &str| holdings.iter().filter(|h| h.asset == asset).map(|h| h.amount_usd).sum::<f64>();
+
+    let mut deltas: Vec<(String, f64)> = target
+        .iter()
+        .map(|t| (t.asset.clone(), total_value_usd * t.percentage / 100.0 - held(&t.asset)))
+        .collect();
+    for holding in holdings {
+        if !target.iter().any(|t| t.asset == holding.asset) && !deltas.iter().any(|(asset, _)| asset == &holding.asset) {
+            deltas.push((holding.asset.clone(), -held(&holding.asset)));
+        }
+    }
+
+    let mut steps: Vec<RebalanceStep> = deltas
+        .into_iter()
+        .filter(|(_, delta)| delta.abs() >= min_trade_usd && delta.abs() > 0.0)
+        .map(|(asset, delta)| RebalanceStep {
+            asset,
+            action: if delta < 0.0 { TradeAction::Sell } else { TradeAction::Buy },
+            amount: (delta.abs() * 100.0).round() / 100.0,
+        })
+        .collect();
+    steps.sort_by(|a, b| {
+        (a.action == TradeAction::Buy)
+            .cmp(&(b.action == TradeAction::Buy))
+            .then(b.amount.total_cmp(&a.amount))
+    });
+
+    RebalancePlan {
+        total_value_usd,
+        target: target.to_vec(),
+        steps,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recommendation(protocol: &str, pair: &str, percentage: f64) -> StrategyRecommendation {
+        StrategyRecommendation {
+            protocol: protocol.to_string(),
+            chain: "Polkadot".to_string(),
+            token_pair: pair.to_string(),
+            allocation_percentage: percentage,
+            allocated_amount: 0.0,
+            expected_apy: 10.0,
+            risk_score: 4,
+            reasoning: String::new(),
+        }
+    }
+
+    fn holding(asset: &str, amount_usd: f64) -> Holding {
+        Holding { asset: asset.to_string(), amount_usd }
+    }
+
+    #[test]
+    fn test_skewed_portfolio_moves_toward_target() {
+        // Recommended 30/30 becomes a 50/50 target
+        let target = target_allocations(&[recommendation("Acala", "aUSD/DOT", 30.0), recommendation("Bifrost", "vDOT", 30.0)]);
+        assert_eq!(target.iter().map(|t| t.percentage).collect::<Vec<_>>(), vec![50.0, 50.0]);
+
+        let holdings = vec![holding("Acala aUSD/DOT", 900.0), holding("Bifrost vDOT", 95.0), holding("Legacy LP", 5.0)];
+        let plan = rebalance(&holdings, &target, DEFAULT_MIN_TRADE_USD);
+        assert_eq!(plan.total_value_usd, 1000.0);
+        assert_eq!(
+            plan.steps,
+            vec![
+                RebalanceStep { asset: "Acala aUSD/DOT".to_string(), action: TradeAction::Sell, amount: 400.0 },
+                RebalanceStep { asset: "Bifrost vDOT".to_string(), action: TradeAction::Buy, amount: 405.0 },
+            ]
+        );
+
+        // Every step shrinks that asset's distance to its target
+        for step in &plan.steps {
+            let before = holdings.iter().find(|h| h.asset == step.asset).unwrap().amount_usd;
+            let after = match step.action {
+                TradeAction::Buy => before + step.amount,
+                TradeAction::Sell => before - step.amount,
+            };
+            assert!((after - 500.0).abs() < (before - 500.0).abs());
+        }
+    }
+
+    #[test]
+    fn test_dust_deltas_are_skipped() {
+        let target = target_allocations(&[recommendation("Acala", "aUSD/DOT", 50.0), recommendation("Bifrost", "vDOT", 50.0)]);
+        let plan = rebalance(&[holding("Acala aUSD/DOT", 504.0), holding("Bifrost vDOT", 496.0)], &target, 10.0);
+        assert!(plan.steps.is_empty());
+
+        let plan = rebalance(&[holding("Acala aUSD/DOT", 504.0), holding("Bifrost vDOT", 496.0)], &target, 1.0);
+        assert_eq!(plan.steps.len(), 2);
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::hash_map::DefaultHasher;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::contract_matcher::ContractPair;
+use crate::training_embedder::EmbeddingResult;
+
+/// Parse a reindex interval: plain seconds or a number with an `s`, `m`,
+/// `h` or `d` suffix, e.g. `900`, `15m`, `6h`. Zero disables the schedule.
+pub fn parse_interval(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (number, unit_secs) = match value.char_indices().last()? {
+        (i, 's') => (&value[..i], 1),
+        (i, 'm') => (&value[..i], 60),
+        (i, 'h') => (&value[..i], 60 * 60),
+        (i, 'd') => (&value[..i], 24 * 60 * 60),
+        _ => (value, 1),
+    };
+    let secs = number.trim().parse::<u64>().ok()?.checked_mul(unit_secs)?;
+    (secs > 0).then_some(Duration::from_secs(secs))
+}
+
+/// Interval from `REINDEX_INTERVAL`; `None` leaves scheduled reindexing off.
+pub fn interval_from_env() -> Option<Duration> {
+    std::env::var("REINDEX_INTERVAL").ok().as_deref().and_then(parse_interval)
+}
+
+/// Hash of every pair's root, type and source files, so a run can tell
+/// whether anything changed since the last one.
+pub fn pairs_fingerprint(pairs: &[(String, ContractPair)]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for (root, pair) in pairs {
+        root.hash(&mut hasher);
+        pair.contract_type.hash(&mut hasher);
+        pair.solidity_content.hash(&mut hasher);
+        pair.ink_content.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// What one scheduled run did.
+#[derive(Debug)]
+pub enum ScheduledRun {
+    /// Example files are unchanged since the last successful run
+    Unchanged,
+    /// Another scheduled run is still in progress
+    AlreadyRunning,
+    Completed(EmbeddingResult),
+    Failed(String),
+}
+
+/// Runs scheduled incremental reindexes one at a time, skipping them when
+/// the example files haven't changed since the last successful run.
+#[derive(Default)]
+pub struct ReindexScheduler {
+    running: tokio::sync::Mutex<()>,
+    last_fingerprint: Mutex<Option<u64>>,
+}
+
+impl ReindexScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `reindex` for a tree with the given fingerprint, unless it's
+    /// unchanged or a run is already active. Failed runs don't record the
+    /// fingerprint, so the next run tries again.
+    pub async fn run<F, Fut>(&self, fingerprint: u64, reindex: F) -> ScheduledRun
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<EmbeddingResult, String>>,
+    {
+        let Ok(_running) = self.running.try_lock() else {
+            return ScheduledRun::AlreadyRunning;
+        };
+        if *self.last_fingerprint.lock().unwrap() == Some(fingerprint) {
+            return ScheduledRun::Unchanged;
+        }
+
+        match reindex().await {
+            Ok(result) => {
+                *self.last_fingerprint.lock().unwrap() = Some(fingerprint);
+                ScheduledRun::Completed(result)
+            }
+            Err(e) => ScheduledRun::Failed(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn pair(contract_type: &str, solidity: &str) -> (String, ContractPair) {
+        (
+            "official".to_string(),
+            ContractPair {
+                solidity_path: format!("{}.sol", contract_type),
+                ink_path: format!("{}/lib.rs", contract_type),
+                contract_type: contract_type.to_string(),
+                description: String::new(),
+                solidity_content: solidity.to_string(),
+                ink_content: "#[ink::contract]\nmod c {}".to_string(),
+            },
+        )
+    }
+
+    async fn counted_run(scheduler: &ReindexScheduler, fingerprint: u64, runs: &AtomicUsize) -> ScheduledRun {
+        scheduler
+            .run(fingerprint, || async {
+                runs.fetch_add(1, Ordering::SeqCst);
+                Ok(EmbeddingResult {
+                    success: true,
+                    processed_pairs: 1,
+                    added: 1,
+                    updated: 0,
+                    skipped: 0,
+                    document_ids: vec!["doc-1".to_string()],
+                    errors: vec![],
+                })
+            })
+            .await
+    }
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("900"), Some(Duration::from_secs(900)));
+        assert_eq!(parse_interval("15m"), Some(Duration::from_secs(15 * 60)));
+        assert_eq!(parse_interval(" 6h "), Some(Duration::from_secs(6 * 60 * 60)));
+        assert_eq!(parse_interval("1d"), Some(Duration::from_secs(24 * 60 * 60)));
+        assert_eq!(parse_interval("0"), None);
+        assert_eq!(parse_interval("hourly"), None);
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_reindexes_only_when_files_change() {
+        let scheduler = ReindexScheduler::new();
+        let runs = AtomicUsize::new(0);
+        let before = pairs_fingerprint(&[pair("Flipper", "contract Flipper {}")]);
+
+        assert!(matches!(counted_run(&scheduler, before, &runs).await, ScheduledRun::Completed(r) if r.added == 1));
+        assert!(matches!(counted_run(&scheduler, before, &runs).await, ScheduledRun::Unchanged));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        let after = pairs_fingerprint(&[pair("Flipper", "contract Flipper { bool value; }")]);
+        assert_ne!(before, after);
+        assert!(matches!(counted_run(&scheduler, after, &runs).await, ScheduledRun::Completed(_)));
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_run_is_retried_and_runs_do_not_overlap() {
+        let scheduler = ReindexScheduler::new();
+        let runs = AtomicUsize::new(0);
+
+        let failed = scheduler.run(7, || async { Err("qdrant unavailable".to_string()) }).await;
+        assert!(matches!(failed, ScheduledRun::Failed(e) if e == "qdrant unavailable"));
+        assert!(matches!(counted_run(&scheduler, 7, &runs).await, ScheduledRun::Completed(_)));
+
+        let _active = scheduler.running.try_lock().unwrap();
+        assert!(matches!(counted_run(&scheduler, 8, &runs).await, ScheduledRun::AlreadyRunning));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+}
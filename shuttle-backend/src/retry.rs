@@ -0,0 +1,79 @@
+use rand::Rng;
+use std::time::Duration;
+
+/// Bounded exponential backoff with jitter for outbound HTTP calls.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay to wait after the given (1-based) failed attempt.
+    ///
+    /// A server-provided `Retry-After` takes precedence over the computed
+    /// backoff; both are capped at `max_delay`.
+    pub fn delay_for(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after {
+            return retry_after.min(self.max_delay);
+        }
+
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let backoff = self.base_delay.saturating_mul(factor).min(self.max_delay);
+
+        // Jitter into [backoff/2, backoff] so concurrent clients don't retry in lockstep
+        let half_ms = backoff.as_millis() as u64 / 2;
+        let jitter_ms = rand::thread_rng().gen_range(0..=half_ms);
+        backoff - Duration::from_millis(jitter_ms)
+    }
+}
+
+/// Parse a `Retry-After` header given in delta-seconds form.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    value.trim().parse::<u64>().ok().map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_grows_and_is_capped() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+        };
+
+        let first = policy.delay_for(1, None);
+        assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+
+        let fifth = policy.delay_for(5, None);
+        assert!(fifth >= Duration::from_millis(150) && fifth <= Duration::from_millis(300));
+    }
+
+    #[test]
+    fn test_retry_after_takes_precedence() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.delay_for(1, Some(Duration::from_secs(2))), Duration::from_secs(2));
+        assert_eq!(policy.delay_for(1, Some(Duration::from_secs(60))), policy.max_delay);
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("3"), Some(Duration::from_secs(3)));
+        assert_eq!(parse_retry_after(" 0 "), Some(Duration::from_secs(0)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+    }
+}
@@ -2,12 +2,8 @@ use crate::rag_system::RAGSystem;
 use std::collections::HashMap;
 use tracing::info;
 
-pub async fn populate_sample_data(rag_system: &RAGSystem) -> Result<(), anyhow::Error> {
-    info!("Populating RAG system with ink! smart contract examples...");
-    
-    let sample_documents = vec![
-        (
-            r#"
+/// The ink! ERC-20 example, also used by parser tests
+pub const ERC20_EXAMPLE: &str = r#"
 // ERC20 Token Implementation in ink!
 #[ink::contract]
 mod erc20 {
@@ -79,17 +75,10 @@ mod erc20 {
         }
     }
 }
-"#.to_string(),
-            HashMap::from([
-                ("category".to_string(), "erc20".to_string()),
-                ("topic".to_string(), "ink_smart_contracts".to_string()),
-                ("file_path".to_string(), "ink-examples/erc20/lib.rs".to_string()),
-                ("language".to_string(), "rust".to_string()),
-                ("contract_type".to_string(), "token".to_string()),
-            ])
-        ),
-        (
-            r#"
+"#;
+
+/// The ink! flipper example, also used by parser tests
+pub const FLIPPER_EXAMPLE: &str = r#"
 // Simple Flipper Contract in ink!
 #[ink::contract]
 mod flipper {
@@ -128,17 +117,56 @@ mod flipper {
         }
     }
 }
-"#.to_string(),
-            HashMap::from([
-                ("category".to_string(), "flipper".to_string()),
-                ("topic".to_string(), "ink_smart_contracts".to_string()),
-                ("file_path".to_string(), "ink-examples/flipper/lib.rs".to_string()),
-                ("language".to_string(), "rust".to_string()),
-                ("contract_type".to_string(), "basic".to_string()),
-            ])
-        ),
-        (
-            r#"
+"#;
+
+/// A built-in example and the metadata it's indexed under.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExampleDoc {
+    pub content: String,
+    /// Which example, e.g. `erc20` or `ink_overview`
+    pub category: String,
+    /// `ink_smart_contracts` for code, `ink_documentation` for prose
+    pub topic: String,
+    pub file_path: String,
+    pub language: String,
+    /// Broad kind of example, e.g. `token` or `documentation`
+    pub contract_type: String,
+}
+
+impl ExampleDoc {
+    /// Payload metadata; every example carries the same keys.
+    pub fn metadata(&self) -> HashMap<String, String> {
+        HashMap::from([
+            ("category".to_string(), self.category.clone()),
+            ("topic".to_string(), self.topic.clone()),
+            ("file_path".to_string(), self.file_path.clone()),
+            ("language".to_string(), self.language.clone()),
+            ("contract_type".to_string(), self.contract_type.clone()),
+        ])
+    }
+}
+
+/// The ink! examples and documentation snippets seeded at startup.
+pub fn sample_examples() -> Vec<ExampleDoc> {
+    vec![
+        ExampleDoc {
+            content: ERC20_EXAMPLE.to_string(),
+            category: "erc20".to_string(),
+            topic: "ink_smart_contracts".to_string(),
+            file_path: "ink-examples/erc20/lib.rs".to_string(),
+            language: "rust".to_string(),
+            contract_type: "token".to_string(),
+        },
+        ExampleDoc {
+            content: FLIPPER_EXAMPLE.to_string(),
+            category: "flipper".to_string(),
+            topic: "ink_smart_contracts".to_string(),
+            file_path: "ink-examples/flipper/lib.rs".to_string(),
+            language: "rust".to_string(),
+            contract_type: "basic".to_string(),
+        },
+        ExampleDoc {
+            content: r#"
 // Incrementer Contract in ink!
 #[ink::contract]
 mod incrementer {
@@ -183,16 +211,14 @@ mod incrementer {
     }
 }
 "#.to_string(),
-            HashMap::from([
-                ("category".to_string(), "incrementer".to_string()),
-                ("topic".to_string(), "ink_smart_contracts".to_string()),
-                ("file_path".to_string(), "ink-examples/incrementer/lib.rs".to_string()),
-                ("language".to_string(), "rust".to_string()),
-                ("contract_type".to_string(), "counter".to_string()),
-            ])
-        ),
-        (
-            r#"
+            category: "incrementer".to_string(),
+            topic: "ink_smart_contracts".to_string(),
+            file_path: "ink-examples/incrementer/lib.rs".to_string(),
+            language: "rust".to_string(),
+            contract_type: "counter".to_string(),
+        },
+        ExampleDoc {
+            content: r#"
 // ERC721 NFT Implementation in ink!
 #[ink::contract]
 mod erc721 {
@@ -239,16 +265,14 @@ mod erc721 {
     }
 }
 "#.to_string(),
-            HashMap::from([
-                ("category".to_string(), "erc721".to_string()),
-                ("topic".to_string(), "ink_smart_contracts".to_string()),
-                ("file_path".to_string(), "ink-examples/erc721/lib.rs".to_string()),
-                ("language".to_string(), "rust".to_string()),
-                ("contract_type".to_string(), "nft".to_string()),
-            ])
-        ),
-        (
-            r#"
+            category: "erc721".to_string(),
+            topic: "ink_smart_contracts".to_string(),
+            file_path: "ink-examples/erc721/lib.rs".to_string(),
+            language: "rust".to_string(),
+            contract_type: "nft".to_string(),
+        },
+        ExampleDoc {
+            content: r#"
 // Cross-contract calls in ink!
 #[ink::contract]
 mod cross_contract_calls {
@@ -285,16 +309,14 @@ mod cross_contract_calls {
     }
 }
 "#.to_string(),
-            HashMap::from([
-                ("category".to_string(), "cross_contract_calls".to_string()),
-                ("topic".to_string(), "ink_smart_contracts".to_string()),
-                ("file_path".to_string(), "ink-examples/cross-contract-calls/lib.rs".to_string()),
-                ("language".to_string(), "rust".to_string()),
-                ("contract_type".to_string(), "advanced".to_string()),
-            ])
-        ),
-        (
-            r#"
+            category: "cross_contract_calls".to_string(),
+            topic: "ink_smart_contracts".to_string(),
+            file_path: "ink-examples/cross-contract-calls/lib.rs".to_string(),
+            language: "rust".to_string(),
+            contract_type: "advanced".to_string(),
+        },
+        ExampleDoc {
+            content: r#"
 // Contract events in ink!
 #[ink::contract]
 mod events {
@@ -337,16 +359,14 @@ mod events {
     }
 }
 "#.to_string(),
-            HashMap::from([
-                ("category".to_string(), "events".to_string()),
-                ("topic".to_string(), "ink_smart_contracts".to_string()),
-                ("file_path".to_string(), "ink-examples/events/lib.rs".to_string()),
-                ("language".to_string(), "rust".to_string()),
-                ("contract_type".to_string(), "events".to_string()),
-            ])
-        ),
-        (
-            r#"
+            category: "events".to_string(),
+            topic: "ink_smart_contracts".to_string(),
+            file_path: "ink-examples/events/lib.rs".to_string(),
+            language: "rust".to_string(),
+            contract_type: "events".to_string(),
+        },
+        ExampleDoc {
+            content: r#"
 // Multisig contract in ink!
 #[ink::contract]
 mod multisig {
@@ -406,16 +426,14 @@ mod multisig {
     }
 }
 "#.to_string(),
-            HashMap::from([
-                ("category".to_string(), "multisig".to_string()),
-                ("topic".to_string(), "ink_smart_contracts".to_string()),
-                ("file_path".to_string(), "ink-examples/multisig/lib.rs".to_string()),
-                ("language".to_string(), "rust".to_string()),
-                ("contract_type".to_string(), "security".to_string()),
-            ])
-        ),
-        (
-            r#"
+            category: "multisig".to_string(),
+            topic: "ink_smart_contracts".to_string(),
+            file_path: "ink-examples/multisig/lib.rs".to_string(),
+            language: "rust".to_string(),
+            contract_type: "security".to_string(),
+        },
+        ExampleDoc {
+            content: r#"
 // Basic contract with storage mapping in ink!
 #[ink::contract]
 mod mapping {
@@ -463,16 +481,14 @@ mod mapping {
     }
 }
 "#.to_string(),
-            HashMap::from([
-                ("category".to_string(), "mapping".to_string()),
-                ("topic".to_string(), "ink_smart_contracts".to_string()),
-                ("file_path".to_string(), "ink-examples/mapping/lib.rs".to_string()),
-                ("language".to_string(), "rust".to_string()),
-                ("contract_type".to_string(), "storage".to_string()),
-            ])
-        ),
-        (
-            r#"
+            category: "mapping".to_string(),
+            topic: "ink_smart_contracts".to_string(),
+            file_path: "ink-examples/mapping/lib.rs".to_string(),
+            language: "rust".to_string(),
+            contract_type: "storage".to_string(),
+        },
+        ExampleDoc {
+            content: r#"
 // Contract termination example in ink!
 #[ink::contract]
 mod contract_terminate {
@@ -517,46 +533,45 @@ mod contract_terminate {
     }
 }
 "#.to_string(),
-            HashMap::from([
-                ("category".to_string(), "contract_terminate".to_string()),
-                ("topic".to_string(), "ink_smart_contracts".to_string()),
-                ("file_path".to_string(), "ink-examples/contract-terminate/lib.rs".to_string()),
-                ("language".to_string(), "rust".to_string()),
-                ("contract_type".to_string(), "lifecycle".to_string()),
-            ])
-        ),
-        (
-            "ink! is a Rust-based embedded domain specific language (eDSL) for writing smart contracts for blockchains built on the Substrate framework. It provides a familiar Rust experience with additional contract-specific functionality. Key features include: storage handling with Mapping and Vec types, message and constructor annotations, event emission, cross-contract calls, and built-in testing frameworks.".to_string(),
-            HashMap::from([
-                ("category".to_string(), "ink_overview".to_string()),
-                ("topic".to_string(), "ink_documentation".to_string()),
-                ("language".to_string(), "rust".to_string()),
-                ("contract_type".to_string(), "documentation".to_string()),
-            ])
-        ),
-        (
-            "ink! contracts are compiled to WebAssembly (WASM) bytecode and executed on Substrate-based blockchains. Key concepts include: #[ink::contract] macro for contract definition, #[ink(storage)] for state variables, #[ink(constructor)] for initialization, #[ink(message)] for public functions, #[ink(event)] for event definitions, and cross-contract calls using ContractRef traits.".to_string(),
-            HashMap::from([
-                ("category".to_string(), "ink_concepts".to_string()),
-                ("topic".to_string(), "ink_documentation".to_string()),
-                ("language".to_string(), "rust".to_string()),
-                ("contract_type".to_string(), "documentation".to_string()),
-            ])
-        ),
-        (
-            "Testing ink! contracts can be done using unit tests with #[ink::test] annotation and end-to-end tests with #[ink_e2e::test]. Unit tests run in an off-chain environment simulating contract execution, while e2e tests deploy and interact with contracts on a real blockchain node. Use ink::env::test utilities for setting up test environments, accounts, and balances.".to_string(),
-            HashMap::from([
-                ("category".to_string(), "ink_testing".to_string()),
-                ("topic".to_string(), "ink_documentation".to_string()),
-                ("language".to_string(), "rust".to_string()),
-                ("contract_type".to_string(), "testing".to_string()),
-            ])
-        ),
-    ];
+            category: "contract_terminate".to_string(),
+            topic: "ink_smart_contracts".to_string(),
+            file_path: "ink-examples/contract-terminate/lib.rs".to_string(),
+            language: "rust".to_string(),
+            contract_type: "lifecycle".to_string(),
+        },
+        ExampleDoc {
+            content: "ink! is a Rust-based embedded domain specific language (eDSL) for writing smart contracts for blockchains built on the Substrate framework. It provides a familiar Rust experience with additional contract-specific functionality. Key features include: storage handling with Mapping and Vec types, message and constructor annotations, event emission, cross-contract calls, and built-in testing frameworks.".to_string(),
+            category: "ink_overview".to_string(),
+            topic: "ink_documentation".to_string(),
+            file_path: "ink-docs/overview".to_string(),
+            language: "rust".to_string(),
+            contract_type: "documentation".to_string(),
+        },
+        ExampleDoc {
+            content: "ink! contracts are compiled to WebAssembly (WASM) bytecode and executed on Substrate-based blockchains. Key concepts include: #[ink::contract] macro for contract definition, #[ink(storage)] for state variables, #[ink(constructor)] for initialization, #[ink(message)] for public functions, #[ink(event)] for event definitions, and cross-contract calls using ContractRef traits.".to_string(),
+            category: "ink_concepts".to_string(),
+            topic: "ink_documentation".to_string(),
+            file_path: "ink-docs/concepts".to_string(),
+            language: "rust".to_string(),
+            contract_type: "documentation".to_string(),
+        },
+        ExampleDoc {
+            content: "Testing ink! contracts can be done using unit tests with #[ink::test] annotation and end-to-end tests with #[ink_e2e::test]. Unit tests run in an off-chain environment simulating contract execution, while e2e tests deploy and interact with contracts on a real blockchain node. Use ink::env::test utilities for setting up test environments, accounts, and balances.".to_string(),
+            category: "ink_testing".to_string(),
+            topic: "ink_documentation".to_string(),
+            file_path: "ink-docs/testing".to_string(),
+            language: "rust".to_string(),
+            contract_type: "testing".to_string(),
+        },
+    ]
+}
+
+pub async fn populate_sample_data(rag_system: &RAGSystem) -> Result<(), anyhow::Error> {
+    info!("Populating RAG system with ink! smart contract examples...");
 
     let mut successful_inserts = 0;
-    for (text, metadata) in sample_documents {
-        match rag_system.add_document(&text, metadata).await {
+    for example in sample_examples() {
+        match rag_system.add_document(&example.content, example.metadata()).await {
             Ok(_) => {
                 successful_inserts += 1;
             }
@@ -568,4 +583,32 @@ mod contract_terminate {
 
     info!("Successfully inserted {} ink! smart contract examples into RAG system", successful_inserts);
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_example_has_required_metadata() {
+        let examples = sample_examples();
+        assert_eq!(examples.len(), 12);
+        for example in &examples {
+            let metadata = example.metadata();
+            for key in ["category", "topic", "file_path", "language", "contract_type"] {
+                assert!(
+                    metadata.get(key).is_some_and(|v| !v.trim().is_empty()),
+                    "{} is missing {}",
+                    example.category,
+                    key
+                );
+            }
+            assert!(!example.content.trim().is_empty());
+        }
+
+        let mut paths: Vec<&str> = examples.iter().map(|e| e.file_path.as_str()).collect();
+        paths.sort();
+        paths.dedup();
+        assert_eq!(paths.len(), examples.len());
+    }
+}
@@ -0,0 +1,308 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+
+use crate::chat::{ChatMessage, ChatSession};
+
+/// Boxed future returned by `SessionStore` methods, so the trait stays
+/// usable as `dyn SessionStore`.
+pub type StoreFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// Where chat sessions live between requests.
+pub trait SessionStore: Send + Sync {
+    /// The session's summary and turns, oldest first; empty for an unknown id.
+    fn load<'a>(&'a self, session_id: &'a str) -> StoreFuture<'a, ChatSession>;
+
+    /// Add turns to the end of the session, creating it if needed.
+    fn append<'a>(&'a self, session_id: &'a str, turns: &'a [ChatMessage]) -> StoreFuture<'a, ()>;
+
+    /// Drop all but the newest `keep_turns` turns, replacing the summary when
+    /// one is given.
+    fn compact<'a>(&'a self, session_id: &'a str, keep_turns: usize, summary: Option<&'a str>) -> StoreFuture<'a, ()>;
+
+    /// Ids of every stored session, most recently updated first.
+    #[allow(dead_code)]
+    fn list_sessions(&self) -> StoreFuture<'_, Vec<String>>;
+}
+
+/// Process-local sessions for tests and development; lost on restart.
+#[derive(Debug, Default)]
+pub struct InMemorySessionStore {
+    sessions: Mutex<HashMap<String, (ChatSession, DateTime<Utc>)>>,
+}
+
+impl InMemorySessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SessionStore for InMemorySessionStore {
+    fn load<'a>(&'a self, session_id: &'a str) -> StoreFuture<'a, ChatSession> {
+        let session = self
+            .sessions
+            .lock()
+            .unwrap()
+            .get(session_id)
+            .map(|(session, _)| session.clone())
+            .unwrap_or_default();
+        Box::pin(async move { Ok(session) })
+    }
+
+    fn append<'a>(&'a self, session_id: &'a str, turns: &'a [ChatMessage]) -> StoreFuture<'a, ()> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let (session, updated_at) = sessions.entry(session_id.to_string()).or_default();
+        session.turns.extend_from_slice(turns);
+        *updated_at = Utc::now();
+        Box::pin(async { Ok(()) })
+    }
+
+    fn compact<'a>(&'a self, session_id: &'a str, keep_turns: usize, summary: Option<&'a str>) -> StoreFuture<'a, ()> {
+        if let Some((session, updated_at)) = self.sessions.lock().unwrap().get_mut(session_id) {
+            session.take_overflow(keep_turns);
+            if let Some(summary) = summary {
+                session.summary = Some(summary.to_string());
+            }
+            *updated_at = Utc::now();
+        }
+        Box::pin(async { Ok(()) })
+    }
+
+    fn list_sessions(&self) -> StoreFuture<'_, Vec<String>> {
+        let mut sessions: Vec<(String, DateTime<Utc>)> = self
+            .sessions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, (_, updated_at))| (id.clone(), *updated_at))
+            .collect();
+        sessions.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Box::pin(async move { Ok(sessions.into_iter().map(|(id, _)| id).collect()) })
+    }
+}
+
+#[derive(FromRow)]
+struct StoredTurn {
+    role: String,
+    content: String,
+    created_at: DateTime<Utc>,
+}
+
+/// Sessions in the `chat_sessions` and `chat_messages` tables.
+#[derive(Debug, Clone)]
+pub struct PgSessionStore {
+    db: PgPool,
+}
+
+impl PgSessionStore {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    pub async fn migrate(db: &PgPool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS chat_sessions (
+                session_id TEXT PRIMARY KEY,
+                summary TEXT,
+                updated_at TIMESTAMP WITH TIME ZONE NOT NULL
+            )
+            "#,
+        )
+        .execute(db)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS chat_messages (
+                id BIGSERIAL PRIMARY KEY,
+                session_id TEXT NOT NULL REFERENCES chat_sessions(session_id) ON DELETE CASCADE,
+                role TEXT NOT NULL,
+                content TEXT NOT NULL,
+                created_at TIMESTAMP WITH TIME ZONE NOT NULL
+            )
+            "#,
+        )
+        .execute(db)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_chat_messages_session ON chat_messages(session_id, id)")
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl SessionStore for PgSessionStore {
+    fn load<'a>(&'a self, session_id: &'a str) -> StoreFuture<'a, ChatSession> {
+        Box::pin(async move {
+            let summary: Option<Option<String>> =
+                sqlx::query_scalar("SELECT summary FROM chat_sessions WHERE session_id = $1")
+                    .bind(session_id)
+                    .fetch_optional(&self.db)
+                    .await?;
+            let turns = sqlx::query_as::<_, StoredTurn>(
+                "SELECT role, content, created_at FROM chat_messages WHERE session_id = $1 ORDER BY id ASC",
+            )
+            .bind(session_id)
+            .fetch_all(&self.db)
+            .await?;
+
+            Ok(ChatSession {
+                summary: summary.flatten(),
+                turns: turns
+                    .into_iter()
+                    .map(|turn| ChatMessage {
+                        role: turn.role,
+                        content: turn.content,
+                        timestamp: turn.created_at,
+                    })
+                    .collect(),
+            })
+        })
+    }
+
+    fn append<'a>(&'a self, session_id: &'a str, turns: &'a [ChatMessage]) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            let mut tx = self.db.begin().await?;
+            sqlx::query(
+                r#"
+                INSERT INTO chat_sessions (session_id, updated_at) VALUES ($1, $2)
+                ON CONFLICT (session_id) DO UPDATE SET updated_at = EXCLUDED.updated_at
+                "#,
+            )
+            .bind(session_id)
+            .bind(Utc::now())
+            .execute(&mut *tx)
+            .await?;
+
+            for turn in turns {
+                sqlx::query("INSERT INTO chat_messages (session_id, role, content, created_at) VALUES ($1, $2, $3, $4)")
+                    .bind(session_id)
+                    .bind(&turn.role)
+                    .bind(&turn.content)
+                    .bind(turn.timestamp)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+
+            tx.commit().await?;
+            Ok(())
+        })
+    }
+
+    fn compact<'a>(&'a self, session_id: &'a str, keep_turns: usize, summary: Option<&'a str>) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            let mut tx = self.db.begin().await?;
+            sqlx::query(
+                r#"
+                DELETE FROM chat_messages
+                WHERE session_id = $1 AND id NOT IN (
+                    SELECT id FROM chat_messages WHERE session_id = $1 ORDER BY id DESC LIMIT $2
+                )
+                "#,
+            )
+            .bind(session_id)
+            .bind(keep_turns as i64)
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query(
+                "UPDATE chat_sessions SET summary = COALESCE($2, summary), updated_at = $3 WHERE session_id = $1",
+            )
+            .bind(session_id)
+            .bind(summary)
+            .bind(Utc::now())
+            .execute(&mut *tx)
+            .await?;
+
+            tx.commit().await?;
+            Ok(())
+        })
+    }
+
+    fn list_sessions(&self) -> StoreFuture<'_, Vec<String>> {
+        Box::pin(async move {
+            let ids: Vec<String> = sqlx::query_scalar("SELECT session_id FROM chat_sessions ORDER BY updated_at DESC, session_id ASC")
+                .fetch_all(&self.db)
+                .await?;
+            Ok(ids)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turn(role: &str, content: &str) -> ChatMessage {
+        ChatMessage {
+            role: role.to_string(),
+            content: content.to_string(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// Exercise any store through the trait object.
+    async fn round_trip_multi_turn_session(store: &dyn SessionStore) {
+        let session_id = format!("session-{}", uuid::Uuid::new_v4());
+        assert!(store.load(&session_id).await.unwrap().turns.is_empty());
+
+        store
+            .append(&session_id, &[turn("user", "What is staking?"), turn("assistant", "Locking DOT to earn rewards.")])
+            .await
+            .unwrap();
+        store
+            .append(&session_id, &[turn("user", "What APY?"), turn("assistant", "Around 12%.")])
+            .await
+            .unwrap();
+
+        let session = store.load(&session_id).await.unwrap();
+        let turns: Vec<(&str, &str)> = session.turns.iter().map(|t| (t.role.as_str(), t.content.as_str())).collect();
+        assert_eq!(
+            turns,
+            vec![
+                ("user", "What is staking?"),
+                ("assistant", "Locking DOT to earn rewards."),
+                ("user", "What APY?"),
+                ("assistant", "Around 12%."),
+            ]
+        );
+        assert!(session.summary.is_none());
+
+        store.compact(&session_id, 2, Some("User asked about staking DOT.")).await.unwrap();
+        let session = store.load(&session_id).await.unwrap();
+        assert_eq!(session.summary.as_deref(), Some("User asked about staking DOT."));
+        assert_eq!(session.turns.len(), 2);
+        assert_eq!(session.turns[0].content, "What APY?");
+
+        // Compacting without a new summary keeps the old one
+        store.compact(&session_id, 1, None).await.unwrap();
+        let session = store.load(&session_id).await.unwrap();
+        assert_eq!(session.summary.as_deref(), Some("User asked about staking DOT."));
+        assert_eq!(session.turns.len(), 1);
+
+        assert!(store.list_sessions().await.unwrap().contains(&session_id));
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_round_trips_session() {
+        let store = InMemorySessionStore::new();
+        round_trip_multi_turn_session(&store).await;
+        assert_eq!(store.list_sessions().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a scratch Postgres database at TEST_DATABASE_URL"]
+    async fn test_pg_store_round_trips_session() {
+        let database_url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL");
+        let db = PgPool::connect(&database_url).await.unwrap();
+        PgSessionStore::migrate(&db).await.unwrap();
+        round_trip_multi_turn_session(&PgSessionStore::new(db)).await;
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::OnceCell;
+
+/// Coalesces concurrent calls with the same key: the first caller runs the
+/// computation and everyone who arrives while it's in flight gets a clone
+/// of its result. Nothing is cached once the computation finishes.
+pub struct SingleFlight<T> {
+    in_flight: Mutex<HashMap<String, Arc<OnceCell<T>>>>,
+}
+
+impl<T> Default for SingleFlight<T> {
+    fn default() -> Self {
+        Self {
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<T: Clone> SingleFlight<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `compute` for `key`, or wait for the run already in flight.
+    /// If the running caller is cancelled, a waiting one takes over.
+    pub async fn run<F, Fut>(&self, key: &str, compute: F) -> T
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let cell = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(key.to_string())
+            .or_insert_with(|| Arc::new(OnceCell::new()))
+            .clone();
+
+        let value = cell.get_or_init(compute).await.clone();
+
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if in_flight.get(key).is_some_and(|current| Arc::ptr_eq(current, &cell)) {
+            in_flight.remove(key);
+        }
+        value
+    }
+}
+
+/// Sentence punctuation dropped from the end of a query
+const TRAILING_PUNCTUATION: &[char] = &['?', '!', '.', ',', ';', ':'];
+
+/// Canonical form of a free-text query, used as the coalescing key and
+/// embedded in place of the query: case, runs of whitespace and trailing
+/// sentence punctuation don't change the answer.
+pub fn normalize_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches(TRAILING_PUNCTUATION)
+        .trim_end()
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_concurrent_identical_queries_call_llm_once() {
+        let flight = Arc::new(SingleFlight::<Result<String, String>>::new());
+        let llm_calls = Arc::new(AtomicUsize::new(0));
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for i in 0..10 {
+            let flight = flight.clone();
+            let llm_calls = llm_calls.clone();
+            // Same question, typed differently
+            let query = if i % 2 == 0 { "How do I store a mapping?" } else { "  how do I store a   MAPPING? " };
+            tasks.spawn(async move {
+                flight
+                    .run(&normalize_query(query), || async {
+                        llm_calls.fetch_add(1, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        Ok("Use ink::storage::Mapping".to_string())
+                    })
+                    .await
+            });
+        }
+
+        let mut answers = Vec::new();
+        while let Some(answer) = tasks.join_next().await {
+            answers.push(answer.unwrap());
+        }
+        assert_eq!(answers.len(), 10);
+        assert!(answers.iter().all(|a| a.as_deref() == Ok("Use ink::storage::Mapping")));
+        assert_eq!(llm_calls.load(Ordering::SeqCst), 1);
+        assert!(flight.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_cosmetic_differences_normalize_away() {
+        assert_eq!(normalize_query("What is ERC20?"), "what is erc20");
+        assert_eq!(normalize_query("  what   is erc20 "), "what is erc20");
+        assert_eq!(normalize_query("What is ERC20 ?!"), "what is erc20");
+        // Punctuation inside the query is kept
+        assert_eq!(normalize_query("How does msg.sender map?"), "how does msg.sender map");
+    }
+
+    #[tokio::test]
+    async fn test_finished_queries_are_not_cached() {
+        let flight = SingleFlight::<usize>::new();
+        let calls = AtomicUsize::new(0);
+
+        for _ in 0..2 {
+            flight.run("flipper", || async { calls.fetch_add(1, Ordering::SeqCst) }).await;
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -11,6 +11,8 @@ use shuttle_axum::axum::{
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, PgPool, postgres::PgPoolOptions};
 use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::timeout::TimeoutLayer;
 use tower_http::limit::RequestBodyLimitLayer;
@@ -19,7 +21,7 @@ use uuid::Uuid;
 use std::env;
 
 // Database models (reuse from main.rs)
-#[derive(Debug, FromRow, Serialize, Deserialize)]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 struct Strategy {
     pub id: Uuid,
     pub account_id: String,
@@ -63,11 +65,83 @@ struct ApiResponse<T> {
 }
 
 // Application state
-#[derive(Clone)]
-struct AppState {
+struct AppState<S> {
+    strategies: Arc<S>,
+}
+
+impl<S> Clone for AppState<S> {
+    fn clone(&self) -> Self {
+        Self {
+            strategies: self.strategies.clone(),
+        }
+    }
+}
+
+/// Where strategies are saved.
+trait StrategyStore: Send + Sync + 'static {
+    fn create(&self, account_id: &str, strategy_data: &StrategyData) -> impl Future<Output = anyhow::Result<Strategy>> + Send;
+
+    /// The account's active strategies, newest first.
+    fn list(&self, account_id: &str) -> impl Future<Output = anyhow::Result<Vec<Strategy>>> + Send;
+}
+
+/// Strategies in the `strategies` table.
+struct PgStrategyStore {
     db: PgPool,
 }
 
+impl StrategyStore for PgStrategyStore {
+    async fn create(&self, account_id: &str, strategy_data: &StrategyData) -> anyhow::Result<Strategy> {
+        Ok(create_strategy_in_db(&self.db, account_id, strategy_data).await?)
+    }
+
+    async fn list(&self, account_id: &str) -> anyhow::Result<Vec<Strategy>> {
+        Ok(get_strategies_from_db(&self.db, account_id).await?)
+    }
+}
+
+/// Process-local strategies for demos and tests; lost on restart.
+#[derive(Default)]
+struct InMemoryStrategyStore {
+    strategies: Mutex<HashMap<String, Vec<Strategy>>>,
+}
+
+impl StrategyStore for InMemoryStrategyStore {
+    async fn create(&self, account_id: &str, strategy_data: &StrategyData) -> anyhow::Result<Strategy> {
+        let now = chrono::Utc::now();
+        let strategy = Strategy {
+            id: Uuid::new_v4(),
+            account_id: account_id.to_string(),
+            name: strategy_data.name.clone(),
+            risk_level: strategy_data.risk_level,
+            parameters: strategy_data.parameters.clone(),
+            contract_strategy_id: None,
+            created_at: now,
+            updated_at: now,
+            is_active: true,
+        };
+        self.strategies
+            .lock()
+            .unwrap()
+            .entry(account_id.to_string())
+            .or_default()
+            .push(strategy.clone());
+        Ok(strategy)
+    }
+
+    async fn list(&self, account_id: &str) -> anyhow::Result<Vec<Strategy>> {
+        // Stored in insertion order, so reversing puts the newest first
+        let strategies = self
+            .strategies
+            .lock()
+            .unwrap()
+            .get(account_id)
+            .map(|strategies| strategies.iter().rev().filter(|s| s.is_active).cloned().collect())
+            .unwrap_or_default();
+        Ok(strategies)
+    }
+}
+
 // Database functions
 async fn create_strategy_in_db(
     db: &PgPool,
@@ -123,8 +197,8 @@ async fn health_check() -> Json<ApiResponse<String>> {
     })
 }
 
-async fn save_strategy(
-    State(state): State<AppState>,
+async fn save_strategy<S: StrategyStore>(
+    State(state): State<AppState<S>>,
     Json(request): Json<CreateStrategyRequest>,
 ) -> Result<Json<ApiResponse<StrategyResponse>>, StatusCode> {
     info!("Saving strategy for account: {}", request.account);
@@ -146,8 +220,8 @@ async fn save_strategy(
         }));
     }
 
-    // Save to database
-    match create_strategy_in_db(&state.db, &request.account, &request.strategy).await {
+    // Save to the configured store
+    match state.strategies.create(&request.account, &request.strategy).await {
         Ok(strategy) => {
             let response = StrategyResponse {
                 name: strategy.name,
@@ -164,20 +238,20 @@ async fn save_strategy(
             }))
         }
         Err(e) => {
-            info!("Database save failed: {}", e);
+            info!("Saving strategy failed: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
 }
 
-async fn get_strategies(
-    State(state): State<AppState>,
+async fn get_strategies<S: StrategyStore>(
+    State(state): State<AppState<S>>,
     Path(account_id): Path<String>,
 ) -> Result<Json<ApiResponse<Vec<StrategyResponse>>>, StatusCode> {
     info!("Getting strategies for account: {}", account_id);
 
-    // Get strategies from database
-    match get_strategies_from_db(&state.db, &account_id).await {
+    // Get strategies from the configured store
+    match state.strategies.list(&account_id).await {
         Ok(strategies) => {
             let response: Vec<StrategyResponse> = strategies
                 .into_iter()
@@ -197,7 +271,7 @@ async fn get_strategies(
             }))
         }
         Err(e) => {
-            info!("Database query failed: {}", e);
+            info!("Listing strategies failed: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
@@ -246,35 +320,16 @@ async fn run_migrations(db: &PgPool) -> Result<(), sqlx::Error> {
     Ok(())
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize tracing
-    tracing_subscriber::fmt::init();
-
-    // Get database URL from environment
-    let database_url = env::var("DATABASE_URL")
-        .unwrap_or_else(|_| "postgresql://localhost/dynavest".to_string());
-
-    // Create database pool
-    let pool = PgPoolOptions::new()
-        .max_connections(10)
-        .connect(&database_url)
-        .await?;
-
-    // Run migrations
-    if let Err(e) = run_migrations(&pool).await {
-        panic!("Failed to run migrations: {}", e);
-    }
-
-    // Create application state
-    let state = AppState { db: pool };
+fn router<S: StrategyStore>(strategies: S) -> Router {
+    let state = AppState {
+        strategies: Arc::new(strategies),
+    };
 
-    // Build router
-    let app = Router::new()
+    Router::new()
         .route("/", get(health_check))
         .route("/health", get(health_check))
-        .route("/strategies", post(save_strategy))
-        .route("/strategies/:account", get(get_strategies))
+        .route("/strategies", post(save_strategy::<S>))
+        .route("/strategies/:account", get(get_strategies::<S>))
         .route("/statistics", get(get_statistics))
         .layer(
             CorsLayer::new()
@@ -284,7 +339,33 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         )
         .layer(TimeoutLayer::new(std::time::Duration::from_secs(30)))
         .layer(RequestBodyLimitLayer::new(1024 * 1024)) // 1MB request limit
-        .with_state(state);
+        .with_state(state)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Initialize tracing
+    tracing_subscriber::fmt::init();
+
+    // Use Postgres when DATABASE_URL is set; otherwise keep strategies in
+    // memory so a demo needs nothing else running
+    let app = match env::var("DATABASE_URL") {
+        Ok(database_url) => {
+            let pool = PgPoolOptions::new()
+                .max_connections(10)
+                .connect(&database_url)
+                .await?;
+
+            if let Err(e) = run_migrations(&pool).await {
+                panic!("Failed to run migrations: {}", e);
+            }
+            router(PgStrategyStore { db: pool })
+        }
+        Err(_) => {
+            info!("DATABASE_URL is not set; strategies are kept in memory and lost on restart");
+            router(InMemoryStrategyStore::default())
+        }
+    };
 
     let port = env::var("PORT")
         .unwrap_or_else(|_| "8000".to_string())
@@ -303,4 +384,66 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     shuttle_axum::axum::serve(listener, app).await?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn in_memory_state() -> AppState<InMemoryStrategyStore> {
+        AppState {
+            strategies: Arc::new(InMemoryStrategyStore::default()),
+        }
+    }
+
+    fn create_request(account: &str, name: &str) -> CreateStrategyRequest {
+        CreateStrategyRequest {
+            account: account.to_string(),
+            strategy: StrategyData {
+                name: name.to_string(),
+                risk_level: 5,
+                parameters: "{}".to_string(),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_and_list_without_database() {
+        let state = in_memory_state();
+        for name in ["First", "Second"] {
+            let Json(response) = save_strategy(State(state.clone()), Json(create_request("alice", name))).await.unwrap();
+            assert!(response.success);
+        }
+        let Json(response) = save_strategy(State(state.clone()), Json(create_request("bob", "Other"))).await.unwrap();
+        assert!(response.success);
+
+        let Json(response) = get_strategies(State(state.clone()), Path("alice".to_string())).await.unwrap();
+        let names: Vec<String> = response.data.unwrap().into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["Second", "First"]);
+
+        let Json(response) = get_strategies(State(state), Path("carol".to_string())).await.unwrap();
+        assert!(response.data.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_creates_are_all_kept() {
+        let state = in_memory_state();
+        let tasks: Vec<_> = (0..32)
+            .map(|i| {
+                let state = state.clone();
+                tokio::spawn(async move {
+                    let Json(response) = save_strategy(State(state), Json(create_request("alice", &format!("Strategy {}", i))))
+                        .await
+                        .unwrap();
+                    assert!(response.success);
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let strategies = state.strategies.list("alice").await.unwrap();
+        assert_eq!(strategies.len(), 32);
+    }
+}
@@ -0,0 +1,185 @@
+use qdrant_client::qdrant::{Condition, Filter};
+use shuttle_axum::axum::{
+    extract::{FromRef, FromRequestParts},
+    http::{header, request::Parts, StatusCode},
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Payload field holding the owning tenant of a knowledge base point
+pub const TENANT_FIELD: &str = "tenant_id";
+
+/// API keys and the tenant each one authenticates, read from
+/// `TENANT_API_KEYS` as `key=tenant` pairs separated by commas.
+/// With no keys configured the deployment is single-tenant.
+#[derive(Debug, Clone, Default)]
+pub struct TenantKeys {
+    keys: Arc<HashMap<String, String>>,
+}
+
+impl TenantKeys {
+    pub fn from_env() -> Self {
+        std::env::var("TENANT_API_KEYS")
+            .map(|spec| Self::parse(&spec))
+            .unwrap_or_default()
+    }
+
+    pub fn parse(spec: &str) -> Self {
+        let keys = spec
+            .split(',')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(key, tenant)| (key.trim().to_string(), tenant.trim().to_string()))
+            .filter(|(key, tenant)| !key.is_empty() && !tenant.is_empty())
+            .collect();
+        Self { keys: Arc::new(keys) }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    pub fn tenant_for(&self, api_key: &str) -> Option<&str> {
+        self.keys.get(api_key).map(String::as_str)
+    }
+}
+
+/// The caller's tenant, taken from the `Authorization: Bearer <key>` header.
+/// `None` when multi-tenancy is off; requests with a missing or unknown key
+/// are rejected with 401 when it's on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TenantClaim(pub Option<String>);
+
+impl TenantClaim {
+    pub fn tenant(&self) -> Option<&str> {
+        self.0.as_deref()
+    }
+}
+
+impl<S> FromRequestParts<S> for TenantClaim
+where
+    TenantKeys: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let keys = TenantKeys::from_ref(state);
+        if !keys.is_enabled() {
+            return Ok(TenantClaim(None));
+        }
+
+        bearer_key(parts)
+            .and_then(|api_key| keys.tenant_for(api_key))
+            .map(|tenant| TenantClaim(Some(tenant.to_string())))
+            .ok_or(StatusCode::UNAUTHORIZED)
+    }
+}
+
+/// The key for the `/admin` routes, read from `ADMIN_API_KEY`. Admin data
+/// spans every tenant, so without a key those routes refuse all requests.
+#[derive(Debug, Clone, Default)]
+pub struct AdminKey(Option<Arc<str>>);
+
+impl AdminKey {
+    pub fn from_env() -> Self {
+        Self::new(&std::env::var("ADMIN_API_KEY").unwrap_or_default())
+    }
+
+    pub fn new(key: &str) -> Self {
+        let key = key.trim();
+        Self((!key.is_empty()).then(|| Arc::from(key)))
+    }
+}
+
+/// A request authenticated with the admin key as `Authorization: Bearer
+/// <key>`; anything else is rejected with 401.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdminClaim;
+
+impl<S> FromRequestParts<S> for AdminClaim
+where
+    AdminKey: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let AdminKey(key) = AdminKey::from_ref(state);
+        match (key, bearer_key(parts)) {
+            (Some(key), Some(api_key)) if *key == *api_key => Ok(AdminClaim),
+            _ => Err(StatusCode::UNAUTHORIZED),
+        }
+    }
+}
+
+fn bearer_key(parts: &Parts) -> Option<&str> {
+    parts
+        .headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+/// Points a tenant may retrieve: its own plus shared ones with no tenant,
+/// such as the bundled ink! examples.
+pub fn visible_to(tenant: &str) -> Filter {
+    Filter::should([
+        Condition::matches(TENANT_FIELD, tenant.to_string()),
+        Condition::is_empty(TENANT_FIELD),
+    ])
+}
+
+/// Points owned by `tenant`.
+pub fn owned_by(tenant: &str) -> Filter {
+    Filter::must([Condition::matches(TENANT_FIELD, tenant.to_string())])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shuttle_axum::axum::http::Request;
+
+    async fn claim(keys: &TenantKeys, authorization: Option<&str>) -> Result<TenantClaim, StatusCode> {
+        let mut request = Request::builder();
+        if let Some(value) = authorization {
+            request = request.header(header::AUTHORIZATION, value);
+        }
+        let (mut parts, _) = request.body(()).unwrap().into_parts();
+        TenantClaim::from_request_parts(&mut parts, keys).await
+    }
+
+    #[tokio::test]
+    async fn test_claim_resolves_tenant_from_api_key() {
+        let keys = TenantKeys::parse("key-a=team-a, key-b=team-b,malformed");
+        assert_eq!(claim(&keys, Some("Bearer key-a")).await, Ok(TenantClaim(Some("team-a".to_string()))));
+        assert_eq!(claim(&keys, Some("Bearer key-b")).await, Ok(TenantClaim(Some("team-b".to_string()))));
+        assert_eq!(claim(&keys, Some("Bearer key-c")).await, Err(StatusCode::UNAUTHORIZED));
+        assert_eq!(claim(&keys, None).await, Err(StatusCode::UNAUTHORIZED));
+    }
+
+    #[tokio::test]
+    async fn test_admin_claim_needs_the_admin_key() {
+        let admin = |key: AdminKey, authorization: Option<&'static str>| async move {
+            let mut request = Request::builder();
+            if let Some(value) = authorization {
+                request = request.header(header::AUTHORIZATION, value);
+            }
+            let (mut parts, _) = request.body(()).unwrap().into_parts();
+            AdminClaim::from_request_parts(&mut parts, &key).await
+        };
+
+        assert_eq!(admin(AdminKey::new("root-key"), Some("Bearer root-key")).await, Ok(AdminClaim));
+        assert_eq!(admin(AdminKey::new("root-key"), Some("Bearer key-a")).await, Err(StatusCode::UNAUTHORIZED));
+        assert_eq!(admin(AdminKey::new("root-key"), None).await, Err(StatusCode::UNAUTHORIZED));
+        // With no key configured nobody is an admin
+        assert_eq!(admin(AdminKey::new(""), Some("Bearer ")).await, Err(StatusCode::UNAUTHORIZED));
+    }
+
+    #[tokio::test]
+    async fn test_single_tenant_mode_has_no_claim() {
+        let keys = TenantKeys::parse("");
+        assert!(!keys.is_enabled());
+        assert_eq!(claim(&keys, None).await, Ok(TenantClaim(None)));
+    }
+}
@@ -0,0 +1,86 @@
+//! Shared request validators. Each returns the `ApiError` handlers put in
+//! their error response, so the same input is rejected with the same code
+//! and message on every endpoint.
+
+use crate::address::{validate_address, AddressChain};
+use crate::{ApiError, ErrorCode};
+
+pub const MIN_RISK_LEVEL: i32 = 1;
+pub const MAX_RISK_LEVEL: i32 = 10;
+
+fn invalid(param: &str, message: String) -> ApiError {
+    ApiError {
+        error_type: "invalid_request_error".to_string(),
+        code: ErrorCode::ParameterInvalid,
+        message,
+        param: Some(param.to_string()),
+    }
+}
+
+/// Risk levels run from 1 (most conservative) to 10.
+pub fn risk_level(value: i32) -> Result<(), ApiError> {
+    if (MIN_RISK_LEVEL..=MAX_RISK_LEVEL).contains(&value) {
+        Ok(())
+    } else {
+        Err(invalid(
+            "risk_level",
+            format!("Risk level must be between {} and {}", MIN_RISK_LEVEL, MAX_RISK_LEVEL),
+        ))
+    }
+}
+
+/// Amounts must be finite and strictly positive; `param` names the field in the error.
+pub fn positive_amount(value: f64, param: &str) -> Result<(), ApiError> {
+    if value.is_finite() && value > 0.0 {
+        Ok(())
+    } else {
+        Err(invalid(param, format!("{} must be greater than 0", param)))
+    }
+}
+
+/// Account address in `chain`'s format; `param` names the field in the error.
+pub fn address(value: &str, chain: AddressChain, param: &str) -> Result<(), ApiError> {
+    validate_address(value, chain).map_err(|reason| invalid(param, format!("Invalid {}: {}", param, reason)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_risk_level_bounds() {
+        assert!(risk_level(0).is_err());
+        assert!(risk_level(1).is_ok());
+        assert!(risk_level(10).is_ok());
+        assert!(risk_level(-3).is_err());
+
+        let error = risk_level(11).unwrap_err();
+        assert_eq!(error.code, ErrorCode::ParameterInvalid);
+        assert_eq!(error.param.as_deref(), Some("risk_level"));
+        assert_eq!(error.message, "Risk level must be between 1 and 10");
+    }
+
+    #[test]
+    fn test_positive_amount_bounds() {
+        assert!(positive_amount(0.01, "investment_amount").is_ok());
+        assert!(positive_amount(1e12, "amount").is_ok());
+        assert!(positive_amount(-1.0, "amount").is_err());
+        assert!(positive_amount(f64::NAN, "amount").is_err());
+        assert!(positive_amount(f64::INFINITY, "amount").is_err());
+
+        let error = positive_amount(0.0, "investment_amount").unwrap_err();
+        assert_eq!(error.code, ErrorCode::ParameterInvalid);
+        assert_eq!(error.param.as_deref(), Some("investment_amount"));
+        assert_eq!(error.message, "investment_amount must be greater than 0");
+    }
+
+    #[test]
+    fn test_address_error_names_the_field() {
+        assert!(address("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY", AddressChain::Polkadot, "user_address").is_ok());
+
+        let error = address("not-an-address", AddressChain::Polkadot, "user_address").unwrap_err();
+        assert_eq!(error.code, ErrorCode::ParameterInvalid);
+        assert_eq!(error.param.as_deref(), Some("user_address"));
+        assert!(error.message.starts_with("Invalid user_address: "));
+    }
+}